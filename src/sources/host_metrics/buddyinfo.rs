@@ -0,0 +1,96 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+
+impl HostMetricsConfig {
+    pub async fn buddyinfo_metrics(&self) -> Vec<Metric> {
+        let buddyinfo = match std::fs::read_to_string(self.procfs_root().join("buddyinfo")) {
+            Ok(buddyinfo) => buddyinfo,
+            Err(error) => {
+                error!(message = "Failed to load buddy allocator info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for zone in parse_buddyinfo(&buddyinfo) {
+            if !self.buddyinfo.nodes.contains_str(Some(&zone.node))
+                || !self.buddyinfo.zones.contains_str(Some(&zone.zone))
+            {
+                continue;
+            }
+            for (order, &blocks) in zone.free_blocks.iter().enumerate() {
+                metrics.push(self.gauge(
+                    "memory_free_blocks",
+                    timestamp,
+                    blocks as f64,
+                    btreemap! {
+                        "node" => zone.node.clone(),
+                        "zone" => zone.zone.clone(),
+                        "order" => order.to_string(),
+                    },
+                ));
+            }
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ZoneFreeBlocks {
+    node: String,
+    zone: String,
+    /// The number of free blocks of `2^order` pages, indexed by order.
+    free_blocks: Vec<u64>,
+}
+
+/// Parses the `Node <node>, zone <zone> <free blocks by order>...` lines of
+/// `/proc/buddyinfo`.
+fn parse_buddyinfo(buddyinfo: &str) -> Vec<ZoneFreeBlocks> {
+    buddyinfo
+        .lines()
+        .filter_map(|line| {
+            let (node, rest) = line.strip_prefix("Node ")?.split_once(',')?;
+            let mut fields = rest.split_whitespace();
+            if fields.next()? != "zone" {
+                return None;
+            }
+            let zone = fields.next()?.to_string();
+            let free_blocks = fields
+                .map(|blocks| blocks.parse().ok())
+                .collect::<Option<Vec<_>>>()?;
+            Some(ZoneFreeBlocks {
+                node: node.trim().to_string(),
+                zone,
+                free_blocks,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_buddyinfo() {
+        let zones = parse_buddyinfo(
+            "Node 0, zone      DMA      1      1      1      0      2      1      1      0      1      1      3
+Node 0, zone    DMA32      3      5      4      4      4      4      4      3      4      4    421
+Node 1, zone   Normal   2371   1583    933    393    172     69     26     10      5      2   3197
+",
+        );
+        assert_eq!(zones.len(), 3);
+        assert_eq!(
+            zones[2],
+            ZoneFreeBlocks {
+                node: "1".into(),
+                zone: "Normal".into(),
+                free_blocks: vec![2371, 1583, 933, 393, 172, 69, 26, 10, 5, 2, 3197],
+            }
+        );
+        assert_eq!(zones[0].zone, "DMA");
+        assert_eq!(zones[1].free_blocks[10], 421);
+    }
+}
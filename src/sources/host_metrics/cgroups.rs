@@ -0,0 +1,409 @@
+use super::{read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+impl HostMetricsConfig {
+    pub async fn cgroup_metrics(&self) -> Vec<Metric> {
+        let root = self.cgroup_root();
+        let hierarchy = match Hierarchy::detect(&root) {
+            Some(hierarchy) => hierarchy,
+            None => return Vec::new(),
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+
+        let memory = read_memory(&root, hierarchy);
+        if let Some(usage) = memory.usage {
+            metrics.push(self.gauge(
+                "cgroup_memory_usage_bytes",
+                timestamp,
+                usage as f64,
+                btreemap! {},
+            ));
+        }
+        if let Some(limit) = memory.limit {
+            metrics.push(self.gauge(
+                "cgroup_memory_limit_bytes",
+                timestamp,
+                limit as f64,
+                btreemap! {},
+            ));
+        }
+
+        if let Some(cpu) = read_cpu(&root, hierarchy) {
+            metrics.push(self.counter(
+                "cgroup_cpu_periods_total",
+                timestamp,
+                cpu.periods as f64,
+                btreemap! {},
+            ));
+            metrics.push(self.counter(
+                "cgroup_cpu_throttled_periods_total",
+                timestamp,
+                cpu.throttled_periods as f64,
+                btreemap! {},
+            ));
+            metrics.push(self.counter(
+                "cgroup_cpu_throttled_seconds_total",
+                timestamp,
+                cpu.throttled_seconds,
+                btreemap! {},
+            ));
+        }
+
+        let sysfs = self.sysfs_root();
+        for io in read_io(&root, hierarchy) {
+            let tags = btreemap! { "device" => device_name(&sysfs, &io.device) };
+            for &(name, value) in &[
+                ("cgroup_io_read_bytes_total", io.read_bytes),
+                ("cgroup_io_write_bytes_total", io.write_bytes),
+                ("cgroup_io_read_ops_total", io.read_ops),
+                ("cgroup_io_write_ops_total", io.write_ops),
+            ] {
+                metrics.push(self.counter(name, timestamp, value as f64, tags.clone()));
+            }
+        }
+
+        metrics
+    }
+
+    fn cgroup_root(&self) -> PathBuf {
+        self.cgroups
+            .base_dir
+            .clone()
+            .unwrap_or_else(|| self.sysfs_root().join("fs/cgroup"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Hierarchy {
+    /// Legacy hierarchy, with one directory per controller.
+    V1,
+    /// Unified hierarchy.
+    V2,
+}
+
+impl Hierarchy {
+    fn detect(root: &Path) -> Option<Self> {
+        if root.join("cgroup.controllers").is_file() {
+            Some(Hierarchy::V2)
+        } else if root.is_dir() {
+            Some(Hierarchy::V1)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct MemoryStats {
+    usage: Option<u64>,
+    /// Unset when the cgroup has no memory limit.
+    limit: Option<u64>,
+}
+
+fn read_memory(root: &Path, hierarchy: Hierarchy) -> MemoryStats {
+    match hierarchy {
+        Hierarchy::V1 => MemoryStats {
+            usage: read_u64(&root.join("memory/memory.usage_in_bytes")),
+            limit: read_u64(&root.join("memory/memory.limit_in_bytes")),
+        },
+        // An unlimited `memory.max` contains "max", which fails to parse.
+        Hierarchy::V2 => MemoryStats {
+            usage: read_u64(&root.join("memory.current")),
+            limit: read_u64(&root.join("memory.max")),
+        },
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CpuStats {
+    periods: u64,
+    throttled_periods: u64,
+    throttled_seconds: f64,
+}
+
+fn read_cpu(root: &Path, hierarchy: Hierarchy) -> Option<CpuStats> {
+    let path = match hierarchy {
+        Hierarchy::V1 => root.join("cpu/cpu.stat"),
+        Hierarchy::V2 => root.join("cpu.stat"),
+    };
+    parse_cpu_stat(&std::fs::read_to_string(path).ok()?, hierarchy)
+}
+
+/// Parses the bandwidth statistics out of `cpu.stat`. These are missing
+/// on the unified hierarchy when the `cpu` controller isn't enabled.
+fn parse_cpu_stat(contents: &str, hierarchy: Hierarchy) -> Option<CpuStats> {
+    let stats = contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key, value.trim().parse::<u64>().ok()?))
+        })
+        .collect::<HashMap<_, _>>();
+    let throttled_seconds = match hierarchy {
+        Hierarchy::V1 => *stats.get("throttled_time")? as f64 / 1_000_000_000.0,
+        Hierarchy::V2 => *stats.get("throttled_usec")? as f64 / 1_000_000.0,
+    };
+    Some(CpuStats {
+        periods: *stats.get("nr_periods")?,
+        throttled_periods: *stats.get("nr_throttled")?,
+        throttled_seconds,
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct IoStats {
+    /// The `major:minor` number of the block device.
+    device: String,
+    read_bytes: u64,
+    write_bytes: u64,
+    read_ops: u64,
+    write_ops: u64,
+}
+
+fn read_io(root: &Path, hierarchy: Hierarchy) -> Vec<IoStats> {
+    let read = |path: &str| fs::read_to_string(root.join(path)).unwrap_or_default();
+    match hierarchy {
+        Hierarchy::V1 => parse_blkio(
+            &read("blkio/blkio.throttle.io_service_bytes"),
+            &read("blkio/blkio.throttle.io_serviced"),
+        ),
+        Hierarchy::V2 => parse_io_stat(&read("io.stat")),
+    }
+}
+
+/// Parses the `<major>:<minor> rbytes=<n> wbytes=<n> rios=<n> wios=<n> ...`
+/// lines of `io.stat`.
+fn parse_io_stat(contents: &str) -> Vec<IoStats> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut io = IoStats {
+                device: fields.next()?.to_string(),
+                ..Default::default()
+            };
+            for (key, value) in fields.filter_map(|field| field.split_once('=')) {
+                let value = match value.parse() {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                match key {
+                    "rbytes" => io.read_bytes = value,
+                    "wbytes" => io.write_bytes = value,
+                    "rios" => io.read_ops = value,
+                    "wios" => io.write_ops = value,
+                    _ => {}
+                }
+            }
+            Some(io)
+        })
+        .collect()
+}
+
+/// Parses the `<major>:<minor> <operation> <n>` lines of the
+/// `blkio.throttle.io_service_bytes` and `blkio.throttle.io_serviced`
+/// files of the legacy hierarchy, which count all IO despite their name.
+fn parse_blkio(bytes: &str, ops: &str) -> Vec<IoStats> {
+    let mut devices = BTreeMap::<&str, IoStats>::new();
+    for (contents, is_bytes) in &[(bytes, true), (ops, false)] {
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let (device, operation, value) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(device), Some(operation), Some(value)) => (device, operation, value),
+                // The trailing `Total <n>` line
+                _ => continue,
+            };
+            let value = match value.parse() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let io = devices.entry(device).or_insert_with(|| IoStats {
+                device: device.to_string(),
+                ..Default::default()
+            });
+            match (operation, is_bytes) {
+                ("Read", true) => io.read_bytes = value,
+                ("Write", true) => io.write_bytes = value,
+                ("Read", false) => io.read_ops = value,
+                ("Write", false) => io.write_ops = value,
+                _ => {}
+            }
+        }
+    }
+    devices.into_iter().map(|(_, io)| io).collect()
+}
+
+/// Resolves a `major:minor` device number to the kernel name of the block
+/// device, such as `sda`, keeping the number if the device is unknown.
+fn device_name(sysfs: &Path, device: &str) -> String {
+    fs::read_link(sysfs.join("dev/block").join(device))
+        .ok()
+        .and_then(|target| Some(target.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| device.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reads_v1_memory() {
+        let root = tempfile::tempdir().unwrap();
+        let memory = root.path().join("memory");
+        fs::create_dir(&memory).unwrap();
+        fs::write(memory.join("memory.usage_in_bytes"), "104857600\n").unwrap();
+        fs::write(memory.join("memory.limit_in_bytes"), "536870912\n").unwrap();
+
+        assert_eq!(Hierarchy::detect(root.path()), Some(Hierarchy::V1));
+        assert_eq!(
+            read_memory(root.path(), Hierarchy::V1),
+            MemoryStats {
+                usage: Some(104857600),
+                limit: Some(536870912),
+            }
+        );
+    }
+
+    #[test]
+    fn reads_v2_memory() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("cgroup.controllers"), "cpu io memory\n").unwrap();
+        fs::write(root.path().join("memory.current"), "104857600\n").unwrap();
+        fs::write(root.path().join("memory.max"), "max\n").unwrap();
+
+        assert_eq!(Hierarchy::detect(root.path()), Some(Hierarchy::V2));
+        assert_eq!(
+            read_memory(root.path(), Hierarchy::V2),
+            MemoryStats {
+                usage: Some(104857600),
+                limit: None,
+            }
+        );
+
+        fs::write(root.path().join("memory.max"), "536870912\n").unwrap();
+        assert_eq!(
+            read_memory(root.path(), Hierarchy::V2).limit,
+            Some(536870912)
+        );
+    }
+
+    #[test]
+    fn parses_v1_cpu_stat() {
+        let stats = parse_cpu_stat(
+            "nr_periods 1200\nnr_throttled 30\nthrottled_time 1500000000\n",
+            Hierarchy::V1,
+        );
+        assert_eq!(
+            stats,
+            Some(CpuStats {
+                periods: 1200,
+                throttled_periods: 30,
+                throttled_seconds: 1.5,
+            })
+        );
+    }
+
+    #[test]
+    fn parses_v2_cpu_stat() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("cgroup.controllers"), "cpu io memory\n").unwrap();
+        fs::write(
+            root.path().join("cpu.stat"),
+            "usage_usec 8236233\nuser_usec 6038474\nsystem_usec 2197759\nnr_periods 1200\nnr_throttled 30\nthrottled_usec 2500000\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_cpu(root.path(), Hierarchy::V2),
+            Some(CpuStats {
+                periods: 1200,
+                throttled_periods: 30,
+                throttled_seconds: 2.5,
+            })
+        );
+    }
+
+    #[test]
+    fn skips_cpu_stat_without_bandwidth() {
+        let stats = parse_cpu_stat(
+            "usage_usec 8236233\nuser_usec 6038474\nsystem_usec 2197759\n",
+            Hierarchy::V2,
+        );
+        assert_eq!(stats, None);
+    }
+
+    #[test]
+    fn reads_v1_io() {
+        let root = tempfile::tempdir().unwrap();
+        let blkio = root.path().join("blkio");
+        fs::create_dir(&blkio).unwrap();
+        fs::write(
+            blkio.join("blkio.throttle.io_service_bytes"),
+            "8:0 Read 4096000\n8:0 Write 1024000\n8:0 Sync 5000000\n8:0 Async 120000\n8:0 Discard 0\n8:0 Total 5120000\nTotal 5120000\n",
+        )
+        .unwrap();
+        fs::write(
+            blkio.join("blkio.throttle.io_serviced"),
+            "8:0 Read 250\n8:0 Write 40\n8:0 Sync 280\n8:0 Async 10\n8:0 Discard 0\n8:0 Total 290\nTotal 290\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_io(root.path(), Hierarchy::V1),
+            vec![IoStats {
+                device: "8:0".into(),
+                read_bytes: 4_096_000,
+                write_bytes: 1_024_000,
+                read_ops: 250,
+                write_ops: 40,
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_v2_io() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("cgroup.controllers"), "cpu io memory\n").unwrap();
+        assert_eq!(read_io(root.path(), Hierarchy::V2), vec![]);
+
+        fs::write(
+            root.path().join("io.stat"),
+            "8:0 rbytes=4096000 wbytes=1024000 rios=250 wios=40 dbytes=0 dios=0\n259:0 rbytes=512 wbytes=0 rios=1 wios=0 dbytes=0 dios=0\n",
+        )
+        .unwrap();
+        let io = read_io(root.path(), Hierarchy::V2);
+        assert_eq!(io.len(), 2);
+        assert_eq!(
+            io[0],
+            IoStats {
+                device: "8:0".into(),
+                read_bytes: 4_096_000,
+                write_bytes: 1_024_000,
+                read_ops: 250,
+                write_ops: 40,
+            }
+        );
+        assert_eq!(io[1].device, "259:0");
+
+        let sysfs = tempfile::tempdir().unwrap();
+        let block = sysfs.path().join("dev/block");
+        fs::create_dir_all(&block).unwrap();
+        std::os::unix::fs::symlink("../../devices/pci0000:00/block/sda", block.join("8:0"))
+            .unwrap();
+        assert_eq!(device_name(sysfs.path(), "8:0"), "sda");
+        assert_eq!(device_name(sysfs.path(), "259:0"), "259:0");
+    }
+
+    #[test]
+    fn detects_missing_hierarchy() {
+        let root = tempfile::tempdir().unwrap();
+        assert_eq!(Hierarchy::detect(&root.path().join("missing")), None);
+    }
+}
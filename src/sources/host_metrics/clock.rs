@@ -0,0 +1,129 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use nix::libc;
+use shared::btreemap;
+use std::{io, mem};
+
+const TIME_ERROR: libc::c_int = 5;
+const STA_UNSYNC: libc::c_int = 0x0040;
+const STA_NANO: libc::c_int = 0x2000;
+
+impl HostMetricsConfig {
+    pub async fn clock_metrics(&self) -> Vec<Metric> {
+        match read_clock() {
+            Ok(clock) => {
+                let timestamp = self.now();
+                vec![
+                    self.gauge(
+                        "clock_offset_seconds",
+                        timestamp,
+                        clock.offset_seconds(),
+                        btreemap! {},
+                    ),
+                    self.gauge(
+                        "clock_sync_status",
+                        timestamp,
+                        if clock.is_synchronized() { 1.0 } else { 0.0 },
+                        btreemap! {},
+                    ),
+                ]
+            }
+            Err(error) => {
+                error!(message = "Failed to load clock state.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+}
+
+/// The parts of the kernel clock discipline state that are reported.
+#[derive(Debug)]
+struct ClockState {
+    /// The return value of `adjtimex`, one of the `TIME_*` clock states.
+    state: libc::c_int,
+    /// The `STA_*` status flags.
+    status: libc::c_int,
+    /// The current time offset, in microseconds or nanoseconds if
+    /// `STA_NANO` is set.
+    offset: i64,
+}
+
+impl ClockState {
+    fn offset_seconds(&self) -> f64 {
+        if self.status & STA_NANO != 0 {
+            self.offset as f64 / 1_000_000_000.0
+        } else {
+            self.offset as f64 / 1_000_000.0
+        }
+    }
+
+    fn is_synchronized(&self) -> bool {
+        self.state != TIME_ERROR && self.status & STA_UNSYNC == 0
+    }
+}
+
+/// Queries the kernel clock state without adjusting it.
+fn read_clock() -> io::Result<ClockState> {
+    let mut timex: libc::timex = unsafe { mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut timex) };
+    if state < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ClockState {
+        state,
+        status: timex.status,
+        offset: timex.offset as i64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_clock_state() {
+        let clock = read_clock().unwrap();
+        assert!(clock.state >= 0);
+    }
+
+    #[test]
+    fn converts_offsets() {
+        let clock = ClockState {
+            state: 0,
+            status: 0,
+            offset: -1500,
+        };
+        assert_eq!(clock.offset_seconds(), -0.0015);
+
+        let clock = ClockState {
+            state: 0,
+            status: STA_NANO,
+            offset: 2_500_000,
+        };
+        assert_eq!(clock.offset_seconds(), 0.0025);
+    }
+
+    #[test]
+    fn detects_synchronization() {
+        let synchronized = ClockState {
+            state: 0,
+            status: 0x2001, // STA_PLL | STA_NANO
+            offset: 0,
+        };
+        assert!(synchronized.is_synchronized());
+
+        let unsynchronized = ClockState {
+            state: 0,
+            status: STA_UNSYNC,
+            offset: 0,
+        };
+        assert!(!unsynchronized.is_synchronized());
+
+        let error = ClockState {
+            state: TIME_ERROR,
+            status: 0,
+            offset: 0,
+        };
+        assert!(!error.is_synchronized());
+    }
+}
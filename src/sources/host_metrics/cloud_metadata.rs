@@ -0,0 +1,255 @@
+use super::{CloudMetadataConfig, CloudProvider};
+use crate::{config::ProxyConfig, http::HttpClient};
+use bytes::Bytes;
+use http::{Request, StatusCode};
+use hyper::{body::to_bytes as body_to_bytes, Body};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tokio::time::{timeout, Duration};
+
+/// Metadata endpoints are link-local, so anything slower means there is
+/// none.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Queries the instance metadata of the configured provider, or of the
+/// first one that responds, into the tags added to every metric. Off the
+/// cloud no tags are returned.
+pub(super) async fn fetch_tags(
+    config: &CloudMetadataConfig,
+    proxy: &ProxyConfig,
+) -> BTreeMap<String, String> {
+    let client = match HttpClient::new(None, proxy) {
+        Ok(client) => client,
+        Err(error) => {
+            error!(message = "Failed to create the cloud metadata client.", %error);
+            return BTreeMap::new();
+        }
+    };
+    let providers = match config.provider {
+        Some(provider) => vec![provider],
+        None => vec![CloudProvider::Aws, CloudProvider::Gcp, CloudProvider::Azure],
+    };
+    for provider in providers {
+        let endpoint = config
+            .endpoint
+            .as_deref()
+            .unwrap_or_else(|| provider.default_endpoint());
+        let endpoint = endpoint.trim_end_matches('/');
+        let metadata = match provider {
+            CloudProvider::Aws => fetch_aws(&client, endpoint).await,
+            CloudProvider::Gcp => fetch_gcp(&client, endpoint).await,
+            CloudProvider::Azure => fetch_azure(&client, endpoint).await,
+        };
+        match metadata {
+            Ok(metadata) => return metadata.into_tags(),
+            Err(error) => {
+                debug!(message = "Cloud instance metadata unavailable.", ?provider, %error)
+            }
+        }
+    }
+    info!(message = "No cloud instance metadata found, metrics are not tagged with it.");
+    BTreeMap::new()
+}
+
+impl CloudProvider {
+    fn default_endpoint(self) -> &'static str {
+        match self {
+            Self::Aws | Self::Azure => "http://169.254.169.254",
+            Self::Gcp => "http://metadata.google.internal",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct InstanceMetadata {
+    instance_id: String,
+    instance_type: String,
+    availability_zone: Option<String>,
+}
+
+impl InstanceMetadata {
+    fn into_tags(self) -> BTreeMap<String, String> {
+        let mut tags = BTreeMap::new();
+        tags.insert("instance_id".into(), self.instance_id);
+        tags.insert("instance_type".into(), self.instance_type);
+        if let Some(availability_zone) = self.availability_zone {
+            tags.insert("availability_zone".into(), availability_zone);
+        }
+        tags
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AwsIdentityDocument {
+    instance_id: String,
+    instance_type: String,
+    availability_zone: String,
+}
+
+/// Reads the identity document through IMDSv2.
+async fn fetch_aws(client: &HttpClient, endpoint: &str) -> crate::Result<InstanceMetadata> {
+    let request = Request::put(format!("{}/latest/api/token", endpoint))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "60")
+        .body(Body::empty())?;
+    let token = send(client, request).await?;
+
+    let request = Request::get(format!(
+        "{}/latest/dynamic/instance-identity/document",
+        endpoint
+    ))
+    .header("X-aws-ec2-metadata-token", token.as_ref())
+    .body(Body::empty())?;
+    let document: AwsIdentityDocument = serde_json::from_slice(&send(client, request).await?)?;
+    Ok(InstanceMetadata {
+        instance_id: document.instance_id,
+        instance_type: document.instance_type,
+        availability_zone: Some(document.availability_zone),
+    })
+}
+
+async fn fetch_gcp(client: &HttpClient, endpoint: &str) -> crate::Result<InstanceMetadata> {
+    let get = |path| async move {
+        let request = Request::get(format!("{}/computeMetadata/v1/instance/{}", endpoint, path))
+            .header("Metadata-Flavor", "Google")
+            .body(Body::empty())?;
+        let value = send(client, request).await?;
+        Ok::<_, crate::Error>(String::from_utf8_lossy(&value).into_owned())
+    };
+    // The machine type and zone are given as resource paths, such as
+    // `projects/1234/zones/us-central1-a`.
+    let last_segment = |value: String| value.rsplit('/').next().unwrap_or_default().to_string();
+    Ok(InstanceMetadata {
+        instance_id: get("id").await?,
+        instance_type: last_segment(get("machine-type").await?),
+        availability_zone: Some(last_segment(get("zone").await?)),
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzureCompute {
+    vm_id: String,
+    vm_size: String,
+    #[serde(default)]
+    zone: String,
+}
+
+async fn fetch_azure(client: &HttpClient, endpoint: &str) -> crate::Result<InstanceMetadata> {
+    let request = Request::get(format!(
+        "{}/metadata/instance/compute?api-version=2021-02-01",
+        endpoint
+    ))
+    .header("Metadata", "true")
+    .body(Body::empty())?;
+    let compute: AzureCompute = serde_json::from_slice(&send(client, request).await?)?;
+    Ok(InstanceMetadata {
+        instance_id: compute.vm_id,
+        instance_type: compute.vm_size,
+        // Virtual machines outside of availability zones have an empty zone.
+        availability_zone: Some(compute.zone).filter(|zone| !zone.is_empty()),
+    })
+}
+
+async fn send(client: &HttpClient, request: Request<Body>) -> crate::Result<Bytes> {
+    let response = timeout(TIMEOUT, client.send(request)).await??;
+    match response.status() {
+        StatusCode::OK => Ok(body_to_bytes(response.into_body()).await?),
+        status => Err(format!("Unexpected status {}.", status).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::HostMetricsConfig;
+    use super::*;
+    use crate::{
+        event::Event,
+        test_util::{next_addr, wait_for_tcp},
+    };
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Method, Response, Server,
+    };
+
+    #[tokio::test]
+    async fn fetches_aws_metadata() {
+        let addr = next_addr();
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, crate::Error>(service_fn(|request: Request<Body>| async move {
+                let body = match (request.method(), request.uri().path()) {
+                    (&Method::PUT, "/latest/api/token") => "token",
+                    (&Method::GET, "/latest/dynamic/instance-identity/document")
+                        if request.headers()["X-aws-ec2-metadata-token"] == "token" =>
+                    {
+                        r#"{
+                            "availabilityZone": "us-east-1b",
+                            "instanceId": "i-0123456789abcdef0",
+                            "instanceType": "m5.large",
+                            "region": "us-east-1"
+                        }"#
+                    }
+                    _ => {
+                        return Ok::<_, crate::Error>(
+                            Response::builder().status(404).body(Body::empty())?,
+                        )
+                    }
+                };
+                Ok(Response::new(Body::from(body)))
+            }))
+        });
+        tokio::spawn(async move {
+            if let Err(error) = Server::bind(&addr).serve(make_svc).await {
+                error!(message = "Server error.", %error);
+            }
+        });
+        wait_for_tcp(addr).await;
+
+        let config = CloudMetadataConfig {
+            enabled: true,
+            provider: Some(CloudProvider::Aws),
+            endpoint: Some(format!("http://{}", addr)),
+        };
+        let tags = fetch_tags(&config, &ProxyConfig::default()).await;
+        assert_eq!(
+            tags,
+            vec![
+                ("availability_zone", "us-east-1b"),
+                ("instance_id", "i-0123456789abcdef0"),
+                ("instance_type", "m5.large"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+        );
+
+        let metrics = HostMetricsConfig {
+            collectors: Some(vec![]),
+            cloud_tags: tags,
+            ..Default::default()
+        }
+        .capture_metrics()
+        .await
+        .map(Event::into_metric)
+        .collect::<Vec<_>>();
+        assert_eq!(metrics.len(), 2);
+        for metric in &metrics {
+            assert_eq!(
+                metric.tag_value("instance_id"),
+                Some("i-0123456789abcdef0".into())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_unavailable_metadata() {
+        let config = CloudMetadataConfig {
+            enabled: true,
+            provider: None,
+            endpoint: Some(format!("http://{}", next_addr())),
+        };
+        assert!(fetch_tags(&config, &ProxyConfig::default())
+            .await
+            .is_empty());
+    }
+}
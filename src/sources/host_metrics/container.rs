@@ -0,0 +1,207 @@
+use super::{load_concurrently, HostMetricsConfig};
+use crate::{docker::docker, event::metric::Metric};
+use bollard::{
+    container::{ListContainersOptions, Stats, StatsOptions},
+    Docker,
+};
+use futures::{stream, StreamExt};
+use shared::btreemap;
+use tokio::sync::Semaphore;
+
+impl HostMetricsConfig {
+    pub async fn container_metrics(&self) -> Vec<Metric> {
+        let docker = match docker(
+            self.container.docker_host.clone(),
+            self.container.tls.clone(),
+        ) {
+            Ok(docker) => docker,
+            Err(error) => {
+                error!(message = "Failed to connect to Docker.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+        // Only running containers are listed.
+        let containers = match docker
+            .list_containers(Some(ListContainersOptions::<String>::default()))
+            .await
+        {
+            Ok(containers) => containers,
+            Err(error) => {
+                error!(message = "Failed to list containers.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let containers = containers.into_iter().filter_map(|container| {
+            let id = container.id?;
+            // Docker prefixes the names with a slash.
+            let name = container
+                .names
+                .and_then(|names| names.into_iter().next())
+                .map(|name| name.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| id.clone());
+            if !self.container.names.contains_str(Some(&name)) {
+                return None;
+            }
+            Some((id, name, container.image.unwrap_or_default()))
+        });
+        let docker = &docker;
+        let limit = Semaphore::new(self.concurrency_limit());
+        load_concurrently(
+            stream::iter(containers),
+            &limit,
+            |(id, name, image)| async move {
+                let stats = load_stats(docker, &id, self.error_log_rate_secs()).await?;
+                let timestamp = self.now();
+                let tags = btreemap! {
+                    "container_id" => id,
+                    "name" => name,
+                    "image" => image,
+                };
+                let mut metrics = vec![self.counter(
+                    "container_cpu_seconds_total",
+                    timestamp,
+                    stats.cpu_stats.cpu_usage.total_usage as f64 / 1_000_000_000.0,
+                    tags.clone(),
+                )];
+                if let Some(usage) = stats.memory_stats.usage {
+                    metrics.push(self.gauge(
+                        "container_memory_usage_bytes",
+                        timestamp,
+                        usage as f64,
+                        tags,
+                    ));
+                }
+                Some(metrics)
+            },
+        )
+        .filter_map(|metrics| async { metrics })
+        .map(stream::iter)
+        .flatten()
+        .collect::<Vec<_>>()
+        .await
+    }
+}
+
+/// Takes a single sample of the resource usage of a container, which may
+/// have stopped since it was listed.
+async fn load_stats(docker: &Docker, id: &str, log_rate_secs: u64) -> Option<Stats> {
+    let options = StatsOptions {
+        stream: false,
+        one_shot: true,
+    };
+    match docker.stats(id, Some(options)).next().await? {
+        Ok(stats) => Some(stats),
+        Err(error) => {
+            error!(message = "Failed to load container stats.", container_id = %id, %error, internal_log_rate_secs = log_rate_secs);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ContainerConfig, FilterList, PatternWrapper};
+    use super::*;
+    use crate::{
+        event::metric::MetricValue,
+        test_util::{next_addr, wait_for_tcp},
+    };
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+
+    const CONTAINERS: &str = r#"[
+        {"Id": "8dfafdbc3a40", "Names": ["/web"], "Image": "nginx:1.21", "State": "running"},
+        {"Id": "9cd87474be90", "Names": ["/db"], "Image": "postgres:13", "State": "running"}
+    ]"#;
+
+    fn stats(id: &str) -> String {
+        let cpu = r#"{
+            "cpu_usage": {"total_usage": 2500000000, "usage_in_kernelmode": 500000000, "usage_in_usermode": 2000000000},
+            "system_cpu_usage": 100000000000,
+            "online_cpus": 2,
+            "throttling_data": {"periods": 0, "throttled_periods": 0, "throttled_time": 0}
+        }"#;
+        format!(
+            r#"{{
+                "read": "2021-09-01T12:00:00.000000000Z",
+                "preread": "2021-09-01T11:59:59.000000000Z",
+                "num_procs": 0,
+                "pids_stats": {{"current": 3}},
+                "blkio_stats": {{}},
+                "storage_stats": {{}},
+                "cpu_stats": {},
+                "precpu_stats": {},
+                "memory_stats": {{"usage": 52428800, "limit": 2147483648}},
+                "name": "/web",
+                "id": "{}"
+            }}"#,
+            cpu, cpu, id
+        )
+    }
+
+    #[tokio::test]
+    async fn tags_container_metrics() {
+        let addr = next_addr();
+        let make_svc = make_service_fn(|_| async {
+            Ok::<_, crate::Error>(service_fn(|request: Request<Body>| async move {
+                let path = request.uri().path();
+                let body = if path.ends_with("/containers/json") {
+                    CONTAINERS.to_string()
+                } else if let Some(id) = path
+                    .strip_suffix("/stats")
+                    .and_then(|path| path.rsplit('/').next())
+                {
+                    stats(id)
+                } else {
+                    return Ok::<_, crate::Error>(
+                        Response::builder().status(404).body(Body::empty())?,
+                    );
+                };
+                Ok(Response::builder()
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body))?)
+            }))
+        });
+        tokio::spawn(async move {
+            if let Err(error) = Server::bind(&addr).serve(make_svc).await {
+                error!(message = "Server error.", %error);
+            }
+        });
+        wait_for_tcp(addr).await;
+
+        let config = HostMetricsConfig {
+            container: ContainerConfig {
+                docker_host: Some(format!("http://{}", addr)),
+                names: FilterList {
+                    includes: None,
+                    excludes: Some(vec![PatternWrapper::new("db").unwrap()]),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let metrics = config.container_metrics().await;
+
+        assert_eq!(metrics.len(), 2);
+        for metric in &metrics {
+            assert_eq!(
+                metric.tag_value("container_id"),
+                Some("8dfafdbc3a40".into())
+            );
+            assert_eq!(metric.tag_value("name"), Some("web".into()));
+            assert_eq!(metric.tag_value("image"), Some("nginx:1.21".into()));
+        }
+        assert_eq!(metrics[0].name(), "container_cpu_seconds_total");
+        assert_eq!(metrics[0].value(), &MetricValue::Counter { value: 2.5 });
+        assert_eq!(metrics[1].name(), "container_memory_usage_bytes");
+        assert_eq!(
+            metrics[1].value(),
+            &MetricValue::Gauge {
+                value: 52_428_800.0
+            }
+        );
+    }
+}
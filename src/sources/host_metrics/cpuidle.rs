@@ -0,0 +1,113 @@
+use super::{read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::fs;
+use std::path::Path;
+
+impl HostMetricsConfig {
+    pub async fn cpuidle_metrics(&self) -> Vec<Metric> {
+        let timestamp = self.now();
+        read_cstates(&self.sysfs_root())
+            .into_iter()
+            .map(|cstate| {
+                self.counter(
+                    "cpu_cstate_seconds_total",
+                    timestamp,
+                    cstate.time_us as f64 / 1_000_000.0,
+                    btreemap! {
+                        "cpu" => cstate.cpu.to_string(),
+                        "state" => cstate.state,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CState {
+    cpu: usize,
+    /// The name of the idle state, such as `C1E`.
+    state: String,
+    /// The time spent in the state since boot.
+    time_us: u64,
+}
+
+/// Reads the time each CPU spent in each of its idle states, which is
+/// nothing without a cpuidle driver, such as in most virtual machines.
+fn read_cstates(sysfs: &Path) -> Vec<CState> {
+    let entries = match fs::read_dir(sysfs.join("devices/system/cpu")) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut cstates = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let cpu = name.to_str()?.strip_prefix("cpu")?.parse::<usize>().ok()?;
+            Some((cpu, fs::read_dir(entry.path().join("cpuidle")).ok()?))
+        })
+        .flat_map(|(cpu, states)| {
+            states.filter_map(Result::ok).filter_map(move |entry| {
+                let name = entry.file_name();
+                let index = name
+                    .to_str()?
+                    .strip_prefix("state")?
+                    .parse::<usize>()
+                    .ok()?;
+                let path = entry.path();
+                let time_us = read_u64(&path.join("time"))?;
+                let state = fs::read_to_string(path.join("name"))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_else(|_| format!("state{}", index));
+                Some((
+                    index,
+                    CState {
+                        cpu,
+                        state,
+                        time_us,
+                    },
+                ))
+            })
+        })
+        .collect::<Vec<_>>();
+    cstates.sort_by_key(|(index, cstate)| (cstate.cpu, *index));
+    cstates.into_iter().map(|(_, cstate)| cstate).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cstates() {
+        let sysfs = tempfile::tempdir().unwrap();
+        assert_eq!(read_cstates(sysfs.path()), vec![]);
+
+        let cpu = sysfs.path().join("devices/system/cpu/cpu0");
+        fs::create_dir_all(&cpu).unwrap();
+        assert_eq!(read_cstates(sysfs.path()), vec![]);
+
+        for (index, name, time) in &[(0, "POLL", "1234\n"), (1, "C1E", "987654321\n")] {
+            let state = cpu.join(format!("cpuidle/state{}", index));
+            fs::create_dir_all(&state).unwrap();
+            fs::write(state.join("name"), format!("{}\n", name)).unwrap();
+            fs::write(state.join("time"), time).unwrap();
+        }
+        assert_eq!(
+            read_cstates(sysfs.path()),
+            vec![
+                CState {
+                    cpu: 0,
+                    state: "POLL".into(),
+                    time_us: 1234,
+                },
+                CState {
+                    cpu: 0,
+                    state: "C1E".into(),
+                    time_us: 987_654_321,
+                },
+            ]
+        );
+    }
+}
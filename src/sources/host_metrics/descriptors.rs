@@ -0,0 +1,880 @@
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+use super::TcpGroupBy;
+use super::{created_name, rate_name, Collector, CounterMode, HostMetricsConfig, ValueType};
+
+/// The type of value a metric is emitted with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Distribution,
+}
+
+/// Describes a metric the source can emit, without collecting it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetricDescriptor {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub kind: MetricType,
+    /// The keys of the tags the metric may carry, sorted.
+    pub tags: Vec<&'static str>,
+    /// What the metric measures, for use as help text.
+    pub description: Option<&'static str>,
+}
+
+impl HostMetricsConfig {
+    /// Lists every metric the enabled collectors can emit on this platform.
+    pub fn describe_metrics(&self) -> Vec<MetricDescriptor> {
+        let mut descriptors = Vec::new();
+        for &collector in Collector::ALL {
+            if !self.has_collector(collector) {
+                continue;
+            }
+            let summarized =
+                self.summary.samples() > 1 && self.summary.collectors.contains(&collector);
+            for (name, kind, tags) in self.collector_metrics(collector) {
+                let kind = self.metric_type(name, kind);
+                let mut tags = tags;
+                tags.extend(&["collector", "host"]);
+                tags.sort_unstable();
+
+                if summarized && kind == MetricType::Gauge {
+                    for suffix in &["min", "max", "avg"] {
+                        descriptors.push(self.descriptor(
+                            format!("{}_{}", name, suffix),
+                            kind,
+                            tags.clone(),
+                        ));
+                    }
+                }
+                descriptors.push(self.descriptor(name.into(), kind, tags));
+            }
+            if self.emit_collected_counts {
+                descriptors.push(self.descriptor(
+                    "collected_metrics_count".into(),
+                    MetricType::Gauge,
+                    vec!["collector", "host"],
+                ));
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if self.self_metrics {
+            let name = "vector_process_cpu_seconds_total";
+            let kind = self.metric_type(name, MetricType::Counter);
+            descriptors.push(self.descriptor(name.into(), kind, vec!["host"]));
+            for &name in &[
+                "vector_process_resident_memory_bytes",
+                "vector_process_threads",
+                "vector_process_open_fds",
+            ] {
+                descriptors.push(self.descriptor(name.into(), MetricType::Gauge, vec!["host"]));
+            }
+        }
+        descriptors.push(self.descriptor(
+            "scrape_interval_seconds".into(),
+            MetricType::Gauge,
+            vec!["host"],
+        ));
+        descriptors.push(self.descriptor(
+            "host_metrics_build_info".into(),
+            MetricType::Gauge,
+            vec!["collectors", "host", "version"],
+        ));
+        if self.emit_errors {
+            descriptors.push(self.descriptor(
+                "collect_error".into(),
+                MetricType::Gauge,
+                vec!["collector", "error", "host"],
+            ));
+        }
+        if self.counter_mode == CounterMode::EmitBoth {
+            let rates = descriptors
+                .iter()
+                .filter(|descriptor| descriptor.kind == MetricType::Counter)
+                .map(|descriptor| MetricDescriptor {
+                    name: rate_name(&descriptor.name),
+                    kind: MetricType::Gauge,
+                    description: None,
+                    ..descriptor.clone()
+                })
+                .collect::<Vec<_>>();
+            descriptors.extend(rates);
+        }
+        if self.created_timestamps {
+            let created = descriptors
+                .iter()
+                .filter(|descriptor| descriptor.kind == MetricType::Counter)
+                .map(|descriptor| MetricDescriptor {
+                    name: created_name(&descriptor.name),
+                    kind: MetricType::Gauge,
+                    description: None,
+                    ..descriptor.clone()
+                })
+                .collect::<Vec<_>>();
+            descriptors.extend(created);
+        }
+        let smoothed_names = self.smoothed_names();
+        let smoothed = descriptors
+            .iter()
+            .filter(|descriptor| {
+                descriptor.kind == MetricType::Gauge && smoothed_names.contains(&descriptor.name)
+            })
+            .map(|descriptor| MetricDescriptor {
+                name: format!("{}_smoothed", descriptor.name),
+                description: None,
+                ..descriptor.clone()
+            })
+            .collect::<Vec<_>>();
+        descriptors.extend(smoothed);
+        descriptors
+    }
+
+    /// Whether any collector can emit a metric of this name on this
+    /// platform.
+    pub(super) fn is_known_metric(&self, name: &str) -> bool {
+        Collector::ALL.iter().any(|&collector| {
+            self.collector_metrics(collector)
+                .iter()
+                .any(|(known, _, _)| *known == name)
+        })
+    }
+
+    /// The type of a metric as emitted, after the configured overrides.
+    fn metric_type(&self, name: &str, kind: MetricType) -> MetricType {
+        let value_type = match kind {
+            MetricType::Counter => ValueType::Counter,
+            MetricType::Gauge => ValueType::Gauge,
+            MetricType::Distribution => return kind,
+        };
+        match self.value_type(name, value_type) {
+            ValueType::Counter => MetricType::Counter,
+            ValueType::Gauge => MetricType::Gauge,
+        }
+    }
+
+    fn descriptor(
+        &self,
+        name: String,
+        kind: MetricType,
+        tags: Vec<&'static str>,
+    ) -> MetricDescriptor {
+        let description = description(&name);
+        let name = self.time_unit.convert(&name, 0.0).0.into_owned();
+        let mut tags = tags;
+        if self.cloud_metadata.enabled {
+            tags.extend(&["availability_zone", "instance_id", "instance_type"]);
+            tags.sort_unstable();
+        }
+        let (namespace, name) = self.metric_name(&name);
+        MetricDescriptor {
+            namespace,
+            name,
+            kind,
+            tags,
+            description,
+        }
+    }
+
+    /// The names, types, and collector-specific tag keys of the metrics of
+    /// one collector. This must be kept in line with the collectors.
+    fn collector_metrics(
+        &self,
+        collector: Collector,
+    ) -> Vec<(&'static str, MetricType, Vec<&'static str>)> {
+        use MetricType::{Counter, Gauge};
+
+        let mut metrics = Vec::new();
+        match collector {
+            Collector::Cpu => {
+                let tags: &[_] = if self.cpu.include_offline {
+                    &["cpu", "mode", "online"]
+                } else {
+                    &["cpu", "mode"]
+                };
+                if self.cpu.percentiles {
+                    metrics.extend(names(
+                        &["cpu_seconds_p50", "cpu_seconds_p95", "cpu_seconds_max"],
+                        Gauge,
+                        &["mode"],
+                    ));
+                } else {
+                    metrics.extend(names(&["cpu_seconds_total"], Counter, tags));
+                }
+                #[cfg(unix)]
+                if self.cpu.saturation {
+                    metrics.extend(names(&["cpu_saturation_ratio"], Gauge, &[]));
+                }
+            }
+            Collector::Disk => {
+                #[allow(unused_mut)]
+                let mut tags = vec!["device"];
+                #[cfg(target_os = "linux")]
+                {
+                    tags.push("mountpoint");
+                    if !self.filesystem.classes.is_empty() {
+                        tags.push("class");
+                    }
+                    if self.disk.resolve_dm_names {
+                        tags.push("name");
+                    }
+                    if self.disk.serial_tags {
+                        tags.push("serial");
+                    }
+                }
+                metrics.extend(names(
+                    &[
+                        "disk_read_bytes_total",
+                        "disk_reads_completed_total",
+                        "disk_written_bytes_total",
+                        "disk_writes_completed_total",
+                    ],
+                    Counter,
+                    &tags,
+                ));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(&["disk_errors_total"], Counter, &tags));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(&["disk_readonly", "disk_removable"], Gauge, &tags));
+            }
+            Collector::Filesystem => {
+                let mut tags = if !self.filesystem.paths.is_empty() {
+                    vec!["mountpoint", "path"]
+                } else if self.filesystem.normalize_types {
+                    vec!["device", "filesystem", "mountpoint", "raw_fstype"]
+                } else {
+                    vec!["device", "filesystem", "mountpoint"]
+                };
+                #[cfg(target_os = "linux")]
+                tags.push("block_device");
+                if !self.filesystem.classes.is_empty() {
+                    tags.push("class");
+                }
+                metrics.extend(names(
+                    &[
+                        "filesystem_free_bytes",
+                        "filesystem_total_bytes",
+                        "filesystem_used_bytes",
+                        "filesystem_near_full",
+                    ],
+                    Gauge,
+                    &tags,
+                ));
+                #[cfg(not(target_os = "windows"))]
+                metrics.extend(names(&["filesystem_used_ratio"], Gauge, &tags));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(&["filesystem_readonly"], Gauge, &tags));
+                if self.filesystem.paths.is_empty() {
+                    metrics.extend(names(&["filesystem_stat_duration_seconds"], Gauge, &tags));
+                }
+                #[cfg(target_os = "linux")]
+                if self.filesystem.errors {
+                    metrics.extend(names(&["filesystem_errors_total"], Counter, &tags));
+                }
+            }
+            Collector::Load => {
+                #[cfg(unix)]
+                {
+                    metrics.extend(names(&["load1", "load5", "load15"], Gauge, &[]));
+                    if self.load_per_core {
+                        metrics.extend(names(
+                            &["load1_per_core", "load5_per_core", "load15_per_core"],
+                            Gauge,
+                            &[],
+                        ));
+                    }
+                }
+            }
+            Collector::Host => {
+                let uptime_tags: &[&str] = if self.boot_time_tag {
+                    &["boot_time"]
+                } else {
+                    &[]
+                };
+                metrics.extend(names(&["uptime"], Gauge, uptime_tags));
+                metrics.extend(names(
+                    &[
+                        "boot_time",
+                        "random_entropy_available_bits",
+                        "random_pool_size_bits",
+                        "random_urandom_min_reseed_seconds",
+                    ],
+                    Gauge,
+                    &[],
+                ));
+                metrics.extend(names(
+                    &["host_info"],
+                    Gauge,
+                    &["arch", "kernel", "os", "os_version"],
+                ));
+            }
+            Collector::Memory => {
+                metrics.extend(names(
+                    &[
+                        "memory_total_bytes",
+                        "memory_free_bytes",
+                        "memory_available_bytes",
+                    ],
+                    Gauge,
+                    &[],
+                ));
+                #[cfg(any(target_os = "linux", target_os = "macos"))]
+                metrics.extend(names(&["memory_active_bytes"], Gauge, &[]));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &[
+                        "memory_buffers_bytes",
+                        "memory_cached_bytes",
+                        "memory_shared_bytes",
+                        "memory_used_bytes",
+                        "memory_pressure_ratio",
+                    ],
+                    Gauge,
+                    &[],
+                ));
+                #[cfg(target_os = "macos")]
+                metrics.extend(names(
+                    &["memory_inactive_bytes", "memory_wired_bytes"],
+                    Gauge,
+                    &[],
+                ));
+                metrics.extend(names(
+                    &[
+                        "memory_swap_free_bytes",
+                        "memory_swap_total_bytes",
+                        "memory_swap_used_bytes",
+                    ],
+                    Gauge,
+                    &[],
+                ));
+                #[cfg(not(target_os = "windows"))]
+                metrics.extend(names(
+                    &[
+                        "memory_swapped_in_bytes_total",
+                        "memory_swapped_out_bytes_total",
+                    ],
+                    Counter,
+                    &[],
+                ));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["swap_device_total_bytes", "swap_device_used_bytes"],
+                    Gauge,
+                    &["device", "type"],
+                ));
+            }
+            Collector::Network => {
+                let mut tags = vec!["device"];
+                if !self.network.aliases.is_empty() && !self.network.alias_as_device {
+                    tags.push("alias");
+                }
+                let mut traffic_tags = tags.clone();
+                if self.network.split_family {
+                    traffic_tags.push("family");
+                }
+                metrics.extend(names(
+                    &[
+                        "network_receive_bytes_total",
+                        "network_receive_packets_total",
+                        "network_transmit_bytes_total",
+                    ],
+                    Counter,
+                    &traffic_tags,
+                ));
+                metrics.extend(names(
+                    &["network_receive_errs_total", "network_transmit_errs_total"],
+                    Counter,
+                    &tags,
+                ));
+                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                {
+                    metrics.extend(names(
+                        &["network_transmit_packets_drop_total"],
+                        Counter,
+                        &tags,
+                    ));
+                    metrics.extend(names(
+                        &["network_transmit_packets_total"],
+                        Counter,
+                        &traffic_tags,
+                    ));
+                }
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &[
+                        "network_receive_compressed_total",
+                        "network_receive_multicast_total",
+                        "network_transmit_compressed_total",
+                        "network_carrier_changes_total",
+                    ],
+                    Counter,
+                    &tags,
+                ));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["network_mtu_bytes", "network_tx_queue_length"],
+                    Gauge,
+                    &tags,
+                ));
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &[
+                        "tcp_mem_pressure_low",
+                        "tcp_mem_pressure",
+                        "tcp_mem_max",
+                        "tcp_mem_allocated",
+                    ],
+                    Gauge,
+                    &[],
+                ));
+            }
+            Collector::Vm => {
+                #[cfg(target_os = "linux")]
+                {
+                    metrics.extend(names(&["vm_steal_seconds_total"], Counter, &[]));
+                    metrics.extend(names(
+                        &["vm_balloon_current_bytes", "vm_balloon_target_bytes"],
+                        Gauge,
+                        &[],
+                    ));
+                }
+            }
+            Collector::Cgroup => {
+                #[cfg(target_os = "linux")]
+                {
+                    metrics.extend(names(
+                        &["cgroup_memory_usage_bytes", "cgroup_memory_limit_bytes"],
+                        Gauge,
+                        &[],
+                    ));
+                    metrics.extend(names(
+                        &[
+                            "cgroup_cpu_periods_total",
+                            "cgroup_cpu_throttled_periods_total",
+                            "cgroup_cpu_throttled_seconds_total",
+                        ],
+                        Counter,
+                        &[],
+                    ));
+                    metrics.extend(names(
+                        &[
+                            "cgroup_io_read_bytes_total",
+                            "cgroup_io_write_bytes_total",
+                            "cgroup_io_read_ops_total",
+                            "cgroup_io_write_ops_total",
+                        ],
+                        Counter,
+                        &["device"],
+                    ));
+                }
+            }
+            Collector::Tcp => {
+                #[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+                metrics.extend(names(
+                    &[
+                        "tcp_rtt_seconds_min",
+                        "tcp_rtt_seconds_max",
+                        "tcp_rtt_seconds_avg",
+                    ],
+                    Gauge,
+                    &[match self.tcp.group_by {
+                        TcpGroupBy::State => "state",
+                        TcpGroupBy::Subnet => "subnet",
+                    }],
+                ));
+            }
+            Collector::Clock => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["clock_offset_seconds", "clock_sync_status"],
+                    Gauge,
+                    &[],
+                ));
+            }
+            Collector::Mdraid => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &[
+                        "mdraid_disks_active",
+                        "mdraid_disks_total",
+                        "mdraid_degraded",
+                    ],
+                    Gauge,
+                    &["device"],
+                ));
+            }
+            Collector::Process => {
+                #[cfg(target_os = "linux")]
+                {
+                    if self.process.cpu_distribution {
+                        metrics.extend(names(
+                            &["process_cpu_seconds"],
+                            MetricType::Distribution,
+                            &[],
+                        ));
+                    } else {
+                        metrics.extend(names(
+                            &["process_cpu_seconds_total"],
+                            Counter,
+                            &["name", "pid"],
+                        ));
+                        metrics.extend(names(
+                            &["process_threads", "process_open_fds"],
+                            Gauge,
+                            &["name", "pid"],
+                        ));
+                        if self.process.names.includes.is_some() {
+                            metrics.extend(names(
+                                &["process_read_bytes_total", "process_write_bytes_total"],
+                                Counter,
+                                &["name", "pid"],
+                            ));
+                        }
+                    }
+                }
+            }
+            Collector::TcpListen => {
+                #[cfg(target_os = "linux")]
+                {
+                    metrics.extend(names(&["tcp_listen_backlog"], Gauge, &["port"]));
+                    metrics.extend(names(&["tcp_listen_overflow_total"], Counter, &[]));
+                }
+            }
+            Collector::BlockDevice => {
+                #[cfg(target_os = "linux")]
+                {
+                    let mut tags = vec!["device"];
+                    if self.disk.resolve_dm_names {
+                        tags.push("name");
+                    }
+                    if self.disk.serial_tags {
+                        tags.push("serial");
+                    }
+                    metrics.extend(names(
+                        &["disk_size_bytes", "disk_sector_size_bytes"],
+                        Gauge,
+                        &tags,
+                    ));
+                }
+            }
+            Collector::NetDevQueue => {
+                #[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+                {
+                    let tags = &["device", "handle", "qdisc"];
+                    metrics.extend(names(&["network_qdisc_backlog_bytes"], Gauge, tags));
+                    metrics.extend(names(&["network_qdisc_drops_total"], Counter, tags));
+                }
+            }
+            Collector::Zfs => {
+                #[cfg(all(target_os = "linux", feature = "sources-host_metrics-zfs"))]
+                {
+                    metrics.extend(names(&["zfs_arc_size_bytes"], Gauge, &[]));
+                    metrics.extend(names(&["zfs_pool_health"], Gauge, &["health", "pool"]));
+                    metrics.extend(names(
+                        &["zfs_pool_allocated_bytes", "zfs_pool_free_bytes"],
+                        Gauge,
+                        &["pool"],
+                    ));
+                }
+            }
+            Collector::ListenSockets => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["listen_socket"],
+                    Gauge,
+                    &["pid", "port", "process", "proto"],
+                ));
+            }
+            Collector::KernelMessages => {
+                #[cfg(target_os = "linux")]
+                {
+                    metrics.extend(names(&["kernel_messages_total"], Counter, &[]));
+                    if self.kernel_messages.by_severity {
+                        metrics.extend(names(&["kernel_buffered_messages"], Gauge, &["severity"]));
+                    }
+                }
+            }
+            Collector::Container => {
+                #[cfg(feature = "sources-host_metrics-docker")]
+                {
+                    let tags = &["container_id", "image", "name"];
+                    metrics.extend(names(&["container_cpu_seconds_total"], Counter, tags));
+                    metrics.extend(names(&["container_memory_usage_bytes"], Gauge, tags));
+                }
+            }
+            Collector::Buddyinfo => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["memory_free_blocks"],
+                    Gauge,
+                    &["node", "order", "zone"],
+                ));
+            }
+            Collector::Updates => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(&["reboot_required", "pending_updates"], Gauge, &[]));
+            }
+            Collector::Schedstat => {
+                #[cfg(target_os = "linux")]
+                {
+                    metrics.extend(names(
+                        &[
+                            "schedstat_running_seconds_total",
+                            "schedstat_waiting_seconds_total",
+                        ],
+                        Counter,
+                        &["cpu"],
+                    ));
+                    metrics.extend(names(
+                        &["schedstat_migrations_total"],
+                        Counter,
+                        &["cpu", "domain"],
+                    ));
+                }
+            }
+            Collector::Cpuidle => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &["cpu_cstate_seconds_total"],
+                    Counter,
+                    &["cpu", "state"],
+                ));
+            }
+            Collector::Softirqs => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(&["softirqs_total"], Counter, &["cpu", "type"]));
+            }
+            Collector::Limits => {
+                #[cfg(unix)]
+                metrics.extend(names(
+                    &["limit_max_open_files", "limit_max_processes"],
+                    Gauge,
+                    &[],
+                ));
+            }
+            Collector::ThermalThrottle => {
+                #[cfg(target_os = "linux")]
+                metrics.extend(names(
+                    &[
+                        "cpu_throttle_count_total",
+                        "cpu_thermal_throttle_seconds_total",
+                    ],
+                    Counter,
+                    &["cpu", "scope"],
+                ));
+            }
+        }
+        metrics
+    }
+}
+
+fn names(
+    names: &[&'static str],
+    kind: MetricType,
+    tags: &[&'static str],
+) -> Vec<(&'static str, MetricType, Vec<&'static str>)> {
+    names
+        .iter()
+        .map(|&name| (name, kind, tags.to_vec()))
+        .collect()
+}
+
+/// The help text of a metric, as exposed by sinks supporting metric
+/// metadata. Metrics carry no metadata of their own, so it is only
+/// available through the descriptors.
+fn description(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "cpu_seconds_total" => "The number of CPU seconds accumulated in different operating modes.",
+        "cpu_seconds_p50" => "The median across the CPUs of the number of CPU seconds accumulated in the mode.",
+        "cpu_seconds_p95" => "The 95th percentile across the CPUs of the number of CPU seconds accumulated in the mode.",
+        "cpu_seconds_max" => "The largest number of CPU seconds accumulated in the mode by any CPU.",
+        "cpu_saturation_ratio" => "How saturated the CPUs are, from 0 to 1. The larger of the one-minute load average per logical CPU, capped at 1, and the share of the last 10 seconds in which some tasks waited for a CPU, from the `avg10` of `/proc/pressure/cpu`. Falls back to the load per CPU without pressure stall information.",
+        "disk_read_bytes_total" => "The accumulated number of bytes read in.",
+        "disk_reads_completed_total" => "The accumulated number of read operations completed.",
+        "disk_written_bytes_total" => "The accumulated number of bytes written out.",
+        "disk_writes_completed_total" => "The accumulated number of write operations completed.",
+        "disk_errors_total" => "The accumulated number of failed I/O requests, for disks whose driver exposes it.",
+        "disk_readonly" => "Whether the block device is read-only (1) or not (0).",
+        "disk_removable" => "Whether the block device is removable (1) or not (0), such as USB drives and optical drives. Not reported for partitions.",
+        "disk_size_bytes" => "The capacity of the block device in bytes.",
+        "disk_sector_size_bytes" => "The hardware sector size of the block device in bytes.",
+        "filesystem_free_bytes" => "The number of bytes free on the named filesystem.",
+        "filesystem_total_bytes" => "The total number of bytes in the named filesystem.",
+        "filesystem_used_bytes" => "The number of bytes used on the named filesystem.",
+        "filesystem_used_ratio" => "The ratio between used and total bytes on the named filesystem.",
+        "filesystem_near_full" => "Whether the ratio between used and total bytes on the named filesystem exceeds `filesystem.near_full_threshold` (1) or not (0).",
+        "filesystem_readonly" => "Whether the filesystem is mounted read-only (1) or not (0), such as after being remounted due to errors.",
+        "filesystem_stat_duration_seconds" => "The time taken to load the usage of the filesystem, which grows on degraded mounts such as unresponsive network filesystems.",
+        "filesystem_errors_total" => "The number of errors the filesystem has detected since it was created, such as corrupted metadata. Only reported for ext4.",
+        "load1" => "System load averaged over the last 1 minute.",
+        "load5" => "System load averaged over the last 5 minutes.",
+        "load15" => "System load averaged over the last 15 minutes.",
+        "load1_per_core" => "`load1` divided by the number of logical CPUs, if `load_per_core` is enabled.",
+        "load5_per_core" => "`load5` divided by the number of logical CPUs, if `load_per_core` is enabled.",
+        "load15_per_core" => "`load15` divided by the number of logical CPUs, if `load_per_core` is enabled.",
+        "uptime" => "The number of seconds since the last boot.",
+        "boot_time" => "The UNIX timestamp of the last boot.",
+        "host_info" => "Always `1`, labeled with the kernel and operating system of the host.",
+        "random_entropy_available_bits" => "The number of bits of entropy credited to the kernel random number generator, fixed at the pool size since Linux 5.18.",
+        "random_pool_size_bits" => "The size of the entropy pool of the kernel random number generator in bits.",
+        "random_urandom_min_reseed_seconds" => "The minimum number of seconds between reseeds of the urandom pool. Not reported since Linux 5.18.",
+        "memory_active_bytes" => "The number of bytes of active main memory.",
+        "memory_available_bytes" => "The number of bytes of main memory available.",
+        "memory_buffers_bytes" => "The number of bytes of main memory used by buffers.",
+        "memory_cached_bytes" => "The number of bytes of main memory used by cached blocks.",
+        "memory_free_bytes" => "The number of bytes of main memory not used.",
+        "memory_inactive_bytes" => "The number of bytes of main memory that is not active.",
+        "memory_pressure_ratio" => "The share of the last 10 seconds in which some tasks were stalled waiting for memory, from the `avg10` of `/proc/pressure/memory`. Only emitted on kernels with pressure stall information enabled.",
+        "memory_shared_bytes" => "The number of bytes of main memory shared between processes.",
+        "memory_swap_free_bytes" => "The number of free bytes of swap space.",
+        "memory_swapped_in_bytes_total" => "The number of bytes that have been swapped in to main memory.",
+        "memory_swapped_out_bytes_total" => "The number of bytes that have been swapped out from main memory.",
+        "swap_device_total_bytes" => "The size of the swap partition or file in bytes.",
+        "swap_device_used_bytes" => "The number of bytes used on the swap partition or file.",
+        "memory_swap_total_bytes" => "The total number of bytes of swap space.",
+        "memory_swap_used_bytes" => "The number of used bytes of swap space.",
+        "memory_total_bytes" => "The total number of bytes of main memory.",
+        "memory_used_bytes" => "The number of bytes of main memory used by programs or caches.",
+        "memory_wired_bytes" => "The number of wired bytes of main memory.",
+        "network_receive_bytes_total" => "The number of bytes received on this interface.",
+        "network_receive_errs_total" => "The number of errors encountered during receives on this interface.",
+        "network_receive_packets_total" => "The number of packets received on this interface.",
+        "network_receive_compressed_total" => "The number of compressed packets received on this interface.",
+        "network_receive_multicast_total" => "The number of multicast packets received on this interface.",
+        "network_carrier_changes_total" => "The number of times the link of this interface went up or down.",
+        "network_mtu_bytes" => "The maximum transmission unit of this interface, the largest packet it sends without fragmenting it.",
+        "network_tx_queue_length" => "The maximum number of packets queued for transmission on this interface by the driver.",
+        "network_transmit_compressed_total" => "The number of compressed packets transmitted on this interface.",
+        "network_transmit_bytes_total" => "The number of bytes transmitted on this interface.",
+        "network_transmit_errs_total" => "The number of errors encountered during transmits on this interface.",
+        "network_transmit_packets_drop_total" => "The number of packets dropped during transmits on this interface.",
+        "network_transmit_packets_total" => "The number of packets transmitted on this interface.",
+        "tcp_mem_pressure_low" => "The number of pages allocated to TCP below which TCP doesn't regulate its memory allocation, from `/proc/sys/net/ipv4/tcp_mem`.",
+        "tcp_mem_pressure" => "The number of pages allocated to TCP above which TCP moderates its memory consumption.",
+        "tcp_mem_max" => "The number of pages TCP may allocate at most for its sockets.",
+        "tcp_mem_allocated" => "The number of pages currently allocated to TCP sockets.",
+        "network_qdisc_backlog_bytes" => "The number of bytes queued in the queueing discipline.",
+        "network_qdisc_drops_total" => "The number of packets dropped by the queueing discipline.",
+        "zfs_arc_size_bytes" => "The current size of the ZFS adaptive replacement cache.",
+        "zfs_pool_health" => "Always 1, with the current health of the pool in the `health` tag.",
+        "zfs_pool_allocated_bytes" => "The number of bytes allocated in the pool.",
+        "zfs_pool_free_bytes" => "The number of bytes free in the pool.",
+        "vector_process_cpu_seconds_total" => "The CPU time spent by the Vector process, in user and system mode.",
+        "vector_process_resident_memory_bytes" => "The resident memory size of the Vector process.",
+        "vector_process_threads" => "The number of threads of the Vector process.",
+        "vector_process_open_fds" => "The number of open file descriptors of the Vector process.",
+        "listen_socket" => "Always 1, for every port a TCP or UDP socket is listening on.",
+        "container_cpu_seconds_total" => "The CPU time spent by the container, in user and system mode.",
+        "container_memory_usage_bytes" => "The memory usage of the container.",
+        "memory_free_blocks" => "The number of free blocks of 2^order contiguous pages. Few free blocks of higher orders indicate fragmented memory.",
+        "kernel_messages_total" => "The number of messages logged to the kernel ring buffer since boot. Reading them requires the `CAP_SYSLOG` capability when `kernel.dmesg_restrict` is set, without which nothing is reported.",
+        "kernel_buffered_messages" => "The number of messages still in the kernel ring buffer, if `kernel_messages.by_severity` is enabled.",
+        "reboot_required" => "Whether the system needs a reboot to apply updates, 1 if so and 0 otherwise. Not reported if it can't be determined.",
+        "pending_updates" => "The number of pending package updates, as counted by update-notifier. Not reported where it isn't available.",
+        "cpu_cstate_seconds_total" => "The time the CPU spent in the idle state. Not reported without a cpuidle driver, as in most virtual machines.",
+        "softirqs_total" => "The number of softirqs of the type handled by the CPU.",
+        "cpu_throttle_count_total" => "The number of times the temperature of the CPU core or package crossed its throttling threshold. Only reported on Intel CPUs.",
+        "cpu_thermal_throttle_seconds_total" => "The time the CPU core or package spent throttled above its temperature threshold. Only reported on Intel CPUs since Linux 5.7.",
+        "limit_max_open_files" => "The maximum number of files the Vector process may have open, its soft `RLIMIT_NOFILE` limit. This is a limit of the Vector process, not of the host. Not reported if unlimited.",
+        "limit_max_processes" => "The maximum number of processes the user running Vector may create, the soft `RLIMIT_NPROC` limit of the Vector process. This is a limit of the Vector process, not of the host. Not reported if unlimited.",
+        "schedstat_running_seconds_total" => "The time the CPU spent running tasks.",
+        "schedstat_waiting_seconds_total" => "The time tasks spent runnable but waiting on the run queue of the CPU, the scheduling latency.",
+        "schedstat_migrations_total" => "The number of tasks the load balancer moved to the CPU from other CPUs of the scheduling domain.",
+        "scrape_interval_seconds" => "The configured `scrape_interval_secs`, emitted on every scrape.",
+        "host_metrics_build_info" => "Always 1, emitted on every scrape to identify the Vector version and the enabled collectors.",
+        "collected_metrics_count" => "The number of metrics a collector collected in the scrape, if `emit_collected_counts` is enabled.",
+        "collect_error" => "Set to 1 when a collector fails to load its data, if `emit_errors` is enabled.",
+        "cgroup_memory_usage_bytes" => "The number of bytes of memory used by the cgroup.",
+        "cgroup_memory_limit_bytes" => "The memory limit of the cgroup, in bytes. Not emitted when the cgroup is unlimited.",
+        "cgroup_cpu_periods_total" => "The number of CPU bandwidth enforcement periods that have elapsed.",
+        "cgroup_cpu_throttled_periods_total" => "The number of periods in which the cgroup was throttled.",
+        "cgroup_cpu_throttled_seconds_total" => "The total time the cgroup was throttled for, in seconds.",
+        "cgroup_io_read_bytes_total" => "The number of bytes the cgroup read from the block device.",
+        "cgroup_io_write_bytes_total" => "The number of bytes the cgroup wrote to the block device.",
+        "cgroup_io_read_ops_total" => "The number of read operations the cgroup issued to the block device.",
+        "cgroup_io_write_ops_total" => "The number of write operations the cgroup issued to the block device.",
+        "process_cpu_seconds_total" => "The CPU time the process spent in user and kernel mode.",
+        "process_threads" => "The number of threads of the process.",
+        "process_open_fds" => "The number of file descriptors the process has open. Only emitted for processes whose descriptors Vector is allowed to list.",
+        "process_read_bytes_total" => "The number of bytes the process caused to be read from storage. Only emitted when `process.names.includes` is configured, for processes whose IO Vector is allowed to read.",
+        "process_write_bytes_total" => "The number of bytes the process caused to be written to storage. Only emitted when `process.names.includes` is configured, for processes whose IO Vector is allowed to read.",
+        "process_cpu_seconds" => "The distribution of the CPU time spent by the matched processes, with one sample per process. Emitted instead of the per-process metrics when `process.cpu_distribution` is enabled.",
+        "mdraid_disks_active" => "The number of active disks of the array.",
+        "mdraid_disks_total" => "The number of disks the array is configured with.",
+        "mdraid_degraded" => "Whether the array is degraded (1), having fewer active disks than configured, or not (0).",
+        "tcp_listen_backlog" => "The number of connections waiting in the accept queues of the sockets listening on the port.",
+        "tcp_listen_overflow_total" => "The number of times the accept queue of a listening socket overflowed. The kernel only tracks this across all sockets.",
+        "clock_offset_seconds" => "The offset between the system clock and the reference clock, as last reported to the kernel by the time synchronization daemon (e.g. NTP or chrony).",
+        "clock_sync_status" => "Whether the kernel considers the system clock synchronized (1) or not (0).",
+        "tcp_rtt_seconds_min" => "The lowest smoothed round-trip time of the connections in the group.",
+        "tcp_rtt_seconds_max" => "The highest smoothed round-trip time of the connections in the group.",
+        "tcp_rtt_seconds_avg" => "The average smoothed round-trip time of the connections in the group.",
+        "vm_steal_seconds_total" => "The number of seconds, summed across all CPUs, that the hypervisor spent running other guests while this one was runnable.",
+        "vm_balloon_current_bytes" => "The current size of the Xen memory balloon.",
+        "vm_balloon_target_bytes" => "The target size of the Xen memory balloon.",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn describes_default_metrics() {
+        let descriptors = HostMetricsConfig::default().describe_metrics();
+        let cpu = descriptors
+            .iter()
+            .find(|descriptor| descriptor.name == "cpu_seconds_total")
+            .expect("Missing cpu_seconds_total");
+        assert_eq!(cpu.namespace, Some("host".into()));
+        assert_eq!(cpu.kind, MetricType::Counter);
+        assert!(cpu.tags.contains(&"mode"));
+        assert!(cpu.tags.contains(&"cpu"));
+        assert_eq!(
+            cpu.description,
+            Some("The number of CPU seconds accumulated in different operating modes.")
+        );
+
+        // Opt-in collectors are not described unless enabled.
+        assert!(!descriptors
+            .iter()
+            .any(|descriptor| descriptor.name.starts_with("cgroup_")));
+    }
+
+    #[test]
+    fn describes_counters_as_gauges() {
+        let config = HostMetricsConfig {
+            counters_as_gauges: true,
+            ..Default::default()
+        };
+        assert!(config
+            .describe_metrics()
+            .iter()
+            .all(|descriptor| descriptor.kind == MetricType::Gauge));
+    }
+
+    #[tokio::test]
+    async fn describes_collected_metrics() {
+        let config = HostMetricsConfig::default();
+        let described = config
+            .describe_metrics()
+            .into_iter()
+            .map(|descriptor| (descriptor.name, descriptor.kind))
+            .collect::<HashSet<_>>();
+
+        for event in config.capture_metrics().await {
+            let metric = event.into_metric();
+            let kind = match metric.value() {
+                crate::event::metric::MetricValue::Counter { .. } => MetricType::Counter,
+                crate::event::metric::MetricValue::Distribution { .. } => MetricType::Distribution,
+                _ => MetricType::Gauge,
+            };
+            assert!(
+                described.contains(&(metric.name().to_string(), kind)),
+                "{} is not described",
+                metric.name()
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use super::{read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use std::io;
+use std::path::Path;
+
+/// The unit of `/sys/block/<dev>/size`, independent of the sector size of
+/// the device.
+const KERNEL_SECTOR_SIZE: u64 = 512;
+
+impl HostMetricsConfig {
+    pub async fn block_device_metrics(&self) -> Vec<Metric> {
+        let sysfs = self.sysfs_root();
+        let entries = match std::fs::read_dir(sysfs.join("block")) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(error) => {
+                error!(message = "Failed to load block devices.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            let device = entry.file_name().to_string_lossy().into_owned();
+            if !self.disk.devices.contains_path(Some(device.as_ref())) {
+                continue;
+            }
+            let tags = self.disk_tags(&device);
+            if let Some(size) = size_bytes(&sysfs, &device) {
+                metrics.push(self.gauge("disk_size_bytes", timestamp, size as f64, tags.clone()));
+            }
+            if let Some(sector_size) = sector_size_bytes(&sysfs, &device) {
+                metrics.push(self.gauge(
+                    "disk_sector_size_bytes",
+                    timestamp,
+                    sector_size as f64,
+                    tags,
+                ));
+            }
+        }
+        metrics
+    }
+}
+
+/// Resolves the device-mapper name (such as `vg-lv`) of a `dm-*` device.
+pub(super) fn dm_name(sysfs: &Path, device: &str) -> Option<String> {
+    if !device.starts_with("dm-") {
+        return None;
+    }
+    let name = std::fs::read_to_string(sysfs.join("block").join(device).join("dm/name")).ok()?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Resolves the serial number of the drive of a device, as exposed by the
+/// NVMe and virtio drivers, or otherwise as recorded by udev, which also
+/// covers partitions and SCSI disks.
+pub(super) fn serial(sysfs: &Path, udev_data: &Path, device: &str) -> Option<String> {
+    let block = sysfs.join("class/block").join(device);
+    let serial = ["device/serial", "serial"]
+        .iter()
+        .find_map(|file| std::fs::read_to_string(block.join(file)).ok())
+        .or_else(|| {
+            // The udev database is keyed by the `<major>:<minor>` device
+            // number.
+            let number = std::fs::read_to_string(block.join("dev")).ok()?;
+            let data =
+                std::fs::read_to_string(udev_data.join(format!("b{}", number.trim()))).ok()?;
+            udev_serial(&data)
+        })?;
+    let serial = serial.trim();
+    (!serial.is_empty()).then(|| serial.to_string())
+}
+
+/// Finds the serial number among the `E:<key>=<value>` properties of a udev
+/// database entry, preferring the one without the vendor and model.
+fn udev_serial(data: &str) -> Option<String> {
+    let property = |key: &str| {
+        data.lines()
+            .find_map(|line| line.strip_prefix("E:")?.strip_prefix(key))
+    };
+    property("ID_SERIAL_SHORT=")
+        .or_else(|| property("ID_SERIAL="))
+        .map(Into::into)
+}
+
+/// Reads the number of failed I/O requests of a SCSI disk, which is only
+/// exposed by some drivers and never for partitions.
+pub(super) fn io_errors(sysfs: &Path, device: &str) -> Option<u64> {
+    let count =
+        std::fs::read_to_string(sysfs.join("block").join(device).join("device/ioerr_cnt")).ok()?;
+    let count = count.trim();
+    match count.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => count.parse().ok(),
+    }
+}
+
+/// Reads a boolean attribute of a block device, such as `ro`, through
+/// `class/block` so that partitions are found too. Partitions don't report
+/// whether they are `removable`.
+pub(super) fn flag(sysfs: &Path, device: &str, flag: &str) -> Option<bool> {
+    read_u64(&sysfs.join("class/block").join(device).join(flag)).map(|value| value != 0)
+}
+
+/// Reads the capacity of a block device, which the kernel always reports in
+/// 512 byte sectors.
+fn size_bytes(sysfs: &Path, device: &str) -> Option<u64> {
+    let sectors = read_u64(&sysfs.join("block").join(device).join("size"))?;
+    Some(sectors * KERNEL_SECTOR_SIZE)
+}
+
+fn sector_size_bytes(sysfs: &Path, device: &str) -> Option<u64> {
+    read_u64(
+        &sysfs
+            .join("block")
+            .join(device)
+            .join("queue/hw_sector_size"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn resolves_dm_names() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let dm = sysfs.path().join("block/dm-3/dm");
+        fs::create_dir_all(&dm).unwrap();
+        fs::write(dm.join("name"), "vg-lv\n").unwrap();
+
+        assert_eq!(dm_name(sysfs.path(), "dm-3"), Some("vg-lv".into()));
+        // Unresolvable devices fall back to their raw name
+        assert_eq!(dm_name(sysfs.path(), "dm-4"), None);
+        assert_eq!(dm_name(sysfs.path(), "sda"), None);
+    }
+
+    #[test]
+    fn resolves_serials() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let udev_data = tempfile::tempdir().unwrap();
+        let nvme = sysfs.path().join("class/block/nvme0n1/device");
+        fs::create_dir_all(&nvme).unwrap();
+        fs::write(nvme.join("serial"), "S4EWNX0N123456      \n").unwrap();
+        let sda1 = sysfs.path().join("class/block/sda1");
+        fs::create_dir_all(&sda1).unwrap();
+        fs::write(sda1.join("dev"), "8:1\n").unwrap();
+        fs::write(
+            udev_data.path().join("b8:1"),
+            "S:disk/by-id/ata-WDC_WD40EFRX_WD-WCC4E1234567-part1
+E:ID_SERIAL=WDC_WD40EFRX_WD-WCC4E1234567
+E:ID_SERIAL_SHORT=WD-WCC4E1234567
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serial(sysfs.path(), udev_data.path(), "nvme0n1"),
+            Some("S4EWNX0N123456".into())
+        );
+        assert_eq!(
+            serial(sysfs.path(), udev_data.path(), "sda1"),
+            Some("WD-WCC4E1234567".into())
+        );
+        assert_eq!(serial(sysfs.path(), udev_data.path(), "sdb"), None);
+    }
+
+    #[test]
+    fn reads_io_errors() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let device = sysfs.path().join("block/sda/device");
+        fs::create_dir_all(&device).unwrap();
+        fs::write(device.join("ioerr_cnt"), "0x1f\n").unwrap();
+        fs::create_dir_all(sysfs.path().join("block/sdb/device")).unwrap();
+
+        assert_eq!(io_errors(sysfs.path(), "sda"), Some(31));
+        assert_eq!(io_errors(sysfs.path(), "sdb"), None);
+        assert_eq!(io_errors(sysfs.path(), "sda1"), None);
+    }
+
+    #[test]
+    fn reads_flags() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let device = sysfs.path().join("class/block/sdc");
+        fs::create_dir_all(device.join("sdc1")).unwrap();
+        fs::write(device.join("ro"), "1\n").unwrap();
+        fs::write(device.join("removable"), "1\n").unwrap();
+        fs::write(device.join("sdc1/ro"), "0\n").unwrap();
+        std::os::unix::fs::symlink(device.join("sdc1"), sysfs.path().join("class/block/sdc1"))
+            .unwrap();
+
+        assert_eq!(flag(sysfs.path(), "sdc", "ro"), Some(true));
+        assert_eq!(flag(sysfs.path(), "sdc", "removable"), Some(true));
+        assert_eq!(flag(sysfs.path(), "sdc1", "ro"), Some(false));
+        assert_eq!(flag(sysfs.path(), "sdc1", "removable"), None);
+        assert_eq!(flag(sysfs.path(), "sdd", "ro"), None);
+    }
+
+    #[test]
+    fn reads_block_device_sizes() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let device = sysfs.path().join("block/nvme0n1");
+        fs::create_dir_all(device.join("queue")).unwrap();
+        fs::write(device.join("size"), "1953525168\n").unwrap();
+        fs::write(device.join("queue/hw_sector_size"), "4096\n").unwrap();
+
+        assert_eq!(size_bytes(sysfs.path(), "nvme0n1"), Some(1_000_204_886_016));
+        assert_eq!(sector_size_bytes(sysfs.path(), "nvme0n1"), Some(4096));
+        assert_eq!(size_bytes(sysfs.path(), "sda"), None);
+        assert_eq!(sector_size_bytes(sysfs.path(), "sda"), None);
+    }
+}
@@ -0,0 +1,361 @@
+use super::{read_u64, HostMetricsConfig, Mount};
+use crate::event::metric::Metric;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+impl HostMetricsConfig {
+    /// Builds the `filesystem_readonly` gauge of a mount point, if its mount
+    /// options are known.
+    pub(super) fn filesystem_readonly(
+        &self,
+        read_only_mounts: &HashMap<PathBuf, bool>,
+        mount_point: &Path,
+        timestamp: DateTime<Utc>,
+        tags: BTreeMap<String, String>,
+    ) -> Option<Metric> {
+        let read_only = *read_only_mounts.get(mount_point)?;
+        Some(self.gauge(
+            "filesystem_readonly",
+            timestamp,
+            if read_only { 1.0 } else { 0.0 },
+            tags,
+        ))
+    }
+
+    /// Builds the `filesystem_errors_total` counter of the filesystem on a
+    /// block device, if enabled and the filesystem counts its errors.
+    pub(super) fn filesystem_errors(
+        &self,
+        device: Option<&String>,
+        timestamp: DateTime<Utc>,
+        tags: BTreeMap<String, String>,
+    ) -> Option<Metric> {
+        if !self.filesystem.errors {
+            return None;
+        }
+        let count = error_count(&self.sysfs_root(), device?)?;
+        Some(self.counter("filesystem_errors_total", timestamp, count as f64, tags))
+    }
+}
+
+/// Reads the number of errors an ext4 filesystem has detected since it was
+/// created, which it keeps in its superblock. Other filesystems don't count
+/// their errors.
+fn error_count(sysfs: &Path, device: &str) -> Option<u64> {
+    read_u64(&sysfs.join("fs/ext4").join(device).join("errors_count"))
+}
+
+/// Reads whether each mount point is mounted read-only from `/proc/mounts`.
+pub(super) fn read_only_mounts(procfs: &Path, log_rate_secs: u64) -> HashMap<PathBuf, bool> {
+    match std::fs::read_to_string(procfs.join("mounts")) {
+        Ok(mounts) => parse_mounts(&mounts),
+        Err(error) => {
+            error!(message = "Failed to load mount options.", %error, internal_log_rate_secs = log_rate_secs);
+            HashMap::new()
+        }
+    }
+}
+
+/// Parses the mount points and options of a `/proc/mounts` table. Later
+/// mounts hide earlier ones on the same mount point, so they take precedence.
+fn parse_mounts(mounts: &str) -> HashMap<PathBuf, bool> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = unescape(fields.nth(1)?);
+            let options = fields.nth(1)?;
+            let read_only = options.split(',').any(|option| option == "ro");
+            Some((PathBuf::from(mount_point), read_only))
+        })
+        .collect()
+}
+
+/// Resolves the kernel name (such as `sda1` or `dm-0`) of the block device
+/// backing each mount point, as used by the disk metrics. Mounts without a
+/// block device, such as tmpfs or overlay, are left out.
+pub(super) fn mount_devices(
+    procfs: &Path,
+    sysfs: &Path,
+    log_rate_secs: u64,
+) -> HashMap<PathBuf, String> {
+    match std::fs::read_to_string(procfs.join("self/mountinfo")) {
+        Ok(mountinfo) => parse_mountinfo(&mountinfo)
+            .into_iter()
+            .filter_map(|(mount_point, device)| {
+                Some((mount_point, block_device_name(sysfs, &device)?))
+            })
+            .collect(),
+        Err(error) => {
+            error!(message = "Failed to load mount info.", %error, internal_log_rate_secs = log_rate_secs);
+            HashMap::new()
+        }
+    }
+}
+
+/// Inverts `mount_devices`, keeping only the devices mounted on a single
+/// mount point.
+pub(super) fn device_mount_points(
+    mount_devices: &HashMap<PathBuf, String>,
+) -> HashMap<String, PathBuf> {
+    let mut mount_points = HashMap::<String, Option<PathBuf>>::new();
+    for (mount_point, device) in mount_devices {
+        mount_points
+            .entry(device.clone())
+            .and_modify(|existing| *existing = None)
+            .or_insert_with(|| Some(mount_point.clone()));
+    }
+    mount_points
+        .into_iter()
+        .filter_map(|(device, mount_point)| Some((device, mount_point?)))
+        .collect()
+}
+
+/// Parses the mount points and `major:minor` device numbers of a
+/// `/proc/self/mountinfo` table, in mount order.
+fn parse_mountinfo(mountinfo: &str) -> Vec<(PathBuf, String)> {
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.nth(2)?;
+            let mount_point = unescape(fields.nth(1)?);
+            Some((PathBuf::from(mount_point), device.to_string()))
+        })
+        .collect()
+}
+
+/// Reads the mounts of `/proc/self/mountinfo`, which include the bind
+/// mounts the partitions of the platform can leave out.
+pub(super) fn read_mounts(procfs: &Path) -> io::Result<Vec<Mount>> {
+    let mountinfo = std::fs::read_to_string(procfs.join("self/mountinfo"))?;
+    Ok(parse_mountinfo_mounts(&mountinfo))
+}
+
+/// Parses the mount points, filesystem types and sources of a
+/// `/proc/self/mountinfo` table, keeping only the last of the mounts on the
+/// same mount point, which hides the others.
+fn parse_mountinfo_mounts(mountinfo: &str) -> Vec<Mount> {
+    let mounts = mountinfo
+        .lines()
+        .filter_map(|line| {
+            // The optional fields before the separator vary in number.
+            let (mount, source) = line.split_once(" - ")?;
+            let mount_point = unescape(mount.split_whitespace().nth(4)?);
+            let mut source = source.split_whitespace();
+            let file_system = source.next()?.to_string();
+            let device = source
+                .next()
+                .filter(|&device| device != "none")
+                .map(|device| PathBuf::from(unescape(device)));
+            Some(Mount {
+                device,
+                mount_point: PathBuf::from(mount_point),
+                file_system,
+            })
+        })
+        .collect::<Vec<_>>();
+    let last = mounts
+        .iter()
+        .enumerate()
+        .map(|(index, mount)| (mount.mount_point.clone(), index))
+        .collect::<HashMap<_, _>>();
+    mounts
+        .into_iter()
+        .enumerate()
+        .filter(|(index, mount)| last[&mount.mount_point] == *index)
+        .map(|(_, mount)| mount)
+        .collect()
+}
+
+/// Looks up the kernel name of a block device through its
+/// `/sys/dev/block/<major>:<minor>` link.
+fn block_device_name(sysfs: &Path, device: &str) -> Option<String> {
+    let target = std::fs::read_link(sysfs.join("dev/block").join(device)).ok()?;
+    Some(target.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Decodes the octal escapes (such as `\040` for a space) the kernel uses
+/// for whitespace and backslashes in mount points.
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let code = rest
+            .get(index + 1..index + 4)
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(code) => {
+                result.push(code as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_read_only_mounts() {
+        let procfs = tempfile::tempdir().unwrap();
+        std::fs::write(
+            procfs.path().join("mounts"),
+            "/dev/sda1 / ext4 rw,relatime,errors=remount-ro 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+/dev/sdb1 /mnt/data ext4 ro,relatime 0 0
+/dev/sdc1 /mnt/my\\040disk vfat rw,relatime 0 0
+/dev/sdd1 /mnt/data xfs rw,relatime 0 0
+/dev/sde1 /mnt/archive ext4 rw,relatime 0 0
+/dev/sde1 /mnt/archive ext4 ro,relatime 0 0
+",
+        )
+        .unwrap();
+
+        let mounts = read_only_mounts(procfs.path(), 60);
+        assert_eq!(mounts.get(Path::new("/")), Some(&false));
+        assert_eq!(mounts.get(Path::new("/mnt/my disk")), Some(&false));
+        // The last mount on a mount point is the visible one
+        assert_eq!(mounts.get(Path::new("/mnt/data")), Some(&false));
+        assert_eq!(mounts.get(Path::new("/mnt/archive")), Some(&true));
+        assert_eq!(mounts.get(Path::new("/srv")), None);
+    }
+
+    #[test]
+    fn generates_readonly_gauge() {
+        let mounts = parse_mounts("/dev/sdb1 /mnt/data ext4 ro,relatime 0 0\n");
+        let config = HostMetricsConfig::default();
+        let timestamp = Utc::now();
+        let tags = shared::btreemap! { "mountpoint" => "/mnt/data" };
+
+        let metric = config
+            .filesystem_readonly(&mounts, Path::new("/mnt/data"), timestamp, tags.clone())
+            .unwrap();
+        assert_eq!(metric.name(), "filesystem_readonly");
+        assert_eq!(
+            metric.value(),
+            &crate::event::metric::MetricValue::Gauge { value: 1.0 }
+        );
+        assert_eq!(metric.tags(), Some(&tags));
+
+        assert!(config
+            .filesystem_readonly(&mounts, Path::new("/srv"), timestamp, tags)
+            .is_none());
+    }
+
+    #[test]
+    fn reads_error_count() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let ext4 = sysfs.path().join("fs/ext4/sda1");
+        std::fs::create_dir_all(&ext4).unwrap();
+        std::fs::write(ext4.join("errors_count"), "3\n").unwrap();
+        std::fs::create_dir_all(sysfs.path().join("fs/xfs/sdb1")).unwrap();
+
+        assert_eq!(error_count(sysfs.path(), "sda1"), Some(3));
+        assert_eq!(error_count(sysfs.path(), "sdb1"), None);
+        assert_eq!(error_count(sysfs.path(), "sdc1"), None);
+    }
+
+    #[test]
+    fn unescapes_mount_points() {
+        assert_eq!(unescape("/mnt/a\\040b\\134c"), "/mnt/a b\\c");
+        assert_eq!(unescape("/mnt/plain"), "/mnt/plain");
+        assert_eq!(unescape("/mnt/bad\\9"), "/mnt/bad\\9");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn correlates_mounts_and_disks() {
+        let procfs = tempfile::tempdir().unwrap();
+        std::fs::create_dir(procfs.path().join("self")).unwrap();
+        std::fs::write(
+            procfs.path().join("self/mountinfo"),
+            "25 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+26 25 0:5 / /dev rw,nosuid shared:2 - devtmpfs udev rw
+27 25 253:0 / /srv/my\\040data rw,relatime shared:3 - xfs /dev/mapper/vg-data rw
+28 25 8:2 / /mnt/a rw,relatime shared:4 - ext4 /dev/sda2 rw
+29 25 8:2 / /mnt/b rw,relatime shared:4 - ext4 /dev/sda2 rw
+30 25 0:40 / /var/lib/docker/overlay2/x/merged rw - overlay overlay rw
+",
+        )
+        .unwrap();
+        let sysfs = tempfile::tempdir().unwrap();
+        let links = sysfs.path().join("dev/block");
+        std::fs::create_dir_all(&links).unwrap();
+        for (device, target) in &[
+            ("8:1", "../../devices/pci0000:00/ata1/block/sda/sda1"),
+            ("8:2", "../../devices/pci0000:00/ata1/block/sda/sda2"),
+            ("253:0", "../../devices/virtual/block/dm-0"),
+        ] {
+            std::os::unix::fs::symlink(target, links.join(device)).unwrap();
+        }
+
+        let mounts = mount_devices(procfs.path(), sysfs.path(), 60);
+        assert_eq!(mounts.get(Path::new("/")), Some(&"sda1".into()));
+        assert_eq!(mounts.get(Path::new("/srv/my data")), Some(&"dm-0".into()));
+        assert_eq!(mounts.get(Path::new("/dev")), None);
+        assert_eq!(
+            mounts.get(Path::new("/var/lib/docker/overlay2/x/merged")),
+            None
+        );
+
+        // Filesystem metrics tagged with the device of a mount point join the
+        // disk metrics tagged with the mount point of that device.
+        let devices = device_mount_points(&mounts);
+        assert_eq!(devices.get("sda1"), Some(&PathBuf::from("/")));
+        assert_eq!(devices.get("dm-0"), Some(&PathBuf::from("/srv/my data")));
+        // sda2 is mounted twice, so has no single mount point
+        assert_eq!(devices.get("sda2"), None);
+    }
+
+    #[test]
+    fn reads_mounts_from_mountinfo() {
+        let procfs = tempfile::tempdir().unwrap();
+        std::fs::create_dir(procfs.path().join("self")).unwrap();
+        std::fs::write(
+            procfs.path().join("self/mountinfo"),
+            "25 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+26 25 0:5 / /dev rw,nosuid shared:2 - devtmpfs udev rw
+27 25 8:1 /srv/www /var/www rw,relatime shared:1 - ext4 /dev/sda1 rw
+28 25 0:47 / /mnt/share rw - cifs //nas/share rw
+29 25 0:48 / /mnt/share rw - nfs4 nas:/export rw
+30 25 0:49 / /run/user/1000 rw,nosuid,nodev - tmpfs none rw
+",
+        )
+        .unwrap();
+
+        let mounts = read_mounts(procfs.path()).unwrap();
+        let mount_points = mounts
+            .iter()
+            .map(|mount| mount.mount_point.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mount_points,
+            vec!["/", "/dev", "/var/www", "/mnt/share", "/run/user/1000"]
+        );
+        // The bind mount of a directory of the root filesystem
+        assert_eq!(
+            mounts[2],
+            Mount {
+                device: Some("/dev/sda1".into()),
+                mount_point: "/var/www".into(),
+                file_system: "ext4".into(),
+            }
+        );
+        // Only the last mount on a mount point is visible
+        assert_eq!(mounts[3].file_system, "nfs4");
+        assert_eq!(mounts[4].device, None);
+
+        assert!(read_mounts(&procfs.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::fs::OpenOptions;
+use std::io::{self, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Once;
+
+const KMSG: &str = "/dev/kmsg";
+
+/// The syslog names of the kernel log levels.
+const LEVELS: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+impl HostMetricsConfig {
+    pub async fn kernel_message_metrics(&self) -> Vec<Metric> {
+        let messages = match read_kernel_messages(&self.host_path(KMSG)) {
+            Ok(messages) => messages,
+            // Reading the ring buffer requires CAP_SYSLOG when
+            // `kernel.dmesg_restrict` is set, which doesn't change while
+            // running.
+            Err(error) if error.kind() == io::ErrorKind::PermissionDenied => {
+                static WARNED: Once = Once::new();
+                WARNED.call_once(|| {
+                    warn!(message = "Permission denied reading kernel messages, skipping them.", path = KMSG, %error);
+                });
+                return Vec::new();
+            }
+            Err(error) => {
+                error!(message = "Failed to load kernel messages.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = vec![self.counter(
+            "kernel_messages_total",
+            timestamp,
+            messages.total as f64,
+            btreemap! {},
+        )];
+        if self.kernel_messages.by_severity {
+            metrics.extend(
+                LEVELS
+                    .iter()
+                    .zip(&messages.levels)
+                    .map(|(severity, &count)| {
+                        self.gauge(
+                            "kernel_buffered_messages",
+                            timestamp,
+                            count as f64,
+                            btreemap! { "severity" => *severity },
+                        )
+                    }),
+            );
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct KernelMessages {
+    /// The sequence number following the last message, which is the number
+    /// of messages logged since boot, including those already overwritten.
+    total: u64,
+    /// The number of messages still in the ring buffer by log level.
+    levels: [u64; 8],
+}
+
+impl KernelMessages {
+    fn add(&mut self, record: &str) {
+        if let Some((level, sequence)) = parse_record(record) {
+            self.total = self.total.max(sequence + 1);
+            self.levels[level] += 1;
+        }
+    }
+}
+
+/// Reads every record in the kernel ring buffer, without waiting for new
+/// ones.
+fn read_kernel_messages(path: &Path) -> io::Result<KernelMessages> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .custom_flags(nix::fcntl::OFlag::O_NONBLOCK.bits())
+        .open(path)?;
+    let mut messages = KernelMessages::default();
+    // Every read returns a single record, which the kernel limits to 8KiB.
+    let mut buffer = vec![0; 8192];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(length) => messages.add(&String::from_utf8_lossy(&buffer[..length])),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+            // The next record was overwritten while reading, the read
+            // continues with the oldest one left.
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(messages)
+}
+
+/// Parses the log level and sequence number from the
+/// `<prefix>,<sequence>,<timestamp>,<flags>[,...];<message>` header of a
+/// `/dev/kmsg` record, where the prefix holds the syslog facility and level.
+fn parse_record(record: &str) -> Option<(usize, u64)> {
+    let (header, _) = record.split_once(';')?;
+    let mut fields = header.split(',');
+    let prefix = fields.next()?.parse::<u32>().ok()?;
+    let sequence = fields.next()?.parse().ok()?;
+    Some(((prefix & 7) as usize, sequence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_records() {
+        assert_eq!(
+            parse_record("6,1523,4153722,-;usb 1-1: new high-speed USB device number 2\n"),
+            Some((6, 1523))
+        );
+        // The kernel facility is 0, userspace messages written to
+        // /dev/kmsg default to the user facility.
+        assert_eq!(
+            parse_record("12,1524,4153801,-,caller=T1;test\n"),
+            Some((4, 1524))
+        );
+        assert_eq!(parse_record(" SUBSYSTEM=usb\n"), None);
+        assert_eq!(parse_record("x,1,2,-;message"), None);
+
+        let mut messages = KernelMessages::default();
+        messages.add("3,41,100,-;first");
+        messages.add("6,42,200,-;second");
+        messages.add("continuation without a header");
+        assert_eq!(messages.total, 43);
+        assert_eq!(messages.levels, [0, 0, 0, 1, 0, 0, 1, 0]);
+    }
+}
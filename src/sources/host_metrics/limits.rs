@@ -0,0 +1,49 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use nix::libc;
+use shared::btreemap;
+use std::{io, mem};
+
+impl HostMetricsConfig {
+    /// Reports the soft resource limits of the Vector process itself, as
+    /// inherited from the service manager or shell that started it, rather
+    /// than any system-wide limit. Unlimited resources are left out.
+    pub async fn limit_metrics(&self) -> Vec<Metric> {
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for &(name, resource) in &[
+            ("limit_max_open_files", libc::RLIMIT_NOFILE),
+            ("limit_max_processes", libc::RLIMIT_NPROC),
+        ] {
+            let mut limit: libc::rlimit = unsafe { mem::zeroed() };
+            if unsafe { libc::getrlimit(resource, &mut limit) } < 0 {
+                let error = io::Error::last_os_error();
+                error!(message = "Failed to load resource limit.", limit = name, %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+            if limit.rlim_cur != libc::RLIM_INFINITY {
+                metrics.push(self.gauge(name, timestamp, limit.rlim_cur as f64, btreemap! {}));
+            }
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::MetricValue;
+
+    #[tokio::test]
+    async fn reports_open_files_limit() {
+        let metrics = HostMetricsConfig::default().limit_metrics().await;
+        let open_files = metrics
+            .iter()
+            .find(|metric| metric.name() == "limit_max_open_files")
+            .expect("Missing open files limit");
+        match open_files.value() {
+            MetricValue::Gauge { value } => assert!(*value > 0.0),
+            value => panic!("unexpected value {:?}", value),
+        }
+    }
+}
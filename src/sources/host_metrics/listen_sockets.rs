@@ -0,0 +1,200 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// The `st` value of listening sockets in `/proc/net/tcp`.
+const TCP_LISTEN: &str = "0A";
+/// The `st` value of unconnected sockets in `/proc/net/udp`.
+const UDP_UNCONNECTED: &str = "07";
+
+impl HostMetricsConfig {
+    pub async fn listen_socket_metrics(&self) -> Vec<Metric> {
+        let procfs = self.procfs_root();
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+
+        let mut sockets = Vec::new();
+        for &(proto, file, state) in &[
+            ("tcp", "net/tcp", TCP_LISTEN),
+            ("tcp", "net/tcp6", TCP_LISTEN),
+            ("udp", "net/udp", UDP_UNCONNECTED),
+            ("udp", "net/udp6", UDP_UNCONNECTED),
+        ] {
+            match std::fs::read_to_string(procfs.join(file)) {
+                Ok(table) => sockets.extend(
+                    parse_listen_sockets(&table, state)
+                        .into_iter()
+                        .map(|socket| (proto, socket)),
+                ),
+                // IPv6 may be disabled
+                Err(error)
+                    if file.ends_with('6') && error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    error!(message = "Failed to load sockets.", file = %file, %error, internal_log_rate_secs = self.error_log_rate_secs());
+                    metrics.extend(self.collect_error(&error));
+                }
+            }
+        }
+
+        let owners = socket_owners(&procfs);
+        // Sockets bound to both IPv4 and IPv6, or to several addresses, are
+        // reported once.
+        let mut series = BTreeSet::new();
+        for (proto, (port, inode)) in sockets {
+            let port = port.to_string();
+            if !self.listen_sockets.ports.contains_str(Some(&port)) {
+                continue;
+            }
+            let mut tags = btreemap! { "proto" => proto, "port" => port };
+            // The owners of sockets are only known for processes that can
+            // be inspected.
+            if let Some(owner) = owners.get(&inode) {
+                tags.insert("pid".into(), owner.pid.to_string());
+                tags.insert("process".into(), owner.name.clone());
+            }
+            series.insert(tags);
+        }
+        metrics.extend(
+            series
+                .into_iter()
+                .map(|tags| self.gauge("listen_socket", timestamp, 1.0, tags)),
+        );
+        metrics
+    }
+}
+
+/// Parses the ports and inodes of the sockets in the given state from a
+/// `/proc/net/{tcp,udp}[6]` table. UDP sockets are considered listening
+/// when they are not connected to a remote address.
+fn parse_listen_sockets(table: &str, state: &str) -> Vec<(u16, u64)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            // sl local_address rem_address st tx_queue:rx_queue tr:tm->when
+            // retrnsmt uid timeout inode ...
+            if fields.len() < 10 || fields[3] != state {
+                return None;
+            }
+            if !fields[2].chars().all(|c| c == '0' || c == ':') {
+                return None;
+            }
+            let (_, port) = fields[1].rsplit_once(':')?;
+            Some((u16::from_str_radix(port, 16).ok()?, fields[9].parse().ok()?))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct Owner {
+    pid: u32,
+    name: String,
+}
+
+/// Maps socket inodes to the processes holding them open, by reading the
+/// `/proc/<pid>/fd` links of every process that can be inspected.
+fn socket_owners(procfs: &Path) -> HashMap<u64, Owner> {
+    let mut owners = HashMap::new();
+    let entries = match std::fs::read_dir(procfs) {
+        Ok(entries) => entries,
+        Err(_) => return owners,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let pid = match entry.file_name().to_str().and_then(|pid| pid.parse().ok()) {
+            Some(pid) => pid,
+            None => continue,
+        };
+        let fds = match std::fs::read_dir(entry.path().join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+        let inodes = fds
+            .filter_map(Result::ok)
+            .filter_map(|fd| std::fs::read_link(fd.path()).ok())
+            .filter_map(|target| socket_inode(target.to_str()?))
+            .collect::<Vec<_>>();
+        if inodes.is_empty() {
+            continue;
+        }
+        let name = std::fs::read_to_string(entry.path().join("comm"))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default();
+        for inode in inodes {
+            owners.insert(
+                inode,
+                Owner {
+                    pid,
+                    name: name.clone(),
+                },
+            );
+        }
+    }
+    owners
+}
+
+/// Parses the inode of a `socket:[<inode>]` file descriptor link.
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0500000A:0016 2200A8C0:D431 01 00000000:00000000 02:000AFC8A 00000000     0        0 31338 2 0000000000000000 20 4 30 10 -1
+";
+
+    #[test]
+    fn parses_listen_sockets() {
+        assert_eq!(parse_listen_sockets(TCP, TCP_LISTEN), vec![(22, 31337)]);
+
+        let udp = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  7: 3500007F:0035 00000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 20941 2 0000000000000000 0
+  8: 0F02000A:E4B5 08080808:0035 01 00000000:00000000 00:00000000 00000000  1000        0 20942 2 0000000000000000 0
+";
+        assert_eq!(
+            parse_listen_sockets(udp, UDP_UNCONNECTED),
+            vec![(53, 20941)]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn maps_sockets_to_processes() {
+        let procfs = tempfile::tempdir().unwrap();
+        let process = procfs.path().join("812");
+        fs::create_dir_all(process.join("fd")).unwrap();
+        fs::write(process.join("comm"), "sshd\n").unwrap();
+        std::os::unix::fs::symlink("socket:[31337]", process.join("fd/3")).unwrap();
+        std::os::unix::fs::symlink("/dev/null", process.join("fd/0")).unwrap();
+
+        let owners = socket_owners(procfs.path());
+        assert_eq!(owners.len(), 1);
+        let (port, inode) = parse_listen_sockets(TCP, TCP_LISTEN)[0];
+        assert_eq!(port, 22);
+        assert_eq!(
+            owners.get(&inode),
+            Some(&Owner {
+                pid: 812,
+                name: "sshd".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn parses_socket_inodes() {
+        assert_eq!(socket_inode("socket:[31337]"), Some(31337));
+        assert_eq!(socket_inode("pipe:[31337]"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+}
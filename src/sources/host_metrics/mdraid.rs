@@ -0,0 +1,130 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::io;
+
+impl HostMetricsConfig {
+    pub async fn mdraid_metrics(&self) -> Vec<Metric> {
+        let mdstat = match std::fs::read_to_string(self.procfs_root().join("mdstat")) {
+            Ok(mdstat) => mdstat,
+            // The file only exists when the md driver is loaded.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(error) => {
+                error!(message = "Failed to load mdraid statistics.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        parse_mdstat(&mdstat)
+            .into_iter()
+            .flat_map(|array| {
+                let tags = btreemap! { "device" => array.device };
+                vec![
+                    self.gauge(
+                        "mdraid_disks_active",
+                        timestamp,
+                        array.active as f64,
+                        tags.clone(),
+                    ),
+                    self.gauge(
+                        "mdraid_disks_total",
+                        timestamp,
+                        array.total as f64,
+                        tags.clone(),
+                    ),
+                    self.gauge(
+                        "mdraid_degraded",
+                        timestamp,
+                        if array.active < array.total { 1.0 } else { 0.0 },
+                        tags,
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct MdArray {
+    device: String,
+    active: u32,
+    total: u32,
+}
+
+/// Parses the arrays of `/proc/mdstat`, skipping inactive arrays which have
+/// no disk status.
+fn parse_mdstat(mdstat: &str) -> Vec<MdArray> {
+    let mut arrays = Vec::new();
+    let mut device = None;
+    for line in mdstat.lines() {
+        if let Some((name, _)) = line.split_once(" : ") {
+            let name = name.trim();
+            device = name.starts_with("md").then(|| name.to_string());
+            continue;
+        }
+        if let Some((total, active)) = device.as_ref().and_then(|_| parse_disk_status(line)) {
+            arrays.push(MdArray {
+                device: device.take().unwrap(),
+                active,
+                total,
+            });
+        }
+    }
+    arrays
+}
+
+/// Finds the `[total/active]` disk counts in an array status line such as
+/// `1046528 blocks super 1.2 [2/1] [U_]`.
+fn parse_disk_status(line: &str) -> Option<(u32, u32)> {
+    line.split_whitespace().find_map(|field| {
+        let (total, active) = field
+            .strip_prefix('[')?
+            .strip_suffix(']')?
+            .split_once('/')?;
+        Some((total.parse().ok()?, active.parse().ok()?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_degraded_mdstat() {
+        let mdstat = "Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid5 sdd1[3] sdc1[1] sdb1[0]
+      3906764800 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/3] [UUU]
+      bitmap: 0/15 pages [0KB], 65536KB chunk
+
+md0 : active raid1 sdf1[1](F) sde1[0]
+      1046528 blocks super 1.2 [2/1] [U_]
+
+md127 : inactive sdg[0](S)
+      976631512 blocks super 1.2
+
+unused devices: <none>
+";
+        assert_eq!(
+            parse_mdstat(mdstat),
+            vec![
+                MdArray {
+                    device: "md1".into(),
+                    active: 3,
+                    total: 3,
+                },
+                MdArray {
+                    device: "md0".into(),
+                    active: 1,
+                    total: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_empty_mdstat() {
+        let mdstat = "Personalities :\nunused devices: <none>\n";
+        assert!(parse_mdstat(mdstat).is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::collections::HashMap;
+
+impl HostMetricsConfig {
+    /// Derives both the memory and the swap metrics from a single read of
+    /// `/proc/meminfo`, which heim would otherwise read once for each.
+    pub async fn meminfo_metrics(&self) -> Vec<Metric> {
+        let meminfo = match std::fs::read_to_string(self.procfs_root().join("meminfo")) {
+            Ok(meminfo) => parse_meminfo(&meminfo),
+            Err(error) => {
+                error!(message = "Failed to load memory info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+        let (swapped_in, swapped_out) = std::fs::read_to_string(self.procfs_root().join("vmstat"))
+            .map(|vmstat| parse_swapped_pages(&vmstat))
+            .unwrap_or_default();
+        let page_size = page_size();
+
+        let timestamp = self.now();
+        let field = |name: &str| meminfo.get(name).copied().unwrap_or(0);
+        let total = field("MemTotal");
+        let free = field("MemFree");
+        let buffers = field("Buffers");
+        let cached = field("Cached");
+        // Kernels before 3.14 don't estimate the available memory.
+        let available = meminfo
+            .get("MemAvailable")
+            .copied()
+            .unwrap_or(free + buffers + cached);
+        let swap_total = field("SwapTotal");
+        let swap_free = field("SwapFree");
+
+        vec![
+            ("memory_total_bytes", total),
+            ("memory_free_bytes", free),
+            ("memory_available_bytes", available),
+            ("memory_active_bytes", field("Active")),
+            ("memory_buffers_bytes", buffers),
+            ("memory_cached_bytes", cached),
+            ("memory_shared_bytes", field("Shmem")),
+            (
+                "memory_used_bytes",
+                used_memory(&meminfo, self.memory.free_compatible),
+            ),
+            ("memory_swap_free_bytes", swap_free),
+            ("memory_swap_total_bytes", swap_total),
+            (
+                "memory_swap_used_bytes",
+                swap_total.saturating_sub(swap_free),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, value)| self.gauge(name, timestamp, value as f64, btreemap! {}))
+        .chain(vec![
+            self.counter(
+                "memory_swapped_in_bytes_total",
+                timestamp,
+                (swapped_in * page_size) as f64,
+                btreemap! {},
+            ),
+            self.counter(
+                "memory_swapped_out_bytes_total",
+                timestamp,
+                (swapped_out * page_size) as f64,
+                btreemap! {},
+            ),
+        ])
+        .collect()
+    }
+
+    /// Reports the share of the last 10 seconds in which some tasks were
+    /// stalled on memory, from the pressure stall information of kernels
+    /// since 4.20.
+    pub async fn memory_pressure_metrics(&self) -> Vec<Metric> {
+        let pressure = match std::fs::read_to_string(self.procfs_root().join("pressure/memory")) {
+            Ok(pressure) => pressure,
+            // PSI is unavailable on older kernels, or disabled.
+            Err(_) => return Vec::new(),
+        };
+        match parse_pressure_ratio(&pressure) {
+            Some(ratio) => {
+                vec![self.gauge("memory_pressure_ratio", self.now(), ratio, btreemap! {})]
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl HostMetricsConfig {
+    /// Reports the size and usage of each swap partition and file.
+    pub async fn swap_device_metrics(&self) -> Vec<Metric> {
+        let swaps = match std::fs::read_to_string(self.procfs_root().join("swaps")) {
+            Ok(swaps) => swaps,
+            Err(error) => {
+                error!(message = "Failed to load swap devices.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for swap in parse_swaps(&swaps) {
+            let tags = btreemap! {
+                "device" => swap.device,
+                "type" => swap.kind,
+            };
+            metrics.push(self.gauge(
+                "swap_device_total_bytes",
+                timestamp,
+                swap.size_bytes as f64,
+                tags.clone(),
+            ));
+            metrics.push(self.gauge(
+                "swap_device_used_bytes",
+                timestamp,
+                swap.used_bytes as f64,
+                tags,
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct SwapDevice {
+    device: String,
+    /// Either `partition` or `file`.
+    kind: String,
+    size_bytes: u64,
+    used_bytes: u64,
+}
+
+/// Parses the devices listed in `/proc/swaps`, skipping the header. The
+/// sizes are given in kB, and spaces in the names are escaped as `\040`.
+fn parse_swaps(swaps: &str) -> Vec<SwapDevice> {
+    swaps
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.replace("\\040", " ");
+            let kind = fields.next()?.to_string();
+            let size = fields.next()?.parse::<u64>().ok()?;
+            let used = fields.next()?.parse::<u64>().ok()?;
+            Some(SwapDevice {
+                device,
+                kind,
+                size_bytes: size * 1024,
+                used_bytes: used * 1024,
+            })
+        })
+        .collect()
+}
+
+/// Parses the `avg10` percentage of the `some` line of a pressure file into
+/// a ratio.
+pub(super) fn parse_pressure_ratio(pressure: &str) -> Option<f64> {
+    let line = pressure
+        .lines()
+        .find_map(|line| line.strip_prefix("some "))?;
+    let avg10 = line
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))?;
+    Some(avg10.parse::<f64>().ok()? / 100.0)
+}
+
+/// Parses the fields of `/proc/meminfo`, converting those given in kB to
+/// bytes.
+fn parse_meminfo(meminfo: &str) -> HashMap<String, u64> {
+    meminfo
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let mut value = value.split_whitespace();
+            let amount = value.next()?.parse::<u64>().ok()?;
+            let amount = match value.next() {
+                Some("kB") => amount * 1024,
+                _ => amount,
+            };
+            Some((name.to_string(), amount))
+        })
+        .collect()
+}
+
+/// Computes the used memory the way heim does, as the memory that is
+/// neither free nor used for buffers or the page cache. `free` also counts
+/// reclaimable slab memory as cache, so its figure is lower.
+fn used_memory(meminfo: &HashMap<String, u64>, free_compatible: bool) -> u64 {
+    let field = |name: &str| meminfo.get(name).copied().unwrap_or(0);
+    let mut cached = field("Cached");
+    if free_compatible {
+        cached += field("SReclaimable");
+    }
+    field("MemTotal").saturating_sub(field("MemFree") + field("Buffers") + cached)
+}
+
+/// Returns the number of pages swapped in and out from `/proc/vmstat`.
+fn parse_swapped_pages(vmstat: &str) -> (u64, u64) {
+    let mut pages = (0, 0);
+    for line in vmstat.lines() {
+        match line.split_once(' ') {
+            Some(("pswpin", value)) => pages.0 = value.trim().parse().unwrap_or(0),
+            Some(("pswpout", value)) => pages.1 = value.trim().parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    pages
+}
+
+fn page_size() -> u64 {
+    nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE)
+        .ok()
+        .flatten()
+        .map(|size| size as u64)
+        .unwrap_or(4096)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::MetricValue;
+
+    #[test]
+    fn parses_swaps() {
+        let swaps = parse_swaps(
+            "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/nvme0n1p3                          partition\t8388604\t\t524288\t\t-2
+/var/lib/swap\\040file                  file\t\t2097148\t\t0\t\t-3
+",
+        );
+        assert_eq!(
+            swaps,
+            vec![
+                SwapDevice {
+                    device: "/dev/nvme0n1p3".into(),
+                    kind: "partition".into(),
+                    size_bytes: 8_589_930_496,
+                    used_bytes: 536_870_912,
+                },
+                SwapDevice {
+                    device: "/var/lib/swap file".into(),
+                    kind: "file".into(),
+                    size_bytes: 2_147_479_552,
+                    used_bytes: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            parse_swaps("Filename\tType\tSize\tUsed\tPriority\n"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn parses_meminfo() {
+        let meminfo = parse_meminfo(
+            "MemTotal:       16318412 kB
+MemFree:         1042428 kB
+HugePages_Total:       0
+",
+        );
+        assert_eq!(meminfo["MemTotal"], 16_710_053_888);
+        assert_eq!(meminfo["MemFree"], 1_067_446_272);
+        assert_eq!(meminfo["HugePages_Total"], 0);
+    }
+
+    #[test]
+    fn computes_used_memory() {
+        let meminfo = parse_meminfo(
+            "MemTotal:       16318412 kB
+MemFree:         1042428 kB
+Buffers:          812116 kB
+Cached:          7361776 kB
+SReclaimable:     905032 kB
+",
+        );
+        // total - free - buffers - (cached + reclaimable slab), as `free`
+        // reports it.
+        assert_eq!(used_memory(&meminfo, true), 6_197_060 * 1024);
+        assert_eq!(used_memory(&meminfo, false), 7_102_092 * 1024);
+        assert_eq!(used_memory(&HashMap::new(), true), 0);
+    }
+
+    #[test]
+    fn parses_swapped_pages() {
+        let vmstat = "pgpgout 123\npswpin 17\npswpout 42\n";
+        assert_eq!(parse_swapped_pages(vmstat), (17, 42));
+        assert_eq!(parse_swapped_pages(""), (0, 0));
+    }
+
+    #[test]
+    fn parses_pressure_ratio() {
+        let pressure = "some avg10=12.50 avg60=3.07 avg300=0.68 total=4173812
+full avg10=1.25 avg60=0.30 avg300=0.06 total=1523371
+";
+        assert_eq!(parse_pressure_ratio(pressure), Some(0.125));
+        assert_eq!(parse_pressure_ratio("full avg10=1.25\n"), None);
+        assert_eq!(parse_pressure_ratio(""), None);
+    }
+
+    #[tokio::test]
+    async fn matches_heim_metrics() {
+        let config = HostMetricsConfig::default();
+        let mut expected = config.memory_metrics().await;
+        expected.extend(config.swap_metrics().await);
+        let metrics = config.meminfo_metrics().await;
+
+        let names = |metrics: &[Metric]| {
+            metrics
+                .iter()
+                .map(|metric| {
+                    let is_counter = matches!(metric.value(), MetricValue::Counter { .. });
+                    (metric.name().to_string(), is_counter)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&metrics), names(&expected));
+
+        // The totals don't change between the reads.
+        for name in &["memory_total_bytes", "memory_swap_total_bytes"] {
+            let value = |metrics: &[Metric]| {
+                metrics
+                    .iter()
+                    .find(|metric| metric.name() == *name)
+                    .map(|metric| metric.value().clone())
+            };
+            assert_eq!(value(&metrics), value(&expected), "name={}", name);
+        }
+    }
+}
@@ -0,0 +1,5969 @@
+#[cfg(target_os = "linux")]
+use crate::event::metric::{Sample, StatisticKind};
+use crate::{
+    config::{DataType, SourceConfig, SourceContext, SourceDescription},
+    event::{
+        metric::{Metric, MetricKind, MetricSeries, MetricValue},
+        Event,
+    },
+    internal_events::HostMetricsEventReceived,
+    pipeline::ClosedError,
+    shutdown::ShutdownSignal,
+    Pipeline,
+};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use futures::{future, stream, Sink, SinkExt, Stream, StreamExt};
+use glob::{Pattern, PatternError};
+#[cfg(target_os = "macos")]
+use heim::memory::os::macos::MemoryExt;
+#[cfg(not(target_os = "windows"))]
+use heim::memory::os::SwapExt;
+#[cfg(target_os = "windows")]
+use heim::net::os::windows::IoCountersExt;
+#[cfg(not(target_os = "windows"))]
+use heim::units::ratio::ratio;
+#[cfg(target_os = "linux")]
+use heim::{
+    cpu::os::linux::CpuTimeExt, memory::os::linux::MemoryExt, net::os::linux::IoCountersExt,
+};
+use heim::{
+    units::{information::byte, time::second},
+    Error,
+};
+use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use shared::btreemap;
+use snafu::Snafu;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+
+#[cfg(target_os = "linux")]
+mod buddyinfo;
+#[cfg(target_os = "linux")]
+mod cgroups;
+#[cfg(target_os = "linux")]
+mod clock;
+#[cfg(feature = "sources-host_metrics-cloud_metadata")]
+mod cloud_metadata;
+#[cfg(feature = "sources-host_metrics-docker")]
+mod container;
+#[cfg(target_os = "linux")]
+mod cpuidle;
+mod descriptors;
+#[cfg(target_os = "linux")]
+mod disk;
+#[cfg(target_os = "linux")]
+mod filesystem;
+#[cfg(target_os = "linux")]
+mod kernel_messages;
+#[cfg(unix)]
+mod limits;
+#[cfg(target_os = "linux")]
+mod listen_sockets;
+#[cfg(target_os = "linux")]
+mod mdraid;
+#[cfg(target_os = "linux")]
+mod memory;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+mod netlink;
+#[cfg(target_os = "linux")]
+mod process;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+mod qdisc;
+#[cfg(target_os = "linux")]
+mod random;
+#[cfg(target_os = "linux")]
+mod schedstat;
+#[cfg(target_os = "linux")]
+mod softirqs;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+mod tcp;
+#[cfg(target_os = "linux")]
+mod tcp_listen;
+#[cfg(target_os = "linux")]
+mod tcp_mem;
+#[cfg(target_os = "linux")]
+mod thermal_throttle;
+mod top_n;
+#[cfg(target_os = "linux")]
+mod updates;
+#[cfg(target_os = "linux")]
+mod vm;
+#[cfg(all(target_os = "linux", feature = "sources-host_metrics-zfs"))]
+mod zfs;
+
+pub use descriptors::{MetricDescriptor, MetricType};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Collector {
+    Cpu,
+    Disk,
+    Filesystem,
+    Load,
+    Host,
+    Memory,
+    Network,
+    Vm,
+    Cgroup,
+    Tcp,
+    Clock,
+    Mdraid,
+    Process,
+    #[serde(rename = "tcp_listen")]
+    TcpListen,
+    #[serde(rename = "block_device")]
+    BlockDevice,
+    #[serde(rename = "net_dev_queue")]
+    NetDevQueue,
+    Zfs,
+    #[serde(rename = "listen_sockets")]
+    ListenSockets,
+    #[serde(rename = "kernel_messages")]
+    KernelMessages,
+    Buddyinfo,
+    Container,
+    Updates,
+    Schedstat,
+    Cpuidle,
+    Softirqs,
+    Limits,
+    #[serde(rename = "thermal_throttle")]
+    ThermalThrottle,
+}
+
+impl Collector {
+    const ALL: &'static [Collector] = &[
+        Collector::Cpu,
+        Collector::Disk,
+        Collector::Filesystem,
+        Collector::Load,
+        Collector::Host,
+        Collector::Memory,
+        Collector::Network,
+        Collector::Vm,
+        Collector::Cgroup,
+        Collector::Tcp,
+        Collector::Clock,
+        Collector::Mdraid,
+        Collector::Process,
+        Collector::TcpListen,
+        Collector::BlockDevice,
+        Collector::NetDevQueue,
+        Collector::Zfs,
+        Collector::ListenSockets,
+        Collector::KernelMessages,
+        Collector::Buddyinfo,
+        Collector::Container,
+        Collector::Updates,
+        Collector::Schedstat,
+        Collector::Cpuidle,
+        Collector::Softirqs,
+        Collector::Limits,
+        Collector::ThermalThrottle,
+    ];
+
+    /// The collectors used when `collectors` is not configured.
+    const DEFAULT: &'static [Collector] = &[
+        Collector::Cpu,
+        Collector::Disk,
+        Collector::Filesystem,
+        Collector::Load,
+        Collector::Host,
+        Collector::Memory,
+        Collector::Network,
+    ];
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Collector::Cpu => "cpu",
+            Collector::Disk => "disk",
+            Collector::Filesystem => "filesystem",
+            Collector::Load => "load",
+            Collector::Host => "host",
+            Collector::Memory => "memory",
+            Collector::Network => "network",
+            Collector::Vm => "vm",
+            Collector::Cgroup => "cgroup",
+            Collector::Tcp => "tcp",
+            Collector::Clock => "clock",
+            Collector::Mdraid => "mdraid",
+            Collector::Process => "process",
+            Collector::TcpListen => "tcp_listen",
+            Collector::BlockDevice => "block_device",
+            Collector::NetDevQueue => "net_dev_queue",
+            Collector::Zfs => "zfs",
+            Collector::ListenSockets => "listen_sockets",
+            Collector::KernelMessages => "kernel_messages",
+            Collector::Buddyinfo => "buddyinfo",
+            Collector::Container => "container",
+            Collector::Updates => "updates",
+            Collector::Schedstat => "schedstat",
+            Collector::Cpuidle => "cpuidle",
+            Collector::Softirqs => "softirqs",
+            Collector::Limits => "limits",
+            Collector::ThermalThrottle => "thermal_throttle",
+        }
+    }
+
+    /// Whether this build can collect anything for the collector, which
+    /// depends on the platform and on the enabled features.
+    const fn is_available(self) -> bool {
+        match self {
+            Collector::Cpu
+            | Collector::Disk
+            | Collector::Filesystem
+            | Collector::Load
+            | Collector::Host
+            | Collector::Memory
+            | Collector::Network => true,
+            Collector::Vm
+            | Collector::Cgroup
+            | Collector::Clock
+            | Collector::Mdraid
+            | Collector::Process
+            | Collector::TcpListen
+            | Collector::BlockDevice
+            | Collector::ListenSockets
+            | Collector::KernelMessages
+            | Collector::Buddyinfo
+            | Collector::Updates
+            | Collector::Schedstat
+            | Collector::Cpuidle
+            | Collector::Softirqs
+            | Collector::ThermalThrottle => cfg!(target_os = "linux"),
+            Collector::Tcp | Collector::NetDevQueue => cfg!(all(
+                target_os = "linux",
+                feature = "sources-host_metrics-netlink"
+            )),
+            Collector::Zfs => cfg!(all(
+                target_os = "linux",
+                feature = "sources-host_metrics-zfs"
+            )),
+            Collector::Container => cfg!(feature = "sources-host_metrics-docker"),
+            Collector::Limits => cfg!(unix),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct FilterList {
+    includes: Option<Vec<PatternWrapper>>,
+    excludes: Option<Vec<PatternWrapper>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct CpuConfig {
+    /// Also report the CPUs taken offline through hotplug, tagging every CPU
+    /// with whether it is online.
+    #[serde(default)]
+    include_offline: bool,
+    /// Also report `cpu_saturation_ratio`, a single signal of how saturated
+    /// the CPUs are for autoscalers.
+    #[serde(default)]
+    saturation: bool,
+    /// Reports the median, 95th percentile and maximum of the CPU times
+    /// across the CPUs for each mode, instead of the times of every CPU.
+    #[serde(default)]
+    percentiles: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct DiskConfig {
+    #[serde(default)]
+    devices: FilterList,
+    #[serde(default)]
+    resolve_dm_names: bool,
+    /// Tags the devices with the serial number of the drive.
+    #[serde(default)]
+    serial_tags: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct FilesystemConfig {
+    #[serde(default)]
+    devices: FilterList,
+    #[serde(default = "default_filesystems")]
+    filesystems: FilterList,
+    #[serde(default = "default_mountpoints")]
+    mountpoints: FilterList,
+    /// The used ratio above which a mount is reported as near full.
+    #[serde(default = "default_near_full_threshold")]
+    near_full_threshold: f64,
+    /// Paths to report the usage of the containing filesystems of, instead
+    /// of enumerating every mounted filesystem.
+    #[serde(default)]
+    paths: Vec<PathBuf>,
+    /// How long enumerating the mounted filesystems may take, defaulting to
+    /// the scrape interval.
+    enumeration_timeout_secs: Option<u64>,
+    /// Maps the types of FUSE and overlay filesystems to the names of the
+    /// filesystems they implement, keeping the reported type in
+    /// `raw_fstype`.
+    #[serde(default)]
+    normalize_types: bool,
+    /// Path prefixes of the mounts in each class, reported in the `class`
+    /// tag. Mounts are put in the class with the longest matching prefix,
+    /// the first listed on ties, or `other` without a match.
+    #[serde(default)]
+    classes: IndexMap<String, Vec<PathBuf>>,
+    /// Leaves out mounts smaller than this, such as boot and EFI system
+    /// partitions. Doesn't apply to the configured `paths`.
+    #[serde(default)]
+    min_size_bytes: u64,
+    /// Where the mounted filesystems are enumerated from.
+    #[serde(default)]
+    mount_source: MountSource,
+    /// Also emits the number of errors the filesystems have detected, where
+    /// they count them, as only ext4 does.
+    #[serde(default)]
+    errors: bool,
+}
+
+impl Default for FilesystemConfig {
+    fn default() -> Self {
+        Self {
+            devices: FilterList::default(),
+            filesystems: default_filesystems(),
+            mountpoints: default_mountpoints(),
+            near_full_threshold: default_near_full_threshold(),
+            paths: Vec::new(),
+            enumeration_timeout_secs: None,
+            normalize_types: false,
+            classes: IndexMap::new(),
+            min_size_bytes: 0,
+            mount_source: MountSource::default(),
+            errors: false,
+        }
+    }
+}
+
+/// Where the mounted filesystems are enumerated from.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum MountSource {
+    /// The partitions as listed by the platform.
+    Partitions,
+    /// Every mount in `/proc/self/mountinfo`, including bind mounts. Linux
+    /// only, falling back to the partitions elsewhere.
+    Mountinfo,
+}
+
+impl Default for MountSource {
+    fn default() -> Self {
+        Self::Partitions
+    }
+}
+
+/// A mounted filesystem, whichever way it was enumerated.
+#[derive(Clone, Debug, PartialEq)]
+struct Mount {
+    /// The mounted device or other source, unset if it is `none`.
+    device: Option<PathBuf>,
+    mount_point: PathBuf,
+    file_system: String,
+}
+
+impl From<heim::disk::Partition> for Mount {
+    fn from(partition: heim::disk::Partition) -> Self {
+        Self {
+            device: partition.device().map(PathBuf::from),
+            mount_point: partition.mount_point().to_path_buf(),
+            file_system: partition.file_system().as_str().to_string(),
+        }
+    }
+}
+
+/// Excludes pseudo and container filesystems, matching the defaults of the
+/// Prometheus node exporter. Replaced entirely when `filesystems` is set.
+fn default_filesystems() -> FilterList {
+    FilterList::excluding(&[
+        "autofs",
+        "binfmt_misc",
+        "bpf",
+        "cgroup",
+        "cgroup2",
+        "configfs",
+        "debugfs",
+        "devpts",
+        "devtmpfs",
+        "fusectl",
+        "hugetlbfs",
+        "iso9660",
+        "mqueue",
+        "nsfs",
+        "overlay",
+        "proc",
+        "procfs",
+        "pstore",
+        "rpc_pipefs",
+        "securityfs",
+        "selinuxfs",
+        "squashfs",
+        "sysfs",
+        "tracefs",
+    ])
+}
+
+/// Excludes kernel and container runtime mountpoints, matching the defaults
+/// of the Prometheus node exporter. Replaced entirely when `mountpoints` is
+/// set.
+fn default_mountpoints() -> FilterList {
+    FilterList::excluding(&[
+        "/dev",
+        "/dev/*",
+        "/proc",
+        "/proc/*",
+        "/sys",
+        "/sys/*",
+        "/run/credentials/*",
+        "/var/lib/docker/*",
+        "/var/lib/containers/storage/*",
+    ])
+}
+
+const fn default_near_full_threshold() -> f64 {
+    0.9
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct NetworkConfig {
+    devices: Option<FilterList>,
+    /// Exclude the loopback and virtual interfaces when `devices` isn't set,
+    /// defaulting to true.
+    exclude_virtual: Option<bool>,
+    /// Tags the byte and packet counters with the protocol `family` they
+    /// count, adding the IPv6 counters of the interfaces where available.
+    #[serde(default)]
+    split_family: bool,
+    /// Emits the counters as increments since the previous scrape rather
+    /// than as their cumulative values.
+    #[serde(default)]
+    incremental: bool,
+    /// Aliases of interfaces, by name, reported in the `alias` tag.
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+    /// Reports the aliases in the `device` tag instead of the interface
+    /// names.
+    #[serde(default)]
+    alias_as_device: bool,
+}
+
+impl NetworkConfig {
+    /// The device filter, which is the configured one if any.
+    fn devices(&self) -> Cow<'_, FilterList> {
+        match &self.devices {
+            Some(devices) => Cow::Borrowed(devices),
+            None if self.exclude_virtual.unwrap_or(true) => Cow::Owned(default_network_devices()),
+            None => Cow::Owned(FilterList::default()),
+        }
+    }
+}
+
+/// Excludes the loopback interface and the virtual interfaces of bridges,
+/// container runtimes and hypervisors.
+fn default_network_devices() -> FilterList {
+    FilterList::excluding(&[
+        "lo", "br-*", "cali*", "cni*", "docker*", "flannel*", "veth*", "virbr*", "vnet*",
+    ])
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ProcessConfig {
+    #[serde(default)]
+    names: FilterList,
+    /// Emit the CPU time of all matched processes as a single distribution
+    /// instead of per-process series.
+    #[serde(default)]
+    cpu_distribution: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct CgroupsConfig {
+    /// The cgroup mount root, defaulting to `fs/cgroup` under the sysfs root.
+    base_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct TcpConfig {
+    #[serde(default)]
+    group_by: TcpGroupBy,
+    #[serde(default = "default_ipv4_prefix_length")]
+    ipv4_prefix_length: u8,
+    #[serde(default = "default_ipv6_prefix_length")]
+    ipv6_prefix_length: u8,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        Self {
+            group_by: TcpGroupBy::default(),
+            ipv4_prefix_length: default_ipv4_prefix_length(),
+            ipv6_prefix_length: default_ipv6_prefix_length(),
+        }
+    }
+}
+
+const fn default_ipv4_prefix_length() -> u8 {
+    24
+}
+
+const fn default_ipv6_prefix_length() -> u8 {
+    64
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct TcpListenConfig {
+    #[serde(default)]
+    ports: FilterList,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ListenSocketsConfig {
+    #[serde(default)]
+    ports: FilterList,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct MemoryConfig {
+    /// Computes `memory_used_bytes` as `free` does, counting reclaimable
+    /// slab memory as cache.
+    #[serde(default)]
+    free_compatible: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct BuddyinfoConfig {
+    #[serde(default)]
+    nodes: FilterList,
+    #[serde(default)]
+    zones: FilterList,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct SoftirqsConfig {
+    #[serde(default)]
+    types: FilterList,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ContainerConfig {
+    /// The Docker API endpoint, defaulting to `DOCKER_HOST` or the local
+    /// socket.
+    docker_host: Option<String>,
+    #[cfg(feature = "sources-host_metrics-docker")]
+    tls: Option<crate::docker::DockerTlsConfig>,
+    #[serde(default)]
+    names: FilterList,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct KernelMessagesConfig {
+    /// Also report the messages still in the ring buffer by severity.
+    #[serde(default)]
+    by_severity: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct UpdatesConfig {
+    /// The file flagging a pending reboot, defaulting to
+    /// `/var/run/reboot-required`.
+    reboot_required_path: Option<PathBuf>,
+    /// The summary of the pending updates written by update-notifier,
+    /// defaulting to `/var/lib/update-notifier/updates-available`.
+    updates_available_path: Option<PathBuf>,
+    /// The directory holding the modules of the installed kernels,
+    /// defaulting to `/lib/modules`.
+    kernel_modules_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct CloudMetadataConfig {
+    /// Tag the metrics with the instance metadata of the cloud provider.
+    #[serde(default)]
+    enabled: bool,
+    /// The provider to query, detected by trying each in turn by default.
+    provider: Option<CloudProvider>,
+    /// Overrides the metadata endpoint of the provider.
+    endpoint: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CloudProvider {
+    Aws,
+    Gcp,
+    Azure,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct ZfsConfig {
+    #[serde(default)]
+    pools: FilterList,
+}
+
+/// How TCP sockets are grouped before their RTTs are summarized.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TcpGroupBy {
+    State,
+    Subnet,
+}
+
+impl Default for TcpGroupBy {
+    fn default() -> Self {
+        Self::State
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SummaryConfig {
+    #[serde(default)]
+    collectors: Vec<Collector>,
+    #[serde(default = "default_summary_samples")]
+    samples: u32,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            collectors: Vec::new(),
+            samples: default_summary_samples(),
+        }
+    }
+}
+
+const fn default_summary_samples() -> u32 {
+    1
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SmoothingConfig {
+    /// The gauges to also emit an exponentially weighted moving average of,
+    /// as `<name>_smoothed`.
+    #[serde(default)]
+    metrics: Vec<String>,
+    /// The weight of the latest value in the average, in `(0, 1]`.
+    #[serde(default = "default_smoothing_factor")]
+    factor: f64,
+}
+
+impl Default for SmoothingConfig {
+    fn default() -> Self {
+        Self {
+            metrics: Vec::new(),
+            factor: default_smoothing_factor(),
+        }
+    }
+}
+
+const fn default_smoothing_factor() -> f64 {
+    0.3
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct InventoryConfig {
+    /// Emits the inventory metrics only on the first scrape and every
+    /// `every`th scrape after it, instead of on every scrape.
+    every: Option<NonZeroU64>,
+    /// The metrics which rarely change.
+    #[serde(default = "default_inventory_metrics")]
+    metrics: Vec<String>,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            every: None,
+            metrics: default_inventory_metrics(),
+        }
+    }
+}
+
+fn default_inventory_metrics() -> Vec<String> {
+    [
+        "host_info",
+        "boot_time",
+        "memory_total_bytes",
+        "memory_swap_total_bytes",
+        "disk_size_bytes",
+        "disk_sector_size_bytes",
+        "filesystem_total_bytes",
+    ]
+    .iter()
+    .map(|&name| name.to_string())
+    .collect()
+}
+
+impl SummaryConfig {
+    /// The number of samples taken per scrape interval, which is always
+    /// one unless at least one collector is summarized.
+    fn samples(&self) -> u32 {
+        if self.collectors.is_empty() {
+            1
+        } else {
+            self.samples.max(1)
+        }
+    }
+}
+
+/// Bounds the cardinality of per-entity metrics, such as per-process ones, by
+/// keeping only the highest ranking series.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TopNConfig {
+    /// The metric whose value ranks the series.
+    metric: String,
+    /// The tags identifying a series.
+    tags: Vec<String>,
+    /// The number of series to keep.
+    count: usize,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TimestampAlignment {
+    /// Use the time each value was collected.
+    Collection,
+    /// Round down to the nearest multiple of the scrape interval.
+    Interval,
+}
+
+impl Default for TimestampAlignment {
+    fn default() -> Self {
+        Self::Collection
+    }
+}
+
+/// How the values of counters are emitted.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CounterMode {
+    /// Emit the cumulative value only.
+    Absolute,
+    /// Also emit the per-second rate since the previous scrape, as a
+    /// `<name>_rate` gauge with the `_total` suffix dropped.
+    EmitBoth,
+}
+
+impl Default for CounterMode {
+    fn default() -> Self {
+        Self::Absolute
+    }
+}
+
+/// The value type a metric is emitted with in place of its own.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ValueType {
+    Counter,
+    Gauge,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TimeUnit {
+    Seconds,
+    Milliseconds,
+}
+
+impl Default for TimeUnit {
+    fn default() -> Self {
+        Self::Seconds
+    }
+}
+
+impl TimeUnit {
+    /// Converts the value of a time-valued metric, which the collectors
+    /// report in seconds, renaming its `_seconds` suffix to match. Other
+    /// metrics are returned unchanged.
+    fn convert(self, name: &str, value: f64) -> (Cow<'_, str>, f64) {
+        match self {
+            Self::Milliseconds if is_time_metric(name) => (
+                name.replacen("_seconds", "_milliseconds", 1).into(),
+                value * 1000.0,
+            ),
+            _ => (name.into(), value),
+        }
+    }
+}
+
+fn is_time_metric(name: &str) -> bool {
+    name.contains("_seconds") || name == "uptime" || name == "boot_time"
+}
+
+/// The source of the current time, which tests replace to get deterministic
+/// timestamps.
+#[derive(Clone)]
+struct Clock(Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>);
+
+impl Clock {
+    #[cfg(test)]
+    fn fixed(timestamp: DateTime<Utc>) -> Self {
+        Self(Arc::new(move || timestamp))
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self(Arc::new(Utc::now))
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("Clock")
+    }
+}
+
+/// The source of the hostname, which tests replace to simulate failures.
+#[derive(Clone)]
+struct Hostname(Arc<dyn Fn() -> std::io::Result<String> + Send + Sync>);
+
+impl Hostname {
+    #[cfg(test)]
+    fn failing() -> Self {
+        Self(Arc::new(|| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "no hostname",
+            ))
+        }))
+    }
+}
+
+impl Default for Hostname {
+    fn default() -> Self {
+        Self(Arc::new(crate::get_hostname))
+    }
+}
+
+impl fmt::Debug for Hostname {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("Hostname")
+    }
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Invalid name_template {:?}: {}", template, reason))]
+    InvalidNameTemplate {
+        template: String,
+        reason: &'static str,
+    },
+    #[snafu(display("Unknown metric {:?} in metric_types", name))]
+    UnknownMetricType { name: String },
+    #[snafu(display("smoothing.factor must be in (0, 1], got {}", factor))]
+    InvalidSmoothingFactor { factor: f64 },
+    #[snafu(display("scrape_interval_secs must be at least 1, got {}", interval))]
+    InvalidScrapeInterval { interval: u64 },
+    #[snafu(display(
+        "summary.samples of {} splits scrape_interval_secs of {} into zero-length periods",
+        samples,
+        interval
+    ))]
+    InvalidSummarySamples { samples: u32, interval: u64 },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Namespace(Option<String>);
+
+impl Default for Namespace {
+    fn default() -> Self {
+        Self(Some("host".into()))
+    }
+}
+
+/// Combines the namespace and name of a metric into its name, replacing the
+/// `{namespace}` and `{name}` placeholders.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+struct NameTemplate(String);
+
+impl NameTemplate {
+    fn validate(&self) -> Result<(), BuildError> {
+        let invalid = |reason| {
+            Err(BuildError::InvalidNameTemplate {
+                template: self.0.clone(),
+                reason,
+            })
+        };
+        let mut has_name = false;
+        let mut rest = self.0.as_str();
+        while let Some((text, placeholder)) = rest.split_once('{') {
+            let (placeholder, after) = match placeholder.split_once('}') {
+                Some(split) => split,
+                None => return invalid("unclosed placeholder"),
+            };
+            if text.contains('}') {
+                return invalid("unopened placeholder");
+            }
+            match placeholder {
+                "name" => has_name = true,
+                "namespace" => {}
+                _ => return invalid("unknown placeholder, expected {namespace} or {name}"),
+            }
+            rest = after;
+        }
+        if rest.contains('}') {
+            return invalid("unopened placeholder");
+        }
+        if !has_name {
+            return invalid("missing the {name} placeholder");
+        }
+        Ok(())
+    }
+
+    fn render(&self, namespace: Option<&str>, name: &str) -> String {
+        let rendered = self
+            .0
+            .replace("{namespace}", namespace.unwrap_or_default())
+            .replace("{name}", name);
+        // Without a namespace, its separator is dropped as well.
+        match namespace {
+            Some(_) => rendered,
+            None => rendered
+                .trim_matches(|c: char| c == '_' || c == '.')
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostMetricsConfig {
+    #[serde(default = "default_scrape_interval")]
+    scrape_interval_secs: u64,
+    /// Where the filesystem of the monitored system is mounted, such as
+    /// when monitoring the host from a container.
+    host_root: Option<PathBuf>,
+
+    collectors: Option<Vec<Collector>>,
+    /// Enables or disables individual collectors, taking precedence over
+    /// `collectors`.
+    #[serde(default)]
+    enable: HashMap<Collector, bool>,
+    #[serde(default)]
+    namespace: Namespace,
+    /// Sets the metric names from the namespace and name instead of passing
+    /// the namespace on separately.
+    name_template: Option<NameTemplate>,
+    #[serde(default)]
+    counters_as_gauges: bool,
+    #[serde(default)]
+    counter_mode: CounterMode,
+    /// Also emits the time each counter started counting from as a
+    /// `<name>_created` gauge, as OpenMetrics does.
+    #[serde(default)]
+    created_timestamps: bool,
+    /// Overrides the value type of individual metrics, by name.
+    #[serde(default)]
+    metric_types: HashMap<String, ValueType>,
+    #[serde(default)]
+    sort_metrics: bool,
+    #[serde(default)]
+    timestamp_alignment: TimestampAlignment,
+    #[serde(default)]
+    time_unit: TimeUnit,
+    #[serde(default)]
+    top_n: Vec<TopNConfig>,
+    /// Filters the collected metrics by the values of their tags, leaving
+    /// metrics without the tag alone.
+    #[serde(default)]
+    tag_filters: HashMap<String, FilterList>,
+    #[serde(default)]
+    emit_errors: bool,
+    #[serde(default)]
+    emit_collected_counts: bool,
+    /// Skips counters whose value didn't change since the previous scrape.
+    #[serde(default)]
+    skip_unchanged_counters: bool,
+    /// Retries a scrape once when every collector failed or came back
+    /// empty.
+    #[serde(default)]
+    retry_empty_scrape: bool,
+    /// Emits the resource usage of the Vector process.
+    #[serde(default)]
+    self_metrics: bool,
+    /// Also emits the load averages divided by the number of logical CPUs.
+    #[serde(default)]
+    load_per_core: bool,
+    /// Tags the `uptime` metric with the time of the last boot as an ISO 8601
+    /// timestamp in `boot_time`, for display next to the uptime.
+    #[serde(default)]
+    boot_time_tag: bool,
+    /// The maximum number of concurrent calls of collectors loading many
+    /// items, defaulting to the number of CPUs. The items are emitted in
+    /// the order they were listed in either way.
+    max_concurrency: Option<usize>,
+    /// Clamps derived values into their valid ranges, enabled by default.
+    clamp_values: Option<bool>,
+    /// Tags the metrics with `host="unknown"` when the hostname can't be
+    /// resolved, instead of leaving the tag out.
+    #[serde(default)]
+    tag_unknown_host: bool,
+    /// Truncates tag values longer than this many characters, unlimited by
+    /// default.
+    max_tag_value_len: Option<usize>,
+    /// The minimum number of seconds between logs of the same collection
+    /// error, 60 by default.
+    error_log_interval_secs: Option<u64>,
+
+    #[serde(default)]
+    cpu: CpuConfig,
+    #[serde(default)]
+    disk: DiskConfig,
+    #[serde(default)]
+    filesystem: FilesystemConfig,
+    #[serde(default)]
+    memory: MemoryConfig,
+    #[serde(default)]
+    network: NetworkConfig,
+    #[serde(default)]
+    cgroups: CgroupsConfig,
+    #[serde(default)]
+    tcp: TcpConfig,
+    #[serde(default)]
+    process: ProcessConfig,
+    #[serde(default)]
+    tcp_listen: TcpListenConfig,
+    #[serde(default)]
+    zfs: ZfsConfig,
+    #[serde(default)]
+    listen_sockets: ListenSocketsConfig,
+    #[serde(default)]
+    kernel_messages: KernelMessagesConfig,
+    #[serde(default)]
+    buddyinfo: BuddyinfoConfig,
+    #[serde(default)]
+    container: ContainerConfig,
+    #[serde(default)]
+    updates: UpdatesConfig,
+    #[serde(default)]
+    softirqs: SoftirqsConfig,
+    #[serde(default)]
+    cloud_metadata: CloudMetadataConfig,
+    #[serde(default)]
+    summary: SummaryConfig,
+    #[serde(default)]
+    smoothing: SmoothingConfig,
+    #[serde(default)]
+    inventory: InventoryConfig,
+
+    #[serde(skip)]
+    clock: Clock,
+    #[serde(skip)]
+    hostname: Hostname,
+    /// The cloud instance metadata tags, fetched when the source is built.
+    #[serde(skip)]
+    cloud_tags: BTreeMap<String, String>,
+    /// The boot time tagging `uptime`, loaded on the first scrape.
+    #[serde(skip)]
+    boot_time: Arc<OnceCell<String>>,
+}
+
+/// The default minimum number of seconds between logs of the same collection
+/// error.
+const DEFAULT_ERROR_LOG_INTERVAL_SECS: u64 = 60;
+
+/// The maximum number of characters of an error kept in `collect_error`.
+const MAX_ERROR_LENGTH: usize = 64;
+
+/// How long to wait before retrying a scrape in which every collector
+/// failed.
+const RETRY_DELAY: time::Duration = time::Duration::from_secs(1);
+
+const fn default_scrape_interval() -> u64 {
+    15
+}
+
+inventory::submit! {
+    SourceDescription::new::<HostMetricsConfig>("host_metrics")
+}
+
+impl_generate_config_from_default!(HostMetricsConfig);
+
+#[async_trait::async_trait]
+#[typetag::serde(name = "host_metrics")]
+impl SourceConfig for HostMetricsConfig {
+    async fn build(&self, cx: SourceContext) -> crate::Result<super::Source> {
+        // The interval timer panics on a zero period.
+        if self.scrape_interval_secs == 0 {
+            return Err(BuildError::InvalidScrapeInterval {
+                interval: self.scrape_interval_secs,
+            }
+            .into());
+        }
+        // Nor may the samples split it into periods shorter than a nanosecond.
+        let samples = self.summary.samples();
+        if (time::Duration::from_secs(self.scrape_interval_secs) / samples).is_zero() {
+            return Err(BuildError::InvalidSummarySamples {
+                samples,
+                interval: self.scrape_interval_secs,
+            }
+            .into());
+        }
+        init_roots(self.host_root.as_deref());
+
+        let mut config = self.clone();
+        config.namespace.0 = config.namespace.0.filter(|namespace| !namespace.is_empty());
+        if let Some(template) = &config.name_template {
+            template.validate()?;
+        }
+        if let Some(name) = config
+            .metric_types
+            .keys()
+            .find(|name| !config.is_known_metric(name))
+        {
+            return Err(BuildError::UnknownMetricType { name: name.clone() }.into());
+        }
+        let factor = config.smoothing.factor;
+        if !(factor > 0.0 && factor <= 1.0) {
+            return Err(BuildError::InvalidSmoothingFactor { factor }.into());
+        }
+        for &collector in Collector::ALL {
+            if config.has_collector(collector) && !collector.is_available() {
+                warn!(
+                    message = "Collector is not available on this platform or in this build, so it will collect nothing.",
+                    collector = collector.as_str(),
+                );
+            }
+        }
+        config.warn_unmatched_includes().await;
+        if let Err(error) = (config.hostname.0)() {
+            if config.tag_unknown_host {
+                warn!(message = "Failed to resolve the hostname, tagging metrics with host=\"unknown\".", %error);
+            } else {
+                warn!(message = "Failed to resolve the hostname, leaving out the host tag.", %error);
+            }
+        }
+        if config.cloud_metadata.enabled {
+            #[cfg(feature = "sources-host_metrics-cloud_metadata")]
+            {
+                config.cloud_tags =
+                    cloud_metadata::fetch_tags(&config.cloud_metadata, &cx.proxy).await;
+            }
+            #[cfg(not(feature = "sources-host_metrics-cloud_metadata"))]
+            warn!(message = "Cloud metadata tags require the `sources-host_metrics-cloud_metadata` feature.");
+        }
+
+        Ok(Box::pin(config.run(cx.out, cx.shutdown)))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn source_type(&self) -> &'static str {
+        "host_metrics"
+    }
+}
+
+impl HostMetricsConfig {
+    async fn run(self, out: Pipeline, shutdown: ShutdownSignal) -> Result<(), ()> {
+        let out = out.with(|metric: Metric| future::ok::<_, ClosedError>(Event::from(metric)));
+        self.run_with_sink(out, shutdown).await
+    }
+
+    /// Scrapes the host every interval until `shutdown` completes, sending
+    /// the metrics to `sink`. This runs the collection outside of a
+    /// topology, such as when embedding it.
+    pub async fn run_with_sink<S>(self, sink: S, shutdown: impl Future) -> Result<(), ()>
+    where
+        S: Sink<Metric> + Unpin,
+        S::Error: fmt::Display,
+    {
+        let mut out =
+            sink.sink_map_err(|error| error!(message = "Error sending host metrics.", %error));
+
+        // When summarizing, the interval is split up into `samples` ticks
+        // and the full scrape only happens on every `samples`th tick.
+        let samples = self.summary.samples();
+        let duration = time::Duration::from_secs(self.scrape_interval_secs) / samples;
+        let mut interval =
+            Box::pin(IntervalStream::new(time::interval(duration)).take_until(shutdown));
+        let mut window = Vec::new();
+        let mut ticks = 0u64;
+        let mut counters = HashMap::new();
+        let mut rate_counters = HashMap::new();
+        let smoothed_names = self.smoothed_names();
+        let mut averages = HashMap::new();
+        let inventory_names = self.inventory_names();
+        let mut network_counters = HashMap::new();
+        let started = self.now();
+        let mut created = HashMap::new();
+        while interval.next().await.is_some() {
+            if samples > 1 {
+                window.push(self.capture_summary_sample().await);
+            }
+            if ticks % u64::from(samples) == 0 {
+                let summaries = summarize(&window, self.align_timestamp(self.now()));
+                window.clear();
+                let mut metrics = self
+                    .capture_metrics_with(summaries)
+                    .await
+                    .collect::<Vec<_>>();
+                if self.counter_mode == CounterMode::EmitBoth {
+                    metrics = add_counter_rates(metrics, &mut rate_counters);
+                }
+                if self.created_timestamps {
+                    metrics = add_created_gauges(metrics, started, &mut created);
+                }
+                if self.network.incremental {
+                    metrics = make_counters_incremental(
+                        metrics,
+                        Collector::Network,
+                        &mut network_counters,
+                    );
+                }
+                if !smoothed_names.is_empty() {
+                    metrics = add_smoothed_gauges(
+                        metrics,
+                        &smoothed_names,
+                        self.smoothing.factor,
+                        &mut averages,
+                    );
+                }
+                if let Some(every) = self.inventory.every {
+                    let scrape = ticks / u64::from(samples);
+                    metrics = drop_inventory_metrics(metrics, &inventory_names, scrape, every);
+                }
+                if self.skip_unchanged_counters {
+                    metrics = drop_unchanged_counters(metrics.into_iter(), &mut counters);
+                }
+                out.send_all(&mut stream::iter(metrics).map(|event| Ok(event.into_metric())))
+                    .await?;
+            }
+            ticks += 1;
+        }
+
+        Ok(())
+    }
+
+    /// The value of the `host` tag, which is the same for every metric of a
+    /// scrape. Failures are logged when the source is built.
+    fn host_tag(&self) -> Option<String> {
+        match (self.hostname.0)() {
+            Ok(hostname) => Some(hostname),
+            Err(_) if self.tag_unknown_host => Some("unknown".into()),
+            Err(_) => None,
+        }
+    }
+
+    /// The names the gauges to smooth are emitted with.
+    fn smoothed_names(&self) -> HashSet<String> {
+        self.smoothing
+            .metrics
+            .iter()
+            .map(|name| self.metric_name(name).1)
+            .collect()
+    }
+
+    /// Warns about include patterns matching none of the devices or mounts
+    /// found, such as `/dev/sda` for disks, which are named `sda`. Such
+    /// patterns leave out all metrics of the collector without an error.
+    async fn warn_unmatched_includes(&self) {
+        if self.has_collector(Collector::Disk) && self.disk.devices.includes.is_some() {
+            if let Ok(counters) = heim::disk::io_counters().await {
+                let devices = counters
+                    .filter_map(|counter| async { counter.ok() })
+                    .map(|counter| counter.device_name().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .await;
+                warn_unmatched_includes("disk.devices", &self.disk.devices, &devices);
+            }
+        }
+        let network_devices = self.network.devices();
+        if self.has_collector(Collector::Network) && network_devices.includes.is_some() {
+            if let Ok(counters) = heim::net::io_counters().await {
+                let interfaces = counters
+                    .filter_map(|counter| async { counter.ok() })
+                    .map(|counter| counter.interface().to_string())
+                    .collect::<Vec<_>>()
+                    .await;
+                warn_unmatched_includes("network.devices", &network_devices, &interfaces);
+            }
+        }
+        let filesystem = &self.filesystem;
+        if self.has_collector(Collector::Filesystem)
+            && filesystem.paths.is_empty()
+            && [
+                &filesystem.devices,
+                &filesystem.filesystems,
+                &filesystem.mountpoints,
+            ]
+            .iter()
+            .any(|filter| filter.includes.is_some())
+        {
+            let timeout = filesystem
+                .enumeration_timeout_secs
+                .unwrap_or(self.scrape_interval_secs);
+            let deadline = time::Instant::now() + time::Duration::from_secs(timeout);
+            if let Ok(Ok(partitions)) = time::timeout_at(deadline, heim::disk::partitions()).await {
+                let partitions = until_deadline(
+                    partitions,
+                    deadline,
+                    "Timed out enumerating partitions.",
+                    self.error_log_rate_secs(),
+                )
+                .filter_map(|partition| async { partition.ok() })
+                .collect::<Vec<_>>()
+                .await;
+                let devices = partitions
+                    .iter()
+                    .filter_map(|partition| partition.device())
+                    .map(|device| device.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>();
+                let filesystems = partitions
+                    .iter()
+                    .map(|partition| {
+                        self.filesystem_type(partition.file_system().as_str())
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+                let mountpoints = partitions
+                    .iter()
+                    .map(|partition| partition.mount_point().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>();
+                warn_unmatched_includes("filesystem.devices", &filesystem.devices, &devices);
+                warn_unmatched_includes(
+                    "filesystem.filesystems",
+                    &filesystem.filesystems,
+                    &filesystems,
+                );
+                warn_unmatched_includes(
+                    "filesystem.mountpoints",
+                    &filesystem.mountpoints,
+                    &mountpoints,
+                );
+            }
+        }
+    }
+
+    /// The names the inventory metrics are emitted with.
+    fn inventory_names(&self) -> HashSet<String> {
+        self.inventory
+            .metrics
+            .iter()
+            .map(|name| self.metric_name(name).1)
+            .collect()
+    }
+
+    fn has_collector(&self, collector: Collector) -> bool {
+        if let Some(&enabled) = self.enable.get(&collector) {
+            return enabled;
+        }
+        match &self.collectors {
+            None => Collector::DEFAULT.contains(&collector),
+            Some(collectors) => collectors.iter().any(|&c| c == collector),
+        }
+    }
+
+    async fn collect(&self, collector: Collector) -> Vec<Metric> {
+        match collector {
+            Collector::Cpu => self.cpu_metrics().await,
+            Collector::Disk => self.disk_metrics().await,
+            Collector::Filesystem => self.filesystem_metrics().await,
+            Collector::Load => self.loadavg_metrics().await,
+            Collector::Host => self.host_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Memory => {
+                let mut metrics = self.meminfo_metrics().await;
+                metrics.extend(self.memory_pressure_metrics().await);
+                metrics.extend(self.swap_device_metrics().await);
+                metrics
+            }
+            #[cfg(not(target_os = "linux"))]
+            Collector::Memory => {
+                let mut metrics = self.memory_metrics().await;
+                metrics.extend(self.swap_metrics().await);
+                metrics
+            }
+            #[cfg(target_os = "linux")]
+            Collector::Network => {
+                let mut metrics = self.network_metrics().await;
+                metrics.extend(self.tcp_memory_metrics().await);
+                metrics
+            }
+            #[cfg(not(target_os = "linux"))]
+            Collector::Network => self.network_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Vm => self.vm_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Cgroup => self.cgroup_metrics().await,
+            #[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+            Collector::Tcp => self.tcp_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Clock => self.clock_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Mdraid => self.mdraid_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Process => self.process_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::TcpListen => self.tcp_listen_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::BlockDevice => self.block_device_metrics().await,
+            #[cfg(all(target_os = "linux", feature = "sources-host_metrics-netlink"))]
+            Collector::NetDevQueue => self.qdisc_metrics().await,
+            #[cfg(all(target_os = "linux", feature = "sources-host_metrics-zfs"))]
+            Collector::Zfs => self.zfs_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::ListenSockets => self.listen_socket_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::KernelMessages => self.kernel_message_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Buddyinfo => self.buddyinfo_metrics().await,
+            #[cfg(feature = "sources-host_metrics-docker")]
+            Collector::Container => self.container_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Updates => self.updates_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Schedstat => self.schedstat_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Cpuidle => self.cpuidle_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::Softirqs => self.softirq_metrics().await,
+            #[cfg(unix)]
+            Collector::Limits => self.limit_metrics().await,
+            #[cfg(target_os = "linux")]
+            Collector::ThermalThrottle => self.thermal_throttle_metrics().await,
+            // Collectors of other platforms or disabled features, which
+            // `Collector::is_available` reports and `build` warns about
+            #[allow(unreachable_patterns)]
+            _ => Vec::new(),
+        }
+    }
+
+    /// Collects the gauges of the summarized collectors for one sub-sample.
+    async fn capture_summary_sample(&self) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        for &collector in &self.summary.collectors {
+            if self.has_collector(collector) {
+                let sample = self
+                    .collect(collector)
+                    .await
+                    .into_iter()
+                    .filter(|metric| matches!(metric.value(), MetricValue::Gauge { .. }));
+                metrics.extend(add_collector(collector.as_str(), sample.collect()));
+            }
+        }
+        metrics
+    }
+
+    #[cfg(test)]
+    async fn capture_metrics(&self) -> impl Iterator<Item = Event> {
+        self.capture_metrics_with(Vec::new()).await
+    }
+
+    /// Captures all enabled collectors, adding `extra` metrics computed
+    /// outside of the scrape (such as summaries) before tagging.
+    async fn capture_metrics_with(&self, extra: Vec<Metric>) -> impl Iterator<Item = Event> {
+        let hostname = self.host_tag();
+        let mut metrics = Vec::new();
+        let scrape = self
+            .retry_failed_scrape(RETRY_DELAY, || self.collect_enabled())
+            .await;
+        for (collector, mut collected) in scrape {
+            if self.emit_collected_counts {
+                let count = collected.len() as f64;
+                collected.push(self.gauge(
+                    "collected_metrics_count",
+                    self.now(),
+                    count,
+                    btreemap! {},
+                ));
+            }
+            metrics.extend(add_collector(collector.as_str(), collected));
+        }
+        metrics.extend(extra);
+        if self.self_metrics {
+            metrics.extend(self.vector_process_metrics().await);
+        }
+        metrics.push(self.gauge(
+            "scrape_interval_seconds",
+            self.now(),
+            self.scrape_interval_secs as f64,
+            btreemap! {},
+        ));
+        metrics.push(self.build_info());
+        if !self.tag_filters.is_empty() {
+            metrics.retain(|metric| self.matches_tag_filters(metric));
+        }
+        for config in &self.top_n {
+            metrics = top_n::top_n(metrics, config);
+        }
+        if let Some(hostname) = &hostname {
+            for metric in &mut metrics {
+                metric.insert_tag("host".into(), hostname.into());
+            }
+        }
+        for metric in &mut metrics {
+            for (key, value) in &self.cloud_tags {
+                metric.insert_tag(key.clone(), value.clone());
+            }
+        }
+        let (metrics, duplicates) = dedupe_series(metrics);
+        if duplicates > 0 {
+            warn!(
+                message = "Dropped metrics with duplicate series.",
+                count = duplicates,
+                internal_log_rate_secs = self.error_log_rate_secs()
+            );
+        }
+        let (mut metrics, non_finite) = drop_non_finite(metrics);
+        for name in non_finite {
+            warn!(
+                message = "Dropped metric with a non-finite value.",
+                %name,
+                internal_log_rate_secs = self.error_log_rate_secs()
+            );
+        }
+        if self.sort_metrics {
+            metrics.sort_by(|a, b| (a.name(), a.tags()).cmp(&(b.name(), b.tags())));
+        }
+        emit!(HostMetricsEventReceived {
+            count: metrics.len()
+        });
+        metrics.into_iter().map(Into::into)
+    }
+
+    /// Captures all enabled collectors twice, `window` apart, and returns
+    /// the per-second rates of the counters in between, for inspecting the
+    /// host on demand rather than through `run`.
+    pub async fn capture_rates(&self, window: time::Duration) -> Vec<Metric> {
+        capture_counter_rates(window, || async {
+            self.capture_metrics_with(Vec::new()).await.collect()
+        })
+        .await
+    }
+
+    fn matches_tag_filters(&self, metric: &Metric) -> bool {
+        self.tag_filters.iter().all(|(key, filter)| {
+            metric
+                .tags()
+                .and_then(|tags| tags.get(key))
+                .map_or(true, |value| filter.contains_str(Some(value)))
+        })
+    }
+
+    /// Identifies the Vector version and the enabled collectors, to
+    /// correlate changes in the metrics with upgrades.
+    fn build_info(&self) -> Metric {
+        let mut collectors = Collector::ALL
+            .iter()
+            .filter(|&&collector| self.has_collector(collector))
+            .map(|collector| collector.as_str())
+            .collect::<Vec<_>>();
+        collectors.sort_unstable();
+        self.gauge(
+            "host_metrics_build_info",
+            self.now(),
+            1.0,
+            btreemap! {
+                "version" => crate::vector_version().to_string(),
+                "collectors" => collectors.join(","),
+            },
+        )
+    }
+
+    async fn collect_enabled(&self) -> Vec<(Collector, Vec<Metric>)> {
+        let mut scrape = Vec::new();
+        for &collector in Collector::ALL {
+            if self.has_collector(collector) {
+                scrape.push((collector, self.collect(collector).await));
+            }
+        }
+        scrape
+    }
+
+    /// Runs `scrape` again after `delay` when every collector of it failed
+    /// or came back empty, such as while procfs is briefly unmounted during
+    /// a container restart. Only a single retry is made, whatever its
+    /// outcome.
+    async fn retry_failed_scrape<F, Fut>(
+        &self,
+        delay: time::Duration,
+        scrape: F,
+    ) -> Vec<(Collector, Vec<Metric>)>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Vec<(Collector, Vec<Metric>)>>,
+    {
+        let collected = scrape().await;
+        if !self.retry_empty_scrape || !self.is_failed_scrape(&collected) {
+            return collected;
+        }
+        warn!(
+            message = "All collectors failed, retrying the scrape.",
+            internal_log_rate_secs = self.error_log_rate_secs()
+        );
+        time::sleep(delay).await;
+        scrape().await
+    }
+
+    fn is_failed_scrape(&self, scrape: &[(Collector, Vec<Metric>)]) -> bool {
+        let (_, error) = self.metric_name("collect_error");
+        !scrape.is_empty()
+            && scrape
+                .iter()
+                .flat_map(|(_, metrics)| metrics)
+                .all(|metric| metric.name() == error)
+    }
+
+    pub async fn cpu_metrics(&self) -> Vec<Metric> {
+        #[cfg(target_os = "linux")]
+        let offline = offline_cpus(&self.sysfs_root());
+        #[cfg(not(target_os = "linux"))]
+        let offline = HashSet::new();
+        let mut metrics = self.cpu_times_metrics(&offline).await;
+        if self.cpu.percentiles {
+            metrics = self.cpu_percentile_metrics(metrics);
+        }
+        if self.cpu.saturation {
+            metrics.extend(self.cpu_saturation_metrics().await);
+        }
+        metrics
+    }
+
+    /// Reports how saturated the CPUs are from the one-minute load average
+    /// and, where available, the CPU pressure stall information.
+    async fn cpu_saturation_metrics(&self) -> Vec<Metric> {
+        #[cfg(unix)]
+        let result = {
+            let load1 = match heim::cpu::os::unix::loadavg().await {
+                Ok(loadavg) => loadavg.0.get::<ratio>() as f64,
+                Err(error) => {
+                    error!(message = "Failed to load load average info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                    return self.collect_error(&error);
+                }
+            };
+            let cores = match heim::cpu::logical_count().await {
+                Ok(cores) => cores,
+                Err(error) => {
+                    error!(message = "Failed to load CPU count.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                    return self.collect_error(&error);
+                }
+            };
+            // PSI is unavailable on older kernels, or disabled.
+            #[cfg(target_os = "linux")]
+            let pressure = std::fs::read_to_string(self.procfs_root().join("pressure/cpu"))
+                .ok()
+                .and_then(|pressure| memory::parse_pressure_ratio(&pressure));
+            #[cfg(not(target_os = "linux"))]
+            let pressure = None;
+            vec![self.gauge(
+                "cpu_saturation_ratio",
+                self.now(),
+                cpu_saturation_ratio(load1, cores, pressure),
+                btreemap! {},
+            )]
+        };
+        #[cfg(not(unix))]
+        let result = vec![];
+
+        result
+    }
+
+    /// Collapses the per-CPU times into gauges of their distribution across
+    /// the CPUs for each mode, whose number doesn't grow with the CPUs.
+    fn cpu_percentile_metrics(&self, per_cpu: Vec<Metric>) -> Vec<Metric> {
+        let timestamp = self.now();
+        // The per-CPU times are already in the configured time unit, which
+        // building the gauges converts them to again.
+        let (_, scale) = self.time_unit.convert("cpu_seconds_total", 1.0);
+        let mut modes = BTreeMap::<String, Vec<f64>>::new();
+        let mut metrics = Vec::new();
+        for metric in per_cpu {
+            let value = match metric.value() {
+                MetricValue::Counter { value } | MetricValue::Gauge { value } => Some(*value),
+                _ => None,
+            };
+            match (metric.tag_value("mode"), value) {
+                (Some(mode), Some(value)) => modes.entry(mode).or_default().push(value / scale),
+                // Such as collection errors
+                _ => metrics.push(metric),
+            }
+        }
+        for (mode, mut values) in modes {
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            for &(name, quantile) in &[
+                ("cpu_seconds_p50", 0.5),
+                ("cpu_seconds_p95", 0.95),
+                ("cpu_seconds_max", 1.0),
+            ] {
+                metrics.push(self.gauge(
+                    name,
+                    timestamp,
+                    percentile(&values, quantile),
+                    btreemap! { "mode" => mode.clone() },
+                ));
+            }
+        }
+        metrics
+    }
+
+    /// Reports the CPU times, skipping the `offline` CPUs unless configured
+    /// to include them, as their times are stale.
+    async fn cpu_times_metrics(&self, offline: &HashSet<usize>) -> Vec<Metric> {
+        match heim::cpu::times().await {
+            Ok(times) => {
+                times
+                    .filter_map(|result| {
+                        filter_result(
+                            result,
+                            "Failed to load/parse CPU time.",
+                            self.error_log_rate_secs(),
+                        )
+                    })
+                    .enumerate()
+                    .map(|(index, times)| {
+                        let online = !offline.contains(&index);
+                        if !online && !self.cpu.include_offline {
+                            return stream::iter(Vec::new().into_iter());
+                        }
+                        let tags = |mode: &str| {
+                            let mut tags = btreemap! { "mode" => mode, "cpu" => index.to_string() };
+                            if self.cpu.include_offline {
+                                tags.insert("online".into(), online.to_string());
+                            }
+                            tags
+                        };
+                        let timestamp = self.now();
+                        let name = "cpu_seconds_total";
+                        stream::iter(
+                            vec![
+                                self.counter(
+                                    name,
+                                    timestamp,
+                                    times.idle().get::<second>(),
+                                    tags("idle"),
+                                ),
+                                #[cfg(target_os = "linux")]
+                                self.counter(
+                                    name,
+                                    timestamp,
+                                    times.nice().get::<second>(),
+                                    tags("nice"),
+                                ),
+                                self.counter(
+                                    name,
+                                    timestamp,
+                                    times.system().get::<second>(),
+                                    tags("system"),
+                                ),
+                                self.counter(
+                                    name,
+                                    timestamp,
+                                    times.user().get::<second>(),
+                                    tags("user"),
+                                ),
+                            ]
+                            .into_iter(),
+                        )
+                    })
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .await
+            }
+            Err(error) => {
+                error!(message = "Failed to load CPU times.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+
+    pub async fn memory_metrics(&self) -> Vec<Metric> {
+        match heim::memory::memory().await {
+            Ok(memory) => {
+                let timestamp = self.now();
+                vec![
+                    self.gauge(
+                        "memory_total_bytes",
+                        timestamp,
+                        memory.total().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    self.gauge(
+                        "memory_free_bytes",
+                        timestamp,
+                        memory.free().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    self.gauge(
+                        "memory_available_bytes",
+                        timestamp,
+                        memory.available().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(any(target_os = "linux", target_os = "macos"))]
+                    self.gauge(
+                        "memory_active_bytes",
+                        timestamp,
+                        memory.active().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "linux")]
+                    self.gauge(
+                        "memory_buffers_bytes",
+                        timestamp,
+                        memory.buffers().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "linux")]
+                    self.gauge(
+                        "memory_cached_bytes",
+                        timestamp,
+                        memory.cached().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "linux")]
+                    self.gauge(
+                        "memory_shared_bytes",
+                        timestamp,
+                        memory.shared().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "linux")]
+                    self.gauge(
+                        "memory_used_bytes",
+                        timestamp,
+                        memory.used().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "macos")]
+                    self.gauge(
+                        "memory_inactive_bytes",
+                        timestamp,
+                        memory.inactive().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(target_os = "macos")]
+                    self.gauge(
+                        "memory_wired_bytes",
+                        timestamp,
+                        memory.wire().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                ]
+            }
+            Err(error) => {
+                error!(message = "Failed to load memory info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+
+    pub async fn swap_metrics(&self) -> Vec<Metric> {
+        match heim::memory::swap().await {
+            Ok(swap) => {
+                let timestamp = self.now();
+                vec![
+                    self.gauge(
+                        "memory_swap_free_bytes",
+                        timestamp,
+                        swap.free().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    self.gauge(
+                        "memory_swap_total_bytes",
+                        timestamp,
+                        swap.total().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    self.gauge(
+                        "memory_swap_used_bytes",
+                        timestamp,
+                        swap.used().get::<byte>() as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(not(target_os = "windows"))]
+                    self.counter(
+                        "memory_swapped_in_bytes_total",
+                        timestamp,
+                        swap.sin().map(|swap| swap.get::<byte>()).unwrap_or(0) as f64,
+                        btreemap! {},
+                    ),
+                    #[cfg(not(target_os = "windows"))]
+                    self.counter(
+                        "memory_swapped_out_bytes_total",
+                        timestamp,
+                        swap.sout().map(|swap| swap.get::<byte>()).unwrap_or(0) as f64,
+                        btreemap! {},
+                    ),
+                ]
+            }
+            Err(error) => {
+                error!(message = "Failed to load swap info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+
+    pub async fn loadavg_metrics(&self) -> Vec<Metric> {
+        #[cfg(unix)]
+        let result = match heim::cpu::os::unix::loadavg().await {
+            Ok(loadavg) => {
+                let timestamp = self.now();
+                let loads = [
+                    ("load1", loadavg.0.get::<ratio>() as f64),
+                    ("load5", loadavg.1.get::<ratio>() as f64),
+                    ("load15", loadavg.2.get::<ratio>() as f64),
+                ];
+                let mut metrics = loads
+                    .iter()
+                    .map(|&(name, load)| self.gauge(name, timestamp, load, btreemap! {}))
+                    .collect::<Vec<_>>();
+                if self.load_per_core {
+                    match heim::cpu::logical_count().await {
+                        Ok(cores) if cores > 0 => {
+                            metrics.extend(loads.iter().map(|&(name, load)| {
+                                self.gauge(
+                                    &format!("{}_per_core", name),
+                                    timestamp,
+                                    load / cores as f64,
+                                    btreemap! {},
+                                )
+                            }))
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            error!(message = "Failed to load CPU count.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                            metrics.extend(self.collect_error(&error));
+                        }
+                    }
+                }
+                metrics
+            }
+            Err(error) => {
+                error!(message = "Failed to load load average info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        };
+        #[cfg(not(unix))]
+        let result = vec![];
+
+        result
+    }
+
+    /// Collects the resource usage of the Vector process itself, read from
+    /// procfs like that of the other processes.
+    pub async fn vector_process_metrics(&self) -> Vec<Metric> {
+        #[cfg(target_os = "linux")]
+        let result = {
+            // Vector's own process is always listed in its own procfs, even
+            // when monitoring the host through `host_root`.
+            let process = match process::read_process(
+                Path::new("/proc"),
+                std::process::id(),
+                clock_ticks_per_second(),
+            ) {
+                Some(process) => process,
+                None => {
+                    error!(
+                        message = "Failed to load Vector process info.",
+                        internal_log_rate_secs = self.error_log_rate_secs()
+                    );
+                    return Vec::new();
+                }
+            };
+            let timestamp = self.now();
+            let mut metrics = vec![self.counter(
+                "vector_process_cpu_seconds_total",
+                timestamp,
+                process.cpu_seconds,
+                btreemap! {},
+            )];
+            if let Some(resident_bytes) = process.resident_bytes {
+                metrics.push(self.gauge(
+                    "vector_process_resident_memory_bytes",
+                    timestamp,
+                    resident_bytes as f64,
+                    btreemap! {},
+                ));
+            }
+            metrics.push(self.gauge(
+                "vector_process_threads",
+                timestamp,
+                process.threads as f64,
+                btreemap! {},
+            ));
+            if let Some(open_fds) = process.open_fds {
+                metrics.push(self.gauge(
+                    "vector_process_open_fds",
+                    timestamp,
+                    open_fds as f64,
+                    btreemap! {},
+                ));
+            }
+            metrics
+        };
+        #[cfg(not(target_os = "linux"))]
+        let result = Vec::new();
+
+        result
+    }
+
+    /// The time of the last boot as an ISO 8601 timestamp, loaded once as it
+    /// doesn't change while the host is up.
+    async fn cached_boot_time(&self) -> Option<String> {
+        if let Some(boot_time) = self.boot_time.get() {
+            return Some(boot_time.clone());
+        }
+        let time = heim::host::boot_time().await.ok()?;
+        let boot_time = Utc
+            .timestamp(time.get::<second>() as i64, 0)
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+        Some(self.boot_time.get_or_init(|| boot_time).clone())
+    }
+
+    pub async fn host_metrics(&self) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        match heim::host::uptime().await {
+            Ok(time) => {
+                let timestamp = self.now();
+                let mut tags = BTreeMap::default();
+                if self.boot_time_tag {
+                    if let Some(boot_time) = self.cached_boot_time().await {
+                        tags.insert("boot_time".into(), boot_time);
+                    }
+                }
+                metrics.push(self.gauge("uptime", timestamp, time.get::<second>() as f64, tags));
+            }
+            Err(error) => {
+                error!(message = "Failed to load host uptime info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+
+        match heim::host::boot_time().await {
+            Ok(time) => {
+                let timestamp = self.now();
+                metrics.push(self.gauge(
+                    "boot_time",
+                    timestamp,
+                    time.get::<second>() as f64,
+                    BTreeMap::default(),
+                ));
+            }
+            Err(error) => {
+                error!(message = "Failed to load host boot time info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+
+        match heim::host::platform().await {
+            Ok(platform) => {
+                let timestamp = self.now();
+                metrics.push(self.gauge(
+                    "host_info",
+                    timestamp,
+                    1.0,
+                    btreemap! {
+                        "kernel" => platform.release(),
+                        "os" => platform.system(),
+                        "os_version" => platform.version(),
+                        "arch" => platform.architecture().as_str(),
+                    },
+                ));
+            }
+            Err(error) => {
+                error!(message = "Failed to load host platform info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        metrics.extend(self.random_metrics());
+
+        metrics
+    }
+
+    pub async fn network_metrics(&self) -> Vec<Metric> {
+        // heim doesn't expose the compressed and multicast columns of
+        // `/proc/net/dev`, so those are read separately.
+        #[cfg(target_os = "linux")]
+        let net_dev = read_net_dev(&self.procfs_root(), self.error_log_rate_secs());
+        let devices = self.network.devices();
+        match heim::net::io_counters().await {
+            Ok(counters) => {
+                let mut metrics = counters
+                    .filter_map(|result| {
+                        filter_result(
+                            result,
+                            "Failed to load/parse network data.",
+                            self.error_log_rate_secs(),
+                        )
+                    })
+                    // The following pair should be possible to do in one
+                    // .filter_map, but it results in a strange "one type is
+                    // more general than the other" error.
+                    .map(|counter| {
+                        devices
+                            .contains_str(Some(counter.interface()))
+                            .then(|| counter)
+                    })
+                    .filter_map(|counter| async { counter })
+                    .map(|counter| {
+                        let timestamp = self.now();
+                        let interface = counter.interface();
+                        #[cfg(target_os = "linux")]
+                        let mut extra = net_dev
+                            .get(interface)
+                            .map(|stats| self.net_dev_metrics(stats, interface, timestamp))
+                            .unwrap_or_default();
+                        #[cfg(target_os = "linux")]
+                        if self.network.split_family {
+                            extra.extend(self.ipv6_interface_metrics(interface, timestamp));
+                        }
+                        #[cfg(target_os = "linux")]
+                        if let Some(changes) = carrier_changes(&self.sysfs_root(), interface) {
+                            extra.push(self.counter(
+                                "network_carrier_changes_total",
+                                timestamp,
+                                changes as f64,
+                                btreemap! { "device" => interface },
+                            ));
+                        }
+                        #[cfg(target_os = "linux")]
+                        for &(name, setting) in &[
+                            ("network_mtu_bytes", "mtu"),
+                            ("network_tx_queue_length", "tx_queue_len"),
+                        ] {
+                            if let Some(value) =
+                                interface_setting(&self.sysfs_root(), interface, setting)
+                            {
+                                extra.push(self.gauge(
+                                    name,
+                                    timestamp,
+                                    value as f64,
+                                    btreemap! { "device" => interface },
+                                ));
+                            }
+                        }
+                        #[cfg(not(target_os = "linux"))]
+                        let extra = Vec::new();
+                        // The interface counters include all protocols.
+                        let family_tags = || {
+                            let mut tags = btreemap! { "device" => interface };
+                            if self.network.split_family {
+                                tags.insert("family".into(), "all".into());
+                            }
+                            tags
+                        };
+                        stream::iter(
+                            vec![
+                                self.counter(
+                                    "network_receive_bytes_total",
+                                    timestamp,
+                                    counter.bytes_recv().get::<byte>() as f64,
+                                    family_tags(),
+                                ),
+                                self.counter(
+                                    "network_receive_errs_total",
+                                    timestamp,
+                                    counter.errors_recv() as f64,
+                                    btreemap! { "device" => interface },
+                                ),
+                                self.counter(
+                                    "network_receive_packets_total",
+                                    timestamp,
+                                    counter.packets_recv() as f64,
+                                    family_tags(),
+                                ),
+                                self.counter(
+                                    "network_transmit_bytes_total",
+                                    timestamp,
+                                    counter.bytes_sent().get::<byte>() as f64,
+                                    family_tags(),
+                                ),
+                                self.counter(
+                                    "network_transmit_errs_total",
+                                    timestamp,
+                                    counter.errors_sent() as f64,
+                                    btreemap! { "device" => interface },
+                                ),
+                                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                                self.counter(
+                                    "network_transmit_packets_drop_total",
+                                    timestamp,
+                                    counter.drop_sent() as f64,
+                                    btreemap! { "device" => interface },
+                                ),
+                                #[cfg(any(target_os = "linux", target_os = "windows"))]
+                                self.counter(
+                                    "network_transmit_packets_total",
+                                    timestamp,
+                                    counter.packets_sent() as f64,
+                                    family_tags(),
+                                ),
+                            ]
+                            .into_iter()
+                            .chain(extra),
+                        )
+                    })
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .await;
+                self.alias_interfaces(&mut metrics);
+                metrics
+            }
+            Err(error) => {
+                error!(message = "Failed to load network I/O counters.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+
+    /// Tags the metrics of the interfaces with an alias with it, or replaces
+    /// the interface name with it. Other interfaces keep their name.
+    fn alias_interfaces(&self, metrics: &mut [Metric]) {
+        let key = if self.network.alias_as_device {
+            "device"
+        } else {
+            "alias"
+        };
+        for metric in metrics {
+            let alias = metric
+                .tag_value("device")
+                .and_then(|device| self.network.aliases.get(&device));
+            if let Some(alias) = alias {
+                metric.insert_tag(key.into(), alias.clone());
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn net_dev_metrics(
+        &self,
+        stats: &NetDevStats,
+        interface: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Vec<Metric> {
+        vec![
+            self.counter(
+                "network_receive_compressed_total",
+                timestamp,
+                stats.receive_compressed as f64,
+                btreemap! { "device" => interface },
+            ),
+            self.counter(
+                "network_receive_multicast_total",
+                timestamp,
+                stats.receive_multicast as f64,
+                btreemap! { "device" => interface },
+            ),
+            self.counter(
+                "network_transmit_compressed_total",
+                timestamp,
+                stats.transmit_compressed as f64,
+                btreemap! { "device" => interface },
+            ),
+        ]
+    }
+
+    /// Reports the IPv6 traffic of an interface, which the kernel counts
+    /// separately from the traffic of other protocols.
+    #[cfg(target_os = "linux")]
+    fn ipv6_interface_metrics(&self, interface: &str, timestamp: DateTime<Utc>) -> Vec<Metric> {
+        // The statistics are missing when IPv6 is disabled.
+        let stats =
+            match std::fs::read_to_string(self.procfs_root().join("net/dev_snmp6").join(interface))
+            {
+                Ok(stats) => parse_dev_snmp6(&stats),
+                Err(_) => return Vec::new(),
+            };
+        vec![
+            ("network_receive_bytes_total", "Ip6InOctets"),
+            ("network_receive_packets_total", "Ip6InReceives"),
+            ("network_transmit_bytes_total", "Ip6OutOctets"),
+            ("network_transmit_packets_total", "Ip6OutRequests"),
+        ]
+        .into_iter()
+        .filter_map(|(name, field)| {
+            let value = *stats.get(field)?;
+            Some(self.counter(
+                name,
+                timestamp,
+                value as f64,
+                btreemap! { "device" => interface, "family" => "ipv6" },
+            ))
+        })
+        .collect()
+    }
+
+    pub async fn filesystem_metrics(&self) -> Vec<Metric> {
+        if !self.filesystem.paths.is_empty() {
+            return self.filesystem_path_metrics().await;
+        }
+        #[cfg(target_os = "linux")]
+        let read_only_mounts =
+            filesystem::read_only_mounts(&self.procfs_root(), self.error_log_rate_secs());
+        #[cfg(target_os = "linux")]
+        let mount_devices = filesystem::mount_devices(
+            &self.procfs_root(),
+            &self.sysfs_root(),
+            self.error_log_rate_secs(),
+        );
+        // Stuck automounts can block the enumeration, which is cut short
+        // rather than stalling the scrape.
+        let timeout = self
+            .filesystem
+            .enumeration_timeout_secs
+            .unwrap_or(self.scrape_interval_secs);
+        let deadline = time::Instant::now() + time::Duration::from_secs(timeout);
+        #[cfg_attr(not(target_os = "linux"), allow(clippy::match_single_binding))]
+        let mounts = match self.filesystem.mount_source {
+            #[cfg(target_os = "linux")]
+            MountSource::Mountinfo => match filesystem::read_mounts(&self.procfs_root()) {
+                Ok(mounts) => mounts,
+                Err(error) => {
+                    error!(message = "Failed to load mount info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                    return self.collect_error(&error);
+                }
+            },
+            _ => {
+                let partitions = match time::timeout_at(deadline, heim::disk::partitions()).await {
+                    Ok(Ok(partitions)) => partitions,
+                    Ok(Err(error)) => {
+                        error!(message = "Failed to load partitions info", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                        return self.collect_error(&error);
+                    }
+                    Err(_) => {
+                        warn!(
+                            message = "Timed out enumerating partitions.",
+                            internal_log_rate_secs = self.error_log_rate_secs()
+                        );
+                        return Vec::new();
+                    }
+                };
+                until_deadline(
+                    partitions,
+                    deadline,
+                    "Timed out enumerating partitions.",
+                    self.error_log_rate_secs(),
+                )
+                .filter_map(|result| {
+                    filter_result(
+                        result,
+                        "Failed to load/parse partition data.",
+                        self.error_log_rate_secs(),
+                    )
+                })
+                .map(Mount::from)
+                .collect::<Vec<_>>()
+                .await
+            }
+        };
+        let mounts = stream::iter(mounts)
+            // Filter on configured mountpoints
+            .map(|mount| {
+                self.filesystem
+                    .mountpoints
+                    .contains_path(Some(&mount.mount_point))
+                    .then(|| mount)
+            })
+            .filter_map(|mount| async { mount })
+            // Filter on configured devices
+            .map(|mount| {
+                self.filesystem
+                    .devices
+                    .contains_path(mount.device.as_deref())
+                    .then(|| mount)
+            })
+            .filter_map(|mount| async { mount })
+            // Filter on configured filesystems
+            .map(|mount| {
+                self.filesystem
+                    .filesystems
+                    .contains_str(Some(self.filesystem_type(&mount.file_system)))
+                    .then(|| mount)
+            })
+            .filter_map(|mount| async { mount });
+        // Load usage from the mount point
+        let limit = Semaphore::new(self.concurrency_limit());
+        load_concurrently(mounts, &limit, |mount| async move {
+            // A slow call is itself a sign of a degraded mount
+            let (usage, duration) =
+                timed(heim::disk::usage(self.host_path(&mount.mount_point))).await;
+            usage
+                .map_err(|error| {
+                    error!(
+                        message = "Failed to load partition usage data.",
+                        mount_point = ?mount.mount_point,
+                        %error,
+                        internal_log_rate_secs = self.error_log_rate_secs(),
+                    )
+                })
+                .map(|usage| (mount, usage, duration))
+                .ok()
+        })
+        .filter_map(|usage| async move {
+            usage.filter(|(_, usage, _)| self.filesystem_large_enough(usage.total().get::<byte>()))
+        })
+        .map(|(mount, usage, duration)| {
+            let timestamp = self.now();
+            let mut tags = btreemap! {
+                "mountpoint" => mount.mount_point.to_string_lossy()
+            };
+            self.insert_filesystem_type(&mut tags, &mount.file_system);
+            self.insert_mount_class(&mut tags, &mount.mount_point);
+            if let Some(device) = &mount.device {
+                tags.insert("device".into(), device.to_string_lossy().into());
+            }
+            // Also tag the name the disk metrics know the device by
+            #[cfg(target_os = "linux")]
+            if let Some(device) = mount_devices.get(&mount.mount_point) {
+                tags.insert("block_device".into(), device.clone());
+            }
+            #[cfg(target_os = "linux")]
+            let read_only = self.filesystem_readonly(
+                &read_only_mounts,
+                &mount.mount_point,
+                timestamp,
+                tags.clone(),
+            );
+            #[cfg(not(target_os = "linux"))]
+            let read_only = None;
+            let stat_duration = self.gauge(
+                "filesystem_stat_duration_seconds",
+                timestamp,
+                duration.as_secs_f64(),
+                tags.clone(),
+            );
+            #[cfg(target_os = "linux")]
+            let errors = self.filesystem_errors(
+                mount_devices.get(&mount.mount_point),
+                timestamp,
+                tags.clone(),
+            );
+            let mut metrics = self.filesystem_usage_metrics(&usage, timestamp, tags, read_only);
+            metrics.push(stat_duration);
+            #[cfg(target_os = "linux")]
+            metrics.extend(errors);
+            stream::iter(metrics)
+        })
+        .flatten()
+        .collect::<Vec<_>>()
+        .await
+    }
+
+    /// Reports the usage of the filesystem at `path`, and the mount
+    /// containing it, for each of the configured paths. The paths are those
+    /// of the monitored system, so are resolved under `host_root`.
+    async fn filesystem_path_metrics(&self) -> Vec<Metric> {
+        #[cfg(target_os = "linux")]
+        let read_only_mounts =
+            filesystem::read_only_mounts(&self.procfs_root(), self.error_log_rate_secs());
+        #[cfg(target_os = "linux")]
+        let mount_devices = filesystem::mount_devices(
+            &self.procfs_root(),
+            &self.sysfs_root(),
+            self.error_log_rate_secs(),
+        );
+        let limit = Semaphore::new(self.concurrency_limit());
+        load_concurrently(
+            stream::iter(&self.filesystem.paths),
+            &limit,
+            |path| async move {
+                heim::disk::usage(self.host_path(path))
+                    .await
+                    .map_err(|error| {
+                        error!(
+                            message = "Failed to load path usage data.",
+                            path = ?path,
+                            %error,
+                            internal_log_rate_secs = self.error_log_rate_secs(),
+                        )
+                    })
+                    .map(|usage| (path, usage))
+                    .ok()
+            },
+        )
+        .filter_map(|usage| async { usage })
+        .map(|(path, usage)| {
+            let timestamp = self.now();
+            let mount_point = containing_mount(&self.host_path(path), &self.host_path("/"));
+            let mut tags = btreemap! { "path" => path.to_string_lossy() };
+            if let Some(mount_point) = &mount_point {
+                tags.insert("mountpoint".into(), mount_point.to_string_lossy().into());
+                self.insert_mount_class(&mut tags, mount_point);
+            }
+            #[cfg(target_os = "linux")]
+            let device = mount_point
+                .as_ref()
+                .and_then(|mount| mount_devices.get(mount));
+            #[cfg(target_os = "linux")]
+            if let Some(device) = device {
+                tags.insert("block_device".into(), device.clone());
+            }
+            #[cfg(target_os = "linux")]
+            let read_only = mount_point.as_ref().and_then(|mount_point| {
+                self.filesystem_readonly(&read_only_mounts, mount_point, timestamp, tags.clone())
+            });
+            #[cfg(not(target_os = "linux"))]
+            let read_only = None;
+            #[cfg(target_os = "linux")]
+            let errors = self.filesystem_errors(device, timestamp, tags.clone());
+            #[allow(unused_mut)]
+            let mut metrics = self.filesystem_usage_metrics(&usage, timestamp, tags, read_only);
+            #[cfg(target_os = "linux")]
+            metrics.extend(errors);
+            stream::iter(metrics)
+        })
+        .flatten()
+        .collect::<Vec<_>>()
+        .await
+    }
+
+    /// The type of a filesystem as tagged and filtered on.
+    fn filesystem_type<'a>(&self, raw: &'a str) -> &'a str {
+        if self.filesystem.normalize_types {
+            normalize_filesystem_type(raw)
+        } else {
+            raw
+        }
+    }
+
+    fn insert_filesystem_type(&self, tags: &mut BTreeMap<String, String>, raw: &str) {
+        tags.insert("filesystem".into(), self.filesystem_type(raw).into());
+        if self.filesystem.normalize_types {
+            tags.insert("raw_fstype".into(), raw.into());
+        }
+    }
+
+    /// Tags `mount_point` with its class, if any classes are configured.
+    fn insert_mount_class(&self, tags: &mut BTreeMap<String, String>, mount_point: &Path) {
+        if self.filesystem.classes.is_empty() {
+            return;
+        }
+        let mut class = "other";
+        let mut longest = None;
+        for (name, prefixes) in &self.filesystem.classes {
+            for prefix in prefixes
+                .iter()
+                .filter(|prefix| mount_point.starts_with(prefix))
+            {
+                let length = prefix.components().count();
+                if longest.map_or(true, |longest| length > longest) {
+                    class = name;
+                    longest = Some(length);
+                }
+            }
+        }
+        tags.insert("class".into(), class.into());
+    }
+
+    fn filesystem_usage_metrics(
+        &self,
+        usage: &heim::disk::Usage,
+        timestamp: DateTime<Utc>,
+        tags: BTreeMap<String, String>,
+        read_only: Option<Metric>,
+    ) -> Vec<Metric> {
+        let total = usage.total().get::<byte>() as f64;
+        let near_full = self.filesystem_near_full(
+            usage.used().get::<byte>() as f64,
+            total,
+            timestamp,
+            tags.clone(),
+        );
+        vec![
+            self.gauge(
+                "filesystem_free_bytes",
+                timestamp,
+                self.clamp_value(
+                    "filesystem_free_bytes",
+                    usage.free().get::<byte>() as f64,
+                    0.0,
+                    total,
+                ),
+                tags.clone(),
+            ),
+            self.gauge("filesystem_total_bytes", timestamp, total, tags.clone()),
+            self.gauge(
+                "filesystem_used_bytes",
+                timestamp,
+                self.clamp_value(
+                    "filesystem_used_bytes",
+                    usage.used().get::<byte>() as f64,
+                    0.0,
+                    total,
+                ),
+                tags.clone(),
+            ),
+            #[cfg(not(target_os = "windows"))]
+            self.gauge(
+                "filesystem_used_ratio",
+                timestamp,
+                self.clamp_value(
+                    "filesystem_used_ratio",
+                    usage.ratio().get::<ratio>() as f64,
+                    0.0,
+                    1.0,
+                ),
+                tags,
+            ),
+        ]
+        .into_iter()
+        .chain(Some(near_full))
+        .chain(read_only)
+        .collect()
+    }
+
+    fn filesystem_large_enough(&self, total: u64) -> bool {
+        total >= self.filesystem.min_size_bytes
+    }
+
+    /// Flags a mount whose used ratio exceeds the configured threshold.
+    /// Mounts without any capacity are never near full.
+    fn filesystem_near_full(
+        &self,
+        used: f64,
+        total: f64,
+        timestamp: DateTime<Utc>,
+        tags: BTreeMap<String, String>,
+    ) -> Metric {
+        let near_full = total > 0.0 && used / total > self.filesystem.near_full_threshold;
+        self.gauge(
+            "filesystem_near_full",
+            timestamp,
+            if near_full { 1.0 } else { 0.0 },
+            tags,
+        )
+    }
+
+    pub async fn disk_metrics(&self) -> Vec<Metric> {
+        #[cfg(target_os = "linux")]
+        let mount_points = filesystem::device_mount_points(&filesystem::mount_devices(
+            &self.procfs_root(),
+            &self.sysfs_root(),
+            self.error_log_rate_secs(),
+        ));
+        match heim::disk::io_counters().await {
+            Ok(counters) => {
+                counters
+                    .filter_map(|result| {
+                        filter_result(
+                            result,
+                            "Failed to load/parse disk I/O data.",
+                            self.error_log_rate_secs(),
+                        )
+                    })
+                    .map(|counter| {
+                        self.disk
+                            .devices
+                            .contains_path(Some(counter.device_name().as_ref()))
+                            .then(|| counter)
+                    })
+                    .filter_map(|counter| async { counter })
+                    .map(|counter| {
+                        let timestamp = self.now();
+                        let device = counter.device_name().to_string_lossy();
+                        #[allow(unused_mut)]
+                        let mut tags = self.disk_tags(&device);
+                        #[cfg(target_os = "linux")]
+                        if let Some(mount_point) = mount_points.get(&*device) {
+                            tags.insert("mountpoint".into(), mount_point.to_string_lossy().into());
+                            self.insert_mount_class(&mut tags, mount_point);
+                        }
+                        #[cfg(target_os = "linux")]
+                        let mut extra = disk::io_errors(&self.sysfs_root(), &device)
+                            .map(|errors| {
+                                self.counter(
+                                    "disk_errors_total",
+                                    timestamp,
+                                    errors as f64,
+                                    tags.clone(),
+                                )
+                            })
+                            .into_iter()
+                            .collect::<Vec<_>>();
+                        #[cfg(target_os = "linux")]
+                        for &(name, flag) in
+                            &[("disk_readonly", "ro"), ("disk_removable", "removable")]
+                        {
+                            if let Some(set) = disk::flag(&self.sysfs_root(), &device, flag) {
+                                extra.push(self.gauge(
+                                    name,
+                                    timestamp,
+                                    if set { 1.0 } else { 0.0 },
+                                    tags.clone(),
+                                ));
+                            }
+                        }
+                        #[cfg(not(target_os = "linux"))]
+                        let extra: Vec<Metric> = Vec::new();
+                        stream::iter(
+                            vec![
+                                self.counter(
+                                    "disk_read_bytes_total",
+                                    timestamp,
+                                    counter.read_bytes().get::<byte>() as f64,
+                                    tags.clone(),
+                                ),
+                                self.counter(
+                                    "disk_reads_completed_total",
+                                    timestamp,
+                                    counter.read_count() as f64,
+                                    tags.clone(),
+                                ),
+                                self.counter(
+                                    "disk_written_bytes_total",
+                                    timestamp,
+                                    counter.write_bytes().get::<byte>() as f64,
+                                    tags.clone(),
+                                ),
+                                self.counter(
+                                    "disk_writes_completed_total",
+                                    timestamp,
+                                    counter.write_count() as f64,
+                                    tags,
+                                ),
+                            ]
+                            .into_iter()
+                            .chain(extra),
+                        )
+                    })
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .await
+            }
+            Err(error) => {
+                error!(message = "Failed to load disk I/O info.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                self.collect_error(&error)
+            }
+        }
+    }
+
+    /// Reports a failed collection as a `collect_error` gauge, if enabled.
+    /// Only the start of the first line of the error is kept as a tag, to
+    /// bound the cardinality.
+    fn collect_error(&self, error: &dyn fmt::Display) -> Vec<Metric> {
+        if !self.emit_errors {
+            return Vec::new();
+        }
+        let error = error.to_string();
+        let error = error.lines().next().unwrap_or_default().trim();
+        let error = match error.char_indices().nth(MAX_ERROR_LENGTH) {
+            Some((index, _)) => &error[..index],
+            None => error,
+        };
+        vec![self.gauge(
+            "collect_error",
+            self.now(),
+            1.0,
+            btreemap! { "error" => error },
+        )]
+    }
+
+    /// Clamps a derived value, which platform quirks can push slightly out
+    /// of range (such as more bytes used than available), into `[min, max]`.
+    fn clamp_value(&self, name: &str, value: f64, min: f64, max: f64) -> f64 {
+        if !self.clamp_values.unwrap_or(true) || (min..=max).contains(&value) {
+            value
+        } else {
+            debug!(message = "Clamping out of range value.", %name, %value, %min, %max);
+            value.clamp(min, max)
+        }
+    }
+
+    fn concurrency_limit(&self) -> usize {
+        self.max_concurrency.unwrap_or_else(num_cpus::get).max(1)
+    }
+
+    /// The rate limit of the collection error logs.
+    fn error_log_rate_secs(&self) -> u64 {
+        self.error_log_interval_secs
+            .unwrap_or(DEFAULT_ERROR_LOG_INTERVAL_SECS)
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        (self.clock.0)()
+    }
+
+    /// Aligns timestamps to the scrape interval, if configured, so series
+    /// from multiple hosts line up.
+    fn align_timestamp(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        match self.timestamp_alignment {
+            TimestampAlignment::Interval if self.scrape_interval_secs > 0 => {
+                let seconds = timestamp.timestamp();
+                let interval = self.scrape_interval_secs as i64;
+                Utc.timestamp(seconds - seconds.rem_euclid(interval), 0)
+            }
+            _ => timestamp,
+        }
+    }
+
+    fn disk_tags(&self, device: &str) -> BTreeMap<String, String> {
+        #[allow(unused_mut)]
+        let mut tags = btreemap! { "device" => device };
+        #[cfg(target_os = "linux")]
+        if self.disk.resolve_dm_names {
+            if let Some(name) = disk::dm_name(&self.sysfs_root(), device) {
+                tags.insert("name".into(), name);
+            }
+        }
+        #[cfg(target_os = "linux")]
+        if self.disk.serial_tags {
+            let udev_data = self.host_path("/run/udev/data");
+            if let Some(serial) = disk::serial(&self.sysfs_root(), &udev_data, device) {
+                tags.insert("serial".into(), serial);
+            }
+        }
+        tags
+    }
+
+    /// The path of a file of the monitored system, which is mounted under
+    /// `host_root` if configured.
+    fn host_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        match &self.host_root {
+            Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.to_path_buf(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn procfs_root(&self) -> PathBuf {
+        std::env::var_os("PROCFS_ROOT")
+            .map(Into::into)
+            .unwrap_or_else(|| self.host_path("/proc"))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sysfs_root(&self) -> PathBuf {
+        std::env::var_os("SYSFS_ROOT")
+            .map(Into::into)
+            .unwrap_or_else(|| self.host_path("/sys"))
+    }
+
+    /// The namespace and name of a metric, combined into the name when a
+    /// name template is configured.
+    fn metric_name(&self, name: &str) -> (Option<String>, String) {
+        let namespace = self
+            .namespace
+            .0
+            .as_deref()
+            .filter(|namespace| !namespace.is_empty());
+        match &self.name_template {
+            Some(template) => (None, template.render(namespace, name)),
+            None => (namespace.map(Into::into), name.to_string()),
+        }
+    }
+
+    fn counter(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        value: f64,
+        tags: BTreeMap<String, String>,
+    ) -> Metric {
+        self.absolute(name, ValueType::Counter, timestamp, value, tags)
+    }
+
+    fn gauge(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        value: f64,
+        tags: BTreeMap<String, String>,
+    ) -> Metric {
+        self.absolute(name, ValueType::Gauge, timestamp, value, tags)
+    }
+
+    /// The value type a metric is emitted with, which `metric_types` takes
+    /// precedence for.
+    fn value_type(&self, name: &str, value_type: ValueType) -> ValueType {
+        match self.metric_types.get(name) {
+            Some(&value_type) => value_type,
+            // Some sinks only understand cumulative values as gauges, so keep
+            // the `_total` name but change the value type.
+            None if self.counters_as_gauges => ValueType::Gauge,
+            None => value_type,
+        }
+    }
+
+    fn absolute(
+        &self,
+        name: &str,
+        value_type: ValueType,
+        timestamp: DateTime<Utc>,
+        value: f64,
+        tags: BTreeMap<String, String>,
+    ) -> Metric {
+        let value_type = self.value_type(name, value_type);
+        let (name, value) = self.time_unit.convert(name, value);
+        let value = match value_type {
+            ValueType::Counter => MetricValue::Counter { value },
+            ValueType::Gauge => MetricValue::Gauge { value },
+        };
+        let (namespace, name) = self.metric_name(&name);
+        Metric::new(name, MetricKind::Absolute, value)
+            .with_namespace(namespace)
+            .with_tags(Some(self.truncate_tags(tags)))
+            .with_timestamp(Some(self.align_timestamp(timestamp)))
+    }
+
+    /// Cuts tag values down to `max_tag_value_len` characters, marking the
+    /// truncation with an ellipsis.
+    fn truncate_tags(&self, mut tags: BTreeMap<String, String>) -> BTreeMap<String, String> {
+        if let Some(max_len) = self.max_tag_value_len {
+            for value in tags.values_mut() {
+                if value.chars().count() > max_len {
+                    *value = value
+                        .chars()
+                        .take(max_len.saturating_sub(1))
+                        .chain(Some('…'))
+                        .collect();
+                }
+            }
+        }
+        tags
+    }
+
+    #[cfg(target_os = "linux")]
+    fn distribution(
+        &self,
+        name: &str,
+        timestamp: DateTime<Utc>,
+        values: Vec<f64>,
+        tags: BTreeMap<String, String>,
+    ) -> Metric {
+        let (name, scale) = self.time_unit.convert(name, 1.0);
+        let samples = values
+            .into_iter()
+            .map(|value| Sample {
+                value: value * scale,
+                rate: 1,
+            })
+            .collect();
+        let value = MetricValue::Distribution {
+            samples,
+            statistic: StatisticKind::Histogram,
+        };
+        let (namespace, name) = self.metric_name(&name);
+        Metric::new(name, MetricKind::Absolute, value)
+            .with_namespace(namespace)
+            .with_tags(Some(self.truncate_tags(tags)))
+            .with_timestamp(Some(self.align_timestamp(timestamp)))
+    }
+}
+
+async fn filter_result<T>(
+    result: Result<T, Error>,
+    message: &'static str,
+    log_rate_secs: u64,
+) -> Option<T> {
+    result
+        .map_err(|error| error!(message, %error, internal_log_rate_secs = log_rate_secs))
+        .ok()
+}
+
+/// Maps the type of a FUSE or overlay filesystem, such as `fuseblk.ntfs-3g`,
+/// to the name of the filesystem it implements. Other types are returned
+/// unchanged.
+fn normalize_filesystem_type(raw: &str) -> &str {
+    // FUSE filesystems are reported as `fuse[blk].<subtype>` if they set a
+    // subtype.
+    let fs = raw
+        .strip_prefix("fuseblk.")
+        .or_else(|| raw.strip_prefix("fuse."))
+        .unwrap_or(raw);
+    match fs {
+        "ntfs-3g" | "ntfs3" => "ntfs",
+        "exfat-fuse" => "exfat",
+        "squashfuse" => "squashfs",
+        "fuse-overlayfs" | "overlayfs" => "overlay",
+        "fuse2fs" => "ext4",
+        _ => fs,
+    }
+}
+
+/// Finds the mount point of the filesystem containing `path`, as the
+/// highest of its ancestors on the same device. Bind mounts of the same
+/// filesystem resolve to the topmost one. The search stops at `root`, the
+/// mount point being returned relative to it as seen from the monitored
+/// system.
+#[cfg(unix)]
+fn containing_mount(path: &Path, root: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let path = std::fs::canonicalize(path).ok()?;
+    let root = std::fs::canonicalize(root).ok()?;
+    let device = std::fs::metadata(&path).ok()?.dev();
+    let mut mount_point = path.as_path();
+    for parent in path.ancestors().skip(1) {
+        if !parent.starts_with(&root) || std::fs::metadata(parent).ok()?.dev() != device {
+            break;
+        }
+        mount_point = parent;
+    }
+    Some(Path::new("/").join(mount_point.strip_prefix(&root).ok()?))
+}
+
+/// Finds the drive containing `path`.
+#[cfg(not(unix))]
+fn containing_mount(path: &Path, _root: &Path) -> Option<PathBuf> {
+    path.ancestors().last().map(Path::to_path_buf)
+}
+
+/// Drops all but the last of the metrics sharing a series, which would
+/// otherwise be counted twice downstream, returning the number dropped.
+fn dedupe_series(metrics: Vec<Metric>) -> (Vec<Metric>, usize) {
+    let mut last = HashMap::with_capacity(metrics.len());
+    for (index, metric) in metrics.iter().enumerate() {
+        last.insert((metric.namespace(), metric.name(), metric.tags()), index);
+    }
+    let duplicates = metrics.len() - last.len();
+    if duplicates == 0 {
+        return (metrics, 0);
+    }
+    let mut keep = vec![false; metrics.len()];
+    for (_, index) in last {
+        keep[index] = true;
+    }
+    let metrics = metrics
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(metric, keep)| keep.then(|| metric))
+        .collect();
+    (metrics, duplicates)
+}
+
+/// Drops the counters and gauges whose value is NaN or infinite, such as
+/// ratios of empty totals, returning the names of those dropped.
+fn drop_non_finite(metrics: Vec<Metric>) -> (Vec<Metric>, Vec<String>) {
+    let mut dropped = Vec::new();
+    let metrics = metrics
+        .into_iter()
+        .filter(|metric| match metric.value() {
+            MetricValue::Counter { value } | MetricValue::Gauge { value } if !value.is_finite() => {
+                dropped.push(metric.name().to_string());
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    (metrics, dropped)
+}
+
+/// Combines the load average per core, capped at 1 as all CPUs are busy from
+/// there on, with the share of time tasks were stalled waiting for a CPU,
+/// by taking the larger of both. Without pressure stall information, this
+/// is the capped load per core alone.
+#[cfg(unix)]
+fn cpu_saturation_ratio(load1: f64, cores: u64, pressure: Option<f64>) -> f64 {
+    let load = if cores > 0 {
+        (load1 / cores as f64).min(1.0)
+    } else {
+        0.0
+    };
+    match pressure {
+        Some(pressure) => load.max(pressure.min(1.0)),
+        None => load,
+    }
+}
+
+/// The nearest-rank percentile of the non-empty `sorted` values, `quantile`
+/// being in `(0, 1]`.
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Ends `stream` at `deadline`, keeping the items it yielded until then and
+/// logging `message` if it was cut short.
+fn until_deadline<S: Stream>(
+    stream: S,
+    deadline: time::Instant,
+    message: &'static str,
+    log_rate_secs: u64,
+) -> impl Stream<Item = S::Item> {
+    stream.take_until(async move {
+        time::sleep_until(deadline).await;
+        warn!(message, internal_log_rate_secs = log_rate_secs);
+    })
+}
+
+fn warn_unmatched_includes(option: &'static str, filter: &FilterList, candidates: &[String]) {
+    for pattern in filter.unmatched_includes(candidates) {
+        warn!(
+            message = "Include pattern matches nothing found on this host, so no metrics will be collected for it.",
+            option,
+            pattern,
+            found = ?candidates,
+        );
+    }
+}
+
+/// Runs `load` for every item, with up to as many calls in flight as `limit`
+/// has permits, keeping the order of the items.
+fn load_concurrently<'a, T, U, F, Fut>(
+    items: impl Stream<Item = T> + 'a,
+    limit: &'a Semaphore,
+    load: F,
+) -> impl Stream<Item = U> + 'a
+where
+    T: 'a,
+    F: Fn(T) -> Fut + 'a,
+    Fut: Future<Output = U> + 'a,
+{
+    items
+        .map(move |item| {
+            let future = load(item);
+            async move {
+                let _permit = limit.acquire().await;
+                future.await
+            }
+        })
+        // The semaphore bounds the calls in flight
+        .buffered(usize::MAX)
+}
+
+/// Runs `future`, returning its output along with how long it took.
+async fn timed<F: Future>(future: F) -> (F::Output, time::Duration) {
+    let start = time::Instant::now();
+    let output = future.await;
+    (output, start.elapsed())
+}
+
+/// Drops the counters whose value equals the one seen in the previous scrape,
+/// replacing `previous` with the values of this scrape.
+fn drop_unchanged_counters(
+    events: impl Iterator<Item = Event>,
+    previous: &mut HashMap<MetricSeries, f64>,
+) -> Vec<Event> {
+    let mut current = HashMap::new();
+    let events = events
+        .filter(|event| {
+            let metric = event.as_metric();
+            match metric.value() {
+                MetricValue::Counter { value } => {
+                    current.insert(metric.series().clone(), *value);
+                    previous.get(metric.series()) != Some(value)
+                }
+                _ => true,
+            }
+        })
+        .collect();
+    *previous = current;
+    events
+}
+
+/// Adds the per-second rate of every counter also seen in the previous
+/// scrape, replacing `previous` with the values and timestamps of this
+/// scrape.
+fn add_counter_rates(
+    mut events: Vec<Event>,
+    previous: &mut HashMap<MetricSeries, (f64, DateTime<Utc>)>,
+) -> Vec<Event> {
+    let mut current = HashMap::new();
+    let mut rates = Vec::new();
+    for event in &events {
+        let metric = event.as_metric();
+        let (value, timestamp) = match (metric.value(), metric.timestamp()) {
+            (MetricValue::Counter { value }, Some(timestamp)) => (*value, timestamp),
+            _ => continue,
+        };
+        if let Some(&(last_value, last_timestamp)) = previous.get(metric.series()) {
+            let elapsed = (timestamp - last_timestamp).num_milliseconds() as f64 / 1000.0;
+            // Counters that went backwards were reset, leaving no rate.
+            if elapsed > 0.0 && value >= last_value {
+                let rate = metric
+                    .clone()
+                    .with_name(rate_name(metric.name()))
+                    .with_value(MetricValue::Gauge {
+                        value: (value - last_value) / elapsed,
+                    });
+                rates.push(Event::from(rate));
+            }
+        }
+        current.insert(metric.series().clone(), (value, timestamp));
+    }
+    *previous = current;
+    events.extend(rates);
+    events
+}
+
+/// Adds the time every counter started counting from, in seconds since the
+/// epoch, replacing `previous` with the values and times of this scrape.
+/// Counters are taken to have started along with the source, or at the
+/// scrape they were found to have been reset in. The time stays in seconds
+/// whatever the configured time unit, as OpenMetrics requires, while being
+/// named after the counter as converted so the two share a family.
+fn add_created_gauges(
+    mut events: Vec<Event>,
+    started: DateTime<Utc>,
+    previous: &mut HashMap<MetricSeries, (f64, DateTime<Utc>)>,
+) -> Vec<Event> {
+    let mut current = HashMap::new();
+    let mut created = Vec::new();
+    for event in &events {
+        let metric = event.as_metric();
+        let value = match metric.value() {
+            MetricValue::Counter { value } => *value,
+            _ => continue,
+        };
+        let since = match previous.get(metric.series()) {
+            Some(&(last_value, since)) if value >= last_value => since,
+            Some(_) => metric.timestamp().unwrap_or(started),
+            None => started,
+        };
+        created.push(Event::from(
+            metric
+                .clone()
+                .with_name(created_name(metric.name()))
+                .with_value(MetricValue::Gauge {
+                    value: since.timestamp_millis() as f64 / 1000.0,
+                }),
+        ));
+        current.insert(metric.series().clone(), (value, since));
+    }
+    *previous = current;
+    events.extend(created);
+    events
+}
+
+/// Runs `capture` twice, `window` apart, returning only the counter rates
+/// added to the second capture.
+async fn capture_counter_rates<F, Fut>(window: time::Duration, capture: F) -> Vec<Metric>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Vec<Event>>,
+{
+    let mut previous = HashMap::new();
+    add_counter_rates(capture().await, &mut previous);
+    time::sleep(window).await;
+    let events = capture().await;
+    let captured = events.len();
+    add_counter_rates(events, &mut previous)
+        .into_iter()
+        .skip(captured)
+        .map(Event::into_metric)
+        .collect()
+}
+
+/// Adds the exponentially weighted moving average of the gauges named in
+/// `names`, replacing `averages` with those of this scrape. The average of a
+/// series starts out at its first value.
+fn add_smoothed_gauges(
+    mut events: Vec<Event>,
+    names: &HashSet<String>,
+    factor: f64,
+    averages: &mut HashMap<MetricSeries, f64>,
+) -> Vec<Event> {
+    let mut current = HashMap::new();
+    let mut smoothed = Vec::new();
+    for event in &events {
+        let metric = event.as_metric();
+        let value = match metric.value() {
+            MetricValue::Gauge { value } if names.contains(metric.name()) => *value,
+            _ => continue,
+        };
+        let average = match averages.get(metric.series()) {
+            Some(average) => factor * value + (1.0 - factor) * average,
+            None => value,
+        };
+        smoothed.push(Event::from(
+            metric
+                .clone()
+                .with_name(format!("{}_smoothed", metric.name()))
+                .with_value(MetricValue::Gauge { value: average }),
+        ));
+        current.insert(metric.series().clone(), average);
+    }
+    *averages = current;
+    events.extend(smoothed);
+    events
+}
+
+/// Turns the counters of `collector` into increments since the previous
+/// scrape, replacing `previous` with their values. Counters not seen before,
+/// such as all of them on the first scrape after startup, increment by zero
+/// rather than by everything counted since boot. Counters that went
+/// backwards were reset, so they increment by their whole value.
+fn make_counters_incremental(
+    events: Vec<Event>,
+    collector: Collector,
+    previous: &mut HashMap<MetricSeries, f64>,
+) -> Vec<Event> {
+    let mut current = HashMap::new();
+    let events = events
+        .into_iter()
+        .map(|event| {
+            let metric = event.into_metric();
+            let value = match metric.value() {
+                MetricValue::Counter { value }
+                    if metric.tag_matches("collector", collector.as_str()) =>
+                {
+                    *value
+                }
+                _ => return Event::from(metric),
+            };
+            let increment = match previous.get(metric.series()) {
+                Some(&last) if value >= last => value - last,
+                Some(_) => value,
+                None => 0.0,
+            };
+            current.insert(metric.series().clone(), value);
+            Event::from(
+                metric
+                    .with_value(MetricValue::Counter { value: increment })
+                    .into_incremental(),
+            )
+        })
+        .collect();
+    *previous = current;
+    events
+}
+
+/// Drops the metrics named in `names` from all but the first and every
+/// `every`th scrape after it, counting scrapes from zero.
+fn drop_inventory_metrics(
+    mut events: Vec<Event>,
+    names: &HashSet<String>,
+    scrape: u64,
+    every: NonZeroU64,
+) -> Vec<Event> {
+    if scrape % every.get() != 0 {
+        events.retain(|event| !names.contains(event.as_metric().name()));
+    }
+    events
+}
+
+/// The name of the rate of a counter, which drops the `_total` suffix so
+/// `cpu_seconds_total` becomes `cpu_seconds_rate`.
+fn rate_name(name: &str) -> String {
+    format!("{}_rate", name.strip_suffix("_total").unwrap_or(name))
+}
+
+/// The name of the creation time of a counter, which drops the `_total`
+/// suffix so `cpu_seconds_total` becomes `cpu_seconds_created`.
+fn created_name(name: &str) -> String {
+    format!("{}_created", name.strip_suffix("_total").unwrap_or(name))
+}
+
+/// Reduces the gauges of a window of samples into `<name>_min`,
+/// `<name>_max` and `<name>_avg` gauges for each series.
+fn summarize(window: &[Vec<Metric>], timestamp: DateTime<Utc>) -> Vec<Metric> {
+    let mut series = IndexMap::<MetricSeries, (Metric, Vec<f64>)>::new();
+    for metric in window.iter().flatten() {
+        if let MetricValue::Gauge { value } = metric.value() {
+            series
+                .entry(metric.series().clone())
+                .or_insert_with(|| (metric.clone(), Vec::new()))
+                .1
+                .push(*value);
+        }
+    }
+
+    series
+        .into_iter()
+        .flat_map(|(_, (metric, values))| {
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let avg = values.iter().sum::<f64>() / values.len() as f64;
+            vec![("min", min), ("max", max), ("avg", avg)]
+                .into_iter()
+                .map(move |(suffix, value)| {
+                    metric
+                        .clone()
+                        .with_name(format!("{}_{}", metric.name(), suffix))
+                        .with_value(MetricValue::Gauge { value })
+                        .with_timestamp(Some(timestamp))
+                })
+        })
+        .collect()
+}
+
+fn add_collector(collector: &str, mut metrics: Vec<Metric>) -> Vec<Metric> {
+    for metric in &mut metrics {
+        metric.insert_tag("collector".into(), collector.into());
+    }
+    metrics
+}
+
+/// Points heim at procfs and sysfs, which it reads from a single location
+/// for the whole process, so `host_root` is taken from the first source built.
+fn init_roots(host_root: Option<&Path>) {
+    #[cfg(not(target_os = "linux"))]
+    let _ = host_root;
+    #[cfg(target_os = "linux")]
+    {
+        use std::sync::Once;
+
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            match std::env::var_os("PROCFS_ROOT") {
+                Some(procfs_root) => {
+                    info!(
+                        message = "PROCFS_ROOT is set in envvars. Using custom for procfs.",
+                        custom = ?procfs_root
+                    );
+                    heim::os::linux::set_procfs_root(std::path::PathBuf::from(&procfs_root));
+                }
+                None => match host_root {
+                    Some(host_root) => {
+                        let procfs_root = host_root.join("proc");
+                        info!(message = "Using procfs under host_root.", custom = ?procfs_root);
+                        heim::os::linux::set_procfs_root(procfs_root);
+                    }
+                    None => {
+                        info!("PROCFS_ROOT is unset. Using default '/proc' for procfs root.")
+                    }
+                },
+            };
+
+            match std::env::var_os("SYSFS_ROOT") {
+                Some(sysfs_root) => {
+                    info!(
+                        message = "SYSFS_ROOT is set in envvars. Using custom for sysfs.",
+                        custom = ?sysfs_root
+                    );
+                    heim::os::linux::set_sysfs_root(std::path::PathBuf::from(&sysfs_root));
+                }
+                None => match host_root {
+                    Some(host_root) => {
+                        let sysfs_root = host_root.join("sys");
+                        info!(message = "Using sysfs under host_root.", custom = ?sysfs_root);
+                        heim::os::linux::set_sysfs_root(sysfs_root);
+                    }
+                    None => info!("SYSFS_ROOT is unset. Using default '/sys' for sysfs root."),
+                },
+            }
+        });
+    };
+}
+
+/// Reads a file containing a single integer, as found throughout sysfs.
+#[cfg(target_os = "linux")]
+fn read_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Lists the CPUs taken offline through hotplug. CPUs without an `online`
+/// file, such as the boot CPU, can't be taken offline.
+#[cfg(target_os = "linux")]
+fn offline_cpus(sysfs: &Path) -> HashSet<usize> {
+    let entries = match std::fs::read_dir(sysfs.join("devices/system/cpu")) {
+        Ok(entries) => entries,
+        Err(_) => return HashSet::new(),
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let index = name.to_str()?.strip_prefix("cpu")?.parse().ok()?;
+            (read_u64(&entry.path().join("online")) == Some(0)).then(|| index)
+        })
+        .collect()
+}
+
+/// The number of clock ticks per second used by the `/proc` time values.
+#[cfg(target_os = "linux")]
+fn clock_ticks_per_second() -> f64 {
+    nix::unistd::sysconf(nix::unistd::SysconfVar::CLK_TCK)
+        .ok()
+        .flatten()
+        .map(|ticks| ticks as f64)
+        .unwrap_or(100.0)
+}
+
+/// Interface counters from `/proc/net/dev` which heim doesn't expose.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NetDevStats {
+    receive_compressed: u64,
+    receive_multicast: u64,
+    transmit_compressed: u64,
+}
+
+/// Reads how often the link of an interface went up or down, which kernels
+/// before 3.15 and some virtual interfaces don't count.
+#[cfg(target_os = "linux")]
+fn carrier_changes(sysfs: &Path, interface: &str) -> Option<u64> {
+    read_u64(
+        &sysfs
+            .join("class/net")
+            .join(interface)
+            .join("carrier_changes"),
+    )
+}
+
+/// Reads a numeric setting of an interface, such as its `mtu` or
+/// `tx_queue_len`.
+#[cfg(target_os = "linux")]
+fn interface_setting(sysfs: &Path, interface: &str, setting: &str) -> Option<u64> {
+    read_u64(&sysfs.join("class/net").join(interface).join(setting))
+}
+
+#[cfg(target_os = "linux")]
+fn read_net_dev(
+    procfs: &Path,
+    log_rate_secs: u64,
+) -> std::collections::HashMap<String, NetDevStats> {
+    match std::fs::read_to_string(procfs.join("net/dev")) {
+        Ok(contents) => parse_net_dev(&contents),
+        Err(error) => {
+            error!(message = "Failed to load network device statistics.", %error, internal_log_rate_secs = log_rate_secs);
+            Default::default()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn parse_net_dev(contents: &str) -> std::collections::HashMap<String, NetDevStats> {
+    contents
+        .lines()
+        // The first two lines are column headers
+        .skip(2)
+        .filter_map(|line| {
+            let (interface, fields) = line.split_once(':')?;
+            let fields = fields
+                .split_whitespace()
+                .map(|field| field.parse::<u64>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            let stats = NetDevStats {
+                receive_compressed: *fields.get(6)?,
+                receive_multicast: *fields.get(7)?,
+                transmit_compressed: *fields.get(15)?,
+            };
+            Some((interface.trim().to_string(), stats))
+        })
+        .collect()
+}
+
+/// Parses the `<field> <value>` lines of a `/proc/net/dev_snmp6/<device>`
+/// file.
+#[cfg(target_os = "linux")]
+fn parse_dev_snmp6(contents: &str) -> std::collections::HashMap<String, u64> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let value = fields.next()?.parse().ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+impl FilterList {
+    /// The include patterns matching none of `candidates`.
+    fn unmatched_includes(&self, candidates: &[String]) -> Vec<&str> {
+        self.includes
+            .iter()
+            .flatten()
+            .filter(|pattern| {
+                !candidates
+                    .iter()
+                    .any(|candidate| pattern.matches_str(candidate))
+            })
+            .map(|pattern| pattern.0.as_str())
+            .collect()
+    }
+
+    fn contains<T, M>(&self, value: &Option<T>, matches: M) -> bool
+    where
+        M: Fn(&PatternWrapper, &T) -> bool,
+    {
+        (match (&self.includes, value) {
+            // No includes list includes everything
+            (None, _) => true,
+            // Includes list matched against empty value returns false
+            (Some(_), None) => false,
+            // Otherwise find the given value
+            (Some(includes), Some(value)) => includes.iter().any(|pattern| matches(pattern, value)),
+        }) && match (&self.excludes, value) {
+            // No excludes, list excludes nothing
+            (None, _) => true,
+            // No value, never excluded
+            (Some(_), None) => true,
+            // Otherwise find the given value
+            (Some(excludes), Some(value)) => {
+                !excludes.iter().any(|pattern| matches(pattern, value))
+            }
+        }
+    }
+
+    fn excluding(patterns: &[&str]) -> Self {
+        Self {
+            includes: None,
+            excludes: Some(
+                patterns
+                    .iter()
+                    .map(|pattern| PatternWrapper::new(pattern).expect("Invalid default pattern"))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn contains_str(&self, value: Option<&str>) -> bool {
+        self.contains(&value, |pattern, s| pattern.matches_str(s))
+    }
+
+    fn contains_path(&self, value: Option<&Path>) -> bool {
+        self.contains(&value, |pattern, path| pattern.matches_path(path))
+    }
+
+    #[cfg(test)]
+    fn contains_test(&self, value: Option<&str>) -> bool {
+        let result = self.contains_str(value);
+        assert_eq!(
+            result,
+            self.contains_path(value.map(|value| std::path::Path::new(value)))
+        );
+        result
+    }
+}
+
+// Pattern doesn't implement Deserialize or Serialize, and we can't
+// implement them ourselves due the orphan rules, so make a wrapper.
+#[derive(Clone, Debug)]
+struct PatternWrapper(Pattern);
+
+impl PatternWrapper {
+    fn new(pattern: impl AsRef<str>) -> Result<PatternWrapper, PatternError> {
+        Ok(PatternWrapper(Pattern::new(pattern.as_ref())?))
+    }
+
+    fn matches_str(&self, s: &str) -> bool {
+        self.0.matches(s)
+    }
+
+    fn matches_path(&self, p: &Path) -> bool {
+        self.0.matches_path(p)
+    }
+}
+
+impl<'de> Deserialize<'de> for PatternWrapper {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PatternVisitor)
+    }
+}
+
+struct PatternVisitor;
+
+impl<'de> Visitor<'de> for PatternVisitor {
+    type Value = PatternWrapper;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "a string")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        PatternWrapper::new(s).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for PatternWrapper {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn filterlist_default_includes_everything() {
+        let filters = FilterList::default();
+        assert!(filters.contains_test(Some("anything")));
+        assert!(filters.contains_test(Some("should")));
+        assert!(filters.contains_test(Some("work")));
+        assert!(filters.contains_test(None));
+    }
+
+    #[test]
+    fn filterlist_includes_works() {
+        let filters = FilterList {
+            includes: Some(vec![
+                PatternWrapper::new("sda").unwrap(),
+                PatternWrapper::new("dm-*").unwrap(),
+            ]),
+            excludes: None,
+        };
+        assert!(!filters.contains_test(Some("sd")));
+        assert!(filters.contains_test(Some("sda")));
+        assert!(!filters.contains_test(Some("sda1")));
+        assert!(filters.contains_test(Some("dm-")));
+        assert!(filters.contains_test(Some("dm-5")));
+        assert!(!filters.contains_test(Some("xda")));
+        assert!(!filters.contains_test(None));
+    }
+
+    #[test]
+    fn filterlist_excludes_works() {
+        let filters = FilterList {
+            includes: None,
+            excludes: Some(vec![
+                PatternWrapper::new("sda").unwrap(),
+                PatternWrapper::new("dm-*").unwrap(),
+            ]),
+        };
+        assert!(filters.contains_test(Some("sd")));
+        assert!(!filters.contains_test(Some("sda")));
+        assert!(filters.contains_test(Some("sda1")));
+        assert!(!filters.contains_test(Some("dm-")));
+        assert!(!filters.contains_test(Some("dm-5")));
+        assert!(filters.contains_test(Some("xda")));
+        assert!(filters.contains_test(None));
+    }
+
+    #[test]
+    fn filterlist_includes_and_excludes_works() {
+        let filters = FilterList {
+            includes: Some(vec![
+                PatternWrapper::new("sda").unwrap(),
+                PatternWrapper::new("dm-*").unwrap(),
+            ]),
+            excludes: Some(vec![PatternWrapper::new("dm-5").unwrap()]),
+        };
+        assert!(!filters.contains_test(Some("sd")));
+        assert!(filters.contains_test(Some("sda")));
+        assert!(!filters.contains_test(Some("sda1")));
+        assert!(filters.contains_test(Some("dm-")));
+        assert!(filters.contains_test(Some("dm-1")));
+        assert!(!filters.contains_test(Some("dm-5")));
+        assert!(!filters.contains_test(Some("xda")));
+        assert!(!filters.contains_test(None));
+    }
+
+    #[test]
+    fn filterlist_finds_unmatched_includes() {
+        let filters = FilterList {
+            includes: Some(vec![
+                PatternWrapper::new("/dev/sda").unwrap(),
+                PatternWrapper::new("sd*").unwrap(),
+            ]),
+            excludes: Some(vec![PatternWrapper::new("sdb").unwrap()]),
+        };
+        let devices = vec!["sda".to_string(), "sdb".to_string(), "lo".to_string()];
+        assert_eq!(filters.unmatched_includes(&devices), vec!["/dev/sda"]);
+        assert_eq!(
+            FilterList::default().unmatched_includes(&devices),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn warns_of_unmatched_includes() {
+        let (logged, _guard) = capture_logged_fields();
+
+        let filters = FilterList {
+            includes: Some(vec![
+                PatternWrapper::new("/dev/sda").unwrap(),
+                PatternWrapper::new("sd*").unwrap(),
+            ]),
+            excludes: None,
+        };
+        let devices = vec!["sda".to_string(), "sdb".to_string()];
+        warn_unmatched_includes("disk.devices", &filters, &devices);
+        warn_unmatched_includes("disk.devices", &FilterList::default(), &devices);
+        let logged = logged.lock().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0]["option"], "disk.devices");
+        assert_eq!(logged[0]["pattern"], "/dev/sda");
+    }
+
+    #[tokio::test]
+    async fn warns_of_unavailable_collectors() {
+        let unavailable = Collector::ALL
+            .iter()
+            .copied()
+            .filter(|collector| !collector.is_available())
+            .collect::<Vec<_>>();
+        for &collector in &unavailable {
+            assert!(HostMetricsConfig::default()
+                .collect(collector)
+                .await
+                .is_empty());
+        }
+
+        let (logged, _guard) = capture_logged_fields();
+        let config = HostMetricsConfig {
+            collectors: Some(Collector::ALL.to_vec()),
+            ..Default::default()
+        };
+        let (tx, _rx) = Pipeline::new_test();
+        assert!(config.build(SourceContext::new_test(tx)).await.is_ok());
+        let warned = logged
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|fields| fields.get("collector").cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warned,
+            unavailable
+                .iter()
+                .map(|collector| collector.as_str().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn filters_on_collectors() {
+        let all_metrics_count = HostMetricsConfig::default().capture_metrics().await.count();
+
+        for collector in Collector::DEFAULT {
+            let some_metrics = HostMetricsConfig {
+                collectors: Some(vec![*collector]),
+                ..Default::default()
+            }
+            .capture_metrics()
+            .await;
+
+            assert!(
+                all_metrics_count > some_metrics.count(),
+                "collector={:?}",
+                collector
+            );
+        }
+    }
+
+    #[test]
+    fn enables_collectors_with_booleans() {
+        let config: HostMetricsConfig = toml::from_str(
+            r#"
+            enable.cpu = false
+            enable.vm = true
+            "#,
+        )
+        .unwrap();
+        assert!(!config.has_collector(Collector::Cpu));
+        assert!(config.has_collector(Collector::Vm));
+        // Unset collectors keep their default
+        assert!(config.has_collector(Collector::Disk));
+        assert!(!config.has_collector(Collector::Cgroup));
+    }
+
+    #[test]
+    fn enables_collectors_with_list() {
+        let config: HostMetricsConfig = toml::from_str(
+            r#"
+            collectors = ["cpu", "vm"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.has_collector(Collector::Cpu));
+        assert!(config.has_collector(Collector::Vm));
+        assert!(!config.has_collector(Collector::Disk));
+    }
+
+    #[test]
+    fn enable_booleans_override_list() {
+        let config: HostMetricsConfig = toml::from_str(
+            r#"
+            collectors = ["cpu", "disk"]
+            enable.disk = false
+            enable.memory = true
+            "#,
+        )
+        .unwrap();
+        assert!(config.has_collector(Collector::Cpu));
+        assert!(!config.has_collector(Collector::Disk));
+        assert!(config.has_collector(Collector::Memory));
+        assert!(!config.has_collector(Collector::Network));
+    }
+
+    #[tokio::test]
+    async fn are_taged_with_hostname() {
+        let mut metrics = HostMetricsConfig::default().capture_metrics().await;
+        let hostname = crate::get_hostname().expect("Broken hostname");
+        assert!(!metrics.any(|event| event
+            .into_metric()
+            .tags()
+            .expect("Missing tags")
+            .get("host")
+            .expect("Missing \"host\" tag")
+            != &hostname));
+    }
+
+    #[tokio::test]
+    async fn uses_custom_namespace() {
+        let mut metrics = HostMetricsConfig {
+            namespace: Namespace(Some("other".into())),
+            ..Default::default()
+        }
+        .capture_metrics()
+        .await;
+
+        assert!(metrics.all(|event| event.into_metric().namespace() == Some("other")));
+    }
+
+    #[tokio::test]
+    async fn uses_default_namespace() {
+        let mut metrics = HostMetricsConfig::default().capture_metrics().await;
+
+        assert!(metrics.all(|event| event.into_metric().namespace() == Some("host")));
+    }
+
+    #[tokio::test]
+    async fn applies_name_template() {
+        async fn names(namespace: Option<&str>, template: &str) -> Vec<String> {
+            HostMetricsConfig {
+                collectors: Some(vec![Collector::Memory]),
+                namespace: Namespace(namespace.map(Into::into)),
+                name_template: Some(NameTemplate(template.into())),
+                ..Default::default()
+            }
+            .capture_metrics()
+            .await
+            .map(|event| {
+                let metric = event.into_metric();
+                assert_eq!(metric.namespace(), None);
+                metric.name().to_string()
+            })
+            .collect()
+        }
+
+        let prefixed = names(Some("host"), "{namespace}_{name}").await;
+        assert!(prefixed
+            .iter()
+            .any(|name| name == "host_memory_total_bytes"));
+        assert!(prefixed.iter().all(|name| name.starts_with("host_")));
+
+        let dotted = names(Some("node"), "{namespace}.{name}").await;
+        assert!(dotted.iter().any(|name| name == "node.memory_total_bytes"));
+
+        let bare = names(None, "{namespace}_{name}").await;
+        assert!(bare.iter().any(|name| name == "memory_total_bytes"));
+    }
+
+    #[test]
+    fn validates_name_templates() {
+        for template in &["{name}", "{namespace}_{name}", "system.{name}"] {
+            assert!(NameTemplate(template.to_string()).validate().is_ok());
+        }
+        for template in &["{namespace}", "{name", "name}", "{host}_{name}", ""] {
+            assert!(
+                NameTemplate(template.to_string()).validate().is_err(),
+                "template={}",
+                template
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn sorts_metrics() {
+        let metrics = HostMetricsConfig {
+            sort_metrics: true,
+            ..Default::default()
+        }
+        .capture_metrics()
+        .await
+        .map(Event::into_metric)
+        .collect::<Vec<_>>();
+        assert!(!metrics.is_empty());
+
+        let keys = metrics
+            .iter()
+            .map(|metric| (metric.name(), metric.tags()))
+            .collect::<Vec<_>>();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[tokio::test]
+    async fn aligns_timestamps_to_interval() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 10,
+            timestamp_alignment: TimestampAlignment::Interval,
+            ..Default::default()
+        };
+        let mut metrics = config.cpu_metrics().await;
+        metrics.extend(config.memory_metrics().await);
+        assert!(!metrics.is_empty());
+
+        for metric in metrics {
+            let timestamp = metric.timestamp().expect("Missing timestamp");
+            assert_eq!(timestamp.timestamp() % 10, 0);
+            assert_eq!(timestamp.timestamp_subsec_nanos(), 0);
+        }
+    }
+
+    #[test]
+    fn collection_alignment_keeps_timestamps() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 10,
+            ..Default::default()
+        };
+        let timestamp = Utc.timestamp(1_600_000_005, 123);
+        assert_eq!(config.align_timestamp(timestamp), timestamp);
+    }
+
+    #[test]
+    fn emits_collect_errors() {
+        let error = std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("{}\nsecond line", "x".repeat(100)),
+        );
+        assert!(HostMetricsConfig::default()
+            .collect_error(&error)
+            .is_empty());
+
+        let config = HostMetricsConfig {
+            emit_errors: true,
+            ..Default::default()
+        };
+        let metrics = add_collector("disk", config.collect_error(&error));
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name(), "collect_error");
+        assert_eq!(metrics[0].value(), &MetricValue::Gauge { value: 1.0 });
+        assert_eq!(metrics[0].tag_value("collector"), Some("disk".into()));
+        assert_eq!(metrics[0].tag_value("error"), Some("x".repeat(64)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn rate_limits_error_logs_as_configured() {
+        let (logged, _guard) = capture_logged_fields();
+
+        // Nothing can be read under an empty host root.
+        let host_root = tempfile::tempdir().unwrap();
+        let mut config = HostMetricsConfig {
+            host_root: Some(host_root.path().into()),
+            ..Default::default()
+        };
+        config.softirq_metrics().await;
+        config.error_log_interval_secs = Some(300);
+        config.softirq_metrics().await;
+        let rates = logged
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|fields| fields.get("internal_log_rate_secs")?.parse().ok())
+            .collect::<Vec<u64>>();
+        assert_eq!(rates, vec![60, 300]);
+    }
+
+    #[test]
+    fn clamps_derived_values() {
+        let config = HostMetricsConfig::default();
+        let (total, free, used) = (100.0, -5.0, 105.0);
+        assert_eq!(
+            config.clamp_value("filesystem_free_bytes", free, 0.0, total),
+            0.0
+        );
+        assert_eq!(
+            config.clamp_value("filesystem_used_bytes", used, 0.0, total),
+            100.0
+        );
+        assert_eq!(
+            config.clamp_value("filesystem_used_ratio", used / total, 0.0, 1.0),
+            1.0
+        );
+        assert_eq!(
+            config.clamp_value("filesystem_used_ratio", 0.5, 0.0, 1.0),
+            0.5
+        );
+
+        let config = HostMetricsConfig {
+            clamp_values: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.clamp_value("filesystem_used_bytes", used, 0.0, total),
+            used
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn reads_files_under_host_root() {
+        let host_root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(host_root.path().join("proc")).unwrap();
+        std::fs::write(
+            host_root.path().join("proc/buddyinfo"),
+            "Node 0, zone   Normal      1      2\n",
+        )
+        .unwrap();
+
+        let config = HostMetricsConfig {
+            host_root: Some(host_root.path().into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.host_path("/var/run/reboot-required"),
+            host_root.path().join("var/run/reboot-required")
+        );
+        let metrics = config.buddyinfo_metrics().await;
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[1].tag_value("order"), Some("1".into()));
+        assert_eq!(metrics[1].value(), &MetricValue::Gauge { value: 2.0 });
+
+        assert_eq!(
+            HostMetricsConfig::default().host_path("/proc"),
+            Path::new("/proc")
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn skips_offline_cpus() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let cpus = sysfs.path().join("devices/system/cpu");
+        for cpu in &["cpu0", "cpu1", "cpu2", "cpufreq"] {
+            std::fs::create_dir_all(cpus.join(cpu)).unwrap();
+        }
+        std::fs::write(cpus.join("cpu1/online"), "0\n").unwrap();
+        std::fs::write(cpus.join("cpu2/online"), "1\n").unwrap();
+        std::fs::write(cpus.join("online"), "0,2\n").unwrap();
+
+        let offline = offline_cpus(sysfs.path());
+        assert_eq!(offline, vec![1].into_iter().collect::<HashSet<_>>());
+
+        let metrics = HostMetricsConfig::default()
+            .cpu_times_metrics(&offline)
+            .await;
+        assert!(!metrics.is_empty());
+        assert!(all_tags_match(&metrics, "cpu", |cpu| cpu != "1"));
+        assert_eq!(count_tag(&metrics, "online"), 0);
+
+        let config = HostMetricsConfig {
+            cpu: CpuConfig {
+                include_offline: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let metrics = config.cpu_times_metrics(&offline).await;
+        assert_eq!(count_tag(&metrics, "online"), metrics.len());
+        for metric in &metrics {
+            let online = metric.tag_value("cpu") != Some("1".into());
+            assert_eq!(metric.tag_value("online"), Some(online.to_string()));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn computes_cpu_saturation_ratio() {
+        let ratios = vec![
+            cpu_saturation_ratio(2.0, 4, None),
+            cpu_saturation_ratio(2.0, 4, Some(0.8)),
+            cpu_saturation_ratio(2.0, 4, Some(0.1)),
+            cpu_saturation_ratio(12.0, 4, None),
+            cpu_saturation_ratio(12.0, 4, Some(0.3)),
+            cpu_saturation_ratio(0.0, 4, Some(0.0)),
+            cpu_saturation_ratio(1.0, 0, None),
+        ];
+        assert_eq!(ratios, vec![0.5, 0.8, 0.5, 1.0, 1.0, 0.0, 0.0]);
+        assert!(ratios.iter().all(|ratio| (0.0..=1.0).contains(ratio)));
+    }
+
+    #[test]
+    fn collapses_cpu_times_into_percentiles() {
+        let collapse = |time_unit| {
+            let config = HostMetricsConfig {
+                time_unit,
+                ..Default::default()
+            };
+            let timestamp = Utc::now();
+            let mut per_cpu = Vec::new();
+            for cpu in 0..20 {
+                for &(mode, value) in &[("user", cpu as f64 + 1.0), ("idle", 100.0)] {
+                    per_cpu.push(config.counter(
+                        "cpu_seconds_total",
+                        timestamp,
+                        value,
+                        btreemap! { "mode" => mode, "cpu" => cpu.to_string() },
+                    ));
+                }
+            }
+            config
+                .cpu_percentile_metrics(per_cpu)
+                .iter()
+                .map(|metric| {
+                    assert_eq!(metric.tag_value("cpu"), None);
+                    let value = match metric.value() {
+                        MetricValue::Gauge { value } => *value,
+                        value => panic!("unexpected value {:?}", value),
+                    };
+                    (
+                        metric.name().to_string(),
+                        metric.tag_value("mode").unwrap(),
+                        value,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let expected = |unit: &str, scale: f64| {
+            vec![
+                (
+                    format!("cpu_{}_p50", unit),
+                    "idle".to_string(),
+                    100.0 * scale,
+                ),
+                (
+                    format!("cpu_{}_p95", unit),
+                    "idle".to_string(),
+                    100.0 * scale,
+                ),
+                (
+                    format!("cpu_{}_max", unit),
+                    "idle".to_string(),
+                    100.0 * scale,
+                ),
+                (
+                    format!("cpu_{}_p50", unit),
+                    "user".to_string(),
+                    10.0 * scale,
+                ),
+                (
+                    format!("cpu_{}_p95", unit),
+                    "user".to_string(),
+                    19.0 * scale,
+                ),
+                (
+                    format!("cpu_{}_max", unit),
+                    "user".to_string(),
+                    20.0 * scale,
+                ),
+            ]
+        };
+        assert_eq!(collapse(TimeUnit::Seconds), expected("seconds", 1.0));
+        // The times are converted to milliseconds once only.
+        assert_eq!(
+            collapse(TimeUnit::Milliseconds),
+            expected("milliseconds", 1000.0)
+        );
+        assert_eq!(percentile(&[3.0], 0.5), 3.0);
+    }
+
+    #[tokio::test]
+    async fn tags_metrics_consistently_without_hostname() {
+        let capture = |tag_unknown_host| async move {
+            HostMetricsConfig {
+                collectors: Some(vec![Collector::Cpu, Collector::Memory]),
+                self_metrics: true,
+                tag_unknown_host,
+                hostname: Hostname::failing(),
+                ..Default::default()
+            }
+            .capture_metrics()
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>()
+        };
+
+        let metrics = capture(false).await;
+        assert!(!metrics.is_empty());
+        assert_eq!(count_tag(&metrics, "host"), 0);
+
+        let metrics = capture(true).await;
+        assert_eq!(count_tag(&metrics, "host"), metrics.len());
+        assert!(all_tags_match(&metrics, "host", |host| host == "unknown"));
+    }
+
+    #[tokio::test]
+    async fn filters_metrics_by_tags() {
+        let config = HostMetricsConfig {
+            collectors: Some(vec![]),
+            tag_filters: vec![(
+                "device".to_string(),
+                FilterList {
+                    includes: None,
+                    excludes: Some(vec![
+                        PatternWrapper::new("loop*").unwrap(),
+                        PatternWrapper::new("lo").unwrap(),
+                    ]),
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let counter =
+            |name, device| config.counter(name, Utc::now(), 1.0, btreemap! { "device" => device });
+        let metrics = config
+            .capture_metrics_with(vec![
+                counter("disk_read_bytes_total", "sda"),
+                counter("disk_read_bytes_total", "loop0"),
+                counter("network_receive_bytes_total", "eth0"),
+                counter("network_receive_bytes_total", "lo"),
+            ])
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+
+        let devices = metrics
+            .iter()
+            .filter_map(|metric| metric.tag_value("device"))
+            .collect::<Vec<_>>();
+        assert_eq!(devices, vec!["sda", "eth0"]);
+        // Metrics without the tag are kept.
+        assert_eq!(count_name(&metrics, "scrape_interval_seconds"), 1);
+    }
+
+    #[tokio::test]
+    async fn drops_duplicate_series() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 10,
+            collectors: Some(vec![]),
+            ..Default::default()
+        };
+        let duplicate = config.gauge("scrape_interval_seconds", Utc::now(), 1.0, btreemap! {});
+        let metrics = config
+            .capture_metrics_with(vec![duplicate])
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        // The last of the duplicates is kept.
+        let scrape_intervals = metrics
+            .iter()
+            .filter(|metric| metric.name() == "scrape_interval_seconds")
+            .collect::<Vec<_>>();
+        assert_eq!(scrape_intervals.len(), 1);
+        assert_eq!(
+            scrape_intervals[0].value(),
+            &MetricValue::Gauge { value: 10.0 }
+        );
+
+        let gauge = |value, tags| config.gauge("load1", Utc::now(), value, tags);
+        let (metrics, duplicates) = dedupe_series(vec![
+            gauge(1.0, btreemap! {}),
+            gauge(2.0, btreemap! { "cpu" => "0" }),
+            gauge(3.0, btreemap! {}),
+        ]);
+        assert_eq!(duplicates, 1);
+        assert_eq!(
+            metrics
+                .iter()
+                .map(|metric| metric.value())
+                .collect::<Vec<_>>(),
+            vec![
+                &MetricValue::Gauge { value: 2.0 },
+                &MetricValue::Gauge { value: 3.0 }
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn emits_build_info() {
+        let config = HostMetricsConfig {
+            collectors: Some(vec![Collector::Memory, Collector::Cpu]),
+            ..Default::default()
+        };
+        let metrics = config
+            .capture_metrics()
+            .await
+            .map(Event::into_metric)
+            .filter(|metric| metric.name() == "host_metrics_build_info")
+            .collect::<Vec<_>>();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].value(), &MetricValue::Gauge { value: 1.0 });
+        assert!(!metrics[0].tag_value("version").unwrap().is_empty());
+        assert_eq!(
+            metrics[0].tag_value("collectors"),
+            Some("cpu,memory".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn emits_scrape_interval() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 30,
+            collectors: Some(vec![]),
+            ..Default::default()
+        };
+        let metrics = config
+            .capture_metrics()
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].name(), "scrape_interval_seconds");
+        assert_eq!(metrics[0].namespace(), Some("host"));
+        assert_eq!(metrics[0].value(), &MetricValue::Gauge { value: 30.0 });
+    }
+
+    #[tokio::test]
+    async fn runs_with_sink() {
+        time::pause();
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 1,
+            collectors: Some(vec![]),
+            ..Default::default()
+        };
+        let mut metrics = Vec::<Metric>::new();
+        let shutdown = time::sleep(time::Duration::from_millis(1500));
+        config.run_with_sink(&mut metrics, shutdown).await.unwrap();
+
+        assert_eq!(metrics.len(), 4);
+        assert_eq!(count_name(&metrics, "scrape_interval_seconds"), 2);
+        assert_eq!(count_name(&metrics, "host_metrics_build_info"), 2);
+    }
+
+    #[tokio::test]
+    async fn emits_collected_counts() {
+        let config = HostMetricsConfig {
+            collectors: Some(vec![Collector::Cpu, Collector::Memory]),
+            emit_collected_counts: true,
+            ..Default::default()
+        };
+        let metrics = config
+            .capture_metrics()
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+
+        for collector in &["cpu", "memory"] {
+            let counts = metrics
+                .iter()
+                .filter(|metric| metric.name() == "collected_metrics_count")
+                .filter(|metric| metric.tag_matches("collector", collector))
+                .collect::<Vec<_>>();
+            assert_eq!(counts.len(), 1, "collector={}", collector);
+
+            let collected = metrics
+                .iter()
+                .filter(|metric| metric.name() != "collected_metrics_count")
+                .filter(|metric| metric.tag_matches("collector", collector))
+                .count();
+            assert!(collected > 0);
+            assert_eq!(
+                counts[0].value(),
+                &MetricValue::Gauge {
+                    value: collected as f64
+                },
+                "collector={}",
+                collector
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn uses_injected_clock() {
+        let timestamp = Utc.timestamp(1_600_000_000, 0);
+        let config = HostMetricsConfig {
+            clock: Clock::fixed(timestamp),
+            ..Default::default()
+        };
+        let metrics = config.capture_metrics().await.collect::<Vec<_>>();
+        assert!(!metrics.is_empty());
+
+        for event in metrics {
+            assert_eq!(event.into_metric().timestamp(), Some(timestamp));
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_cpu_metrics() {
+        let metrics = HostMetricsConfig::default().cpu_metrics().await;
+        assert!(!metrics.is_empty());
+        assert!(all_counters(&metrics));
+
+        // They should all be named cpu_seconds_total
+        assert_eq!(metrics.len(), count_name(&metrics, "cpu_seconds_total"));
+
+        // They should all have a "mode" tag
+        assert_eq!(count_tag(&metrics, "mode"), metrics.len());
+    }
+
+    #[tokio::test]
+    async fn emits_counters_as_gauges() {
+        let metrics = HostMetricsConfig {
+            counters_as_gauges: true,
+            ..Default::default()
+        }
+        .cpu_metrics()
+        .await;
+        assert!(!metrics.is_empty());
+        assert!(all_gauges(&metrics));
+        assert!(metrics
+            .iter()
+            .all(|metric| metric.kind() == MetricKind::Absolute));
+        assert_eq!(metrics.len(), count_name(&metrics, "cpu_seconds_total"));
+    }
+
+    #[test]
+    fn overrides_metric_types() {
+        let config = HostMetricsConfig {
+            metric_types: vec![
+                ("uptime".to_string(), ValueType::Counter),
+                ("disk_read_bytes_total".to_string(), ValueType::Gauge),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let metric = config.gauge("uptime", Utc::now(), 60.0, btreemap! {});
+        assert_eq!(metric.kind(), MetricKind::Absolute);
+        assert_eq!(metric.value(), &MetricValue::Counter { value: 60.0 });
+        let metric = config.counter("disk_read_bytes_total", Utc::now(), 1.0, btreemap! {});
+        assert_eq!(metric.value(), &MetricValue::Gauge { value: 1.0 });
+        let metric = config.gauge("boot_time", Utc::now(), 1.0, btreemap! {});
+        assert_eq!(metric.value(), &MetricValue::Gauge { value: 1.0 });
+
+        assert!(config.is_known_metric("uptime"));
+        assert!(!config.is_known_metric("uptime_total"));
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_scrape_interval() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 0,
+            ..Default::default()
+        };
+        let (tx, _rx) = Pipeline::new_test();
+        let error = config
+            .build(SourceContext::new_test(tx))
+            .await
+            .err()
+            .expect("Zero interval was accepted");
+        assert_eq!(
+            error.to_string(),
+            "scrape_interval_secs must be at least 1, got 0"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_length_sample_periods() {
+        let config = HostMetricsConfig {
+            scrape_interval_secs: 1,
+            summary: SummaryConfig {
+                collectors: vec![Collector::Load],
+                samples: 2_000_000_000,
+            },
+            ..Default::default()
+        };
+        let (tx, _rx) = Pipeline::new_test();
+        let error = config
+            .build(SourceContext::new_test(tx))
+            .await
+            .err()
+            .expect("Zero-length sample periods were accepted");
+        assert_eq!(
+            error.to_string(),
+            "summary.samples of 2000000000 splits scrape_interval_secs of 1 into zero-length periods"
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_concurrent_loads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let limit = Semaphore::new(2);
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let loaded = load_concurrently(stream::iter(0..10), &limit, |item| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                item
+            }
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+        assert_eq!(loaded, (0..10).collect::<Vec<_>>());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn normalizes_filesystem_types() {
+        let config = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                normalize_types: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut tags = BTreeMap::new();
+        config.insert_filesystem_type(&mut tags, "fuseblk.ntfs-3g");
+        assert_eq!(
+            tags,
+            btreemap! { "filesystem" => "ntfs", "raw_fstype" => "fuseblk.ntfs-3g" }
+        );
+
+        assert_eq!(normalize_filesystem_type("fuse.fuse-overlayfs"), "overlay");
+        assert_eq!(normalize_filesystem_type("fuse.sshfs"), "sshfs");
+        assert_eq!(normalize_filesystem_type("ext4"), "ext4");
+
+        // Disabled by default
+        let mut tags = BTreeMap::new();
+        HostMetricsConfig::default().insert_filesystem_type(&mut tags, "fuseblk.ntfs-3g");
+        assert_eq!(tags, btreemap! { "filesystem" => "fuseblk.ntfs-3g" });
+    }
+
+    #[test]
+    fn tags_mount_classes() {
+        let config = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                classes: vec![
+                    ("os".to_string(), vec!["/".into()]),
+                    ("data".to_string(), vec!["/data".into(), "/srv".into()]),
+                ]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let class = |mount_point: &str| {
+            let mut tags = BTreeMap::new();
+            config.insert_mount_class(&mut tags, Path::new(mount_point));
+            tags.remove("class")
+        };
+        assert_eq!(class("/").as_deref(), Some("os"));
+        assert_eq!(class("/data").as_deref(), Some("data"));
+        assert_eq!(class("/data/db").as_deref(), Some("data"));
+        assert_eq!(class("/database").as_deref(), Some("os"));
+        assert_eq!(class("/boot").as_deref(), Some("os"));
+
+        let config = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                classes: vec![("data".to_string(), vec!["/data".into()])]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut tags = BTreeMap::new();
+        config.insert_mount_class(&mut tags, Path::new("/boot"));
+        assert_eq!(tags, btreemap! { "class" => "other" });
+
+        // Not tagged without classes
+        let mut tags = BTreeMap::new();
+        HostMetricsConfig::default().insert_mount_class(&mut tags, Path::new("/"));
+        assert!(tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cuts_stuck_enumerations_short() {
+        let started = time::Instant::now();
+        let partitions = stream::iter(vec!["/", "/home"]).chain(stream::pending());
+        let deadline = started + time::Duration::from_millis(50);
+        let enumerated = until_deadline(partitions, deadline, "Timed out.", 60)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(enumerated, vec!["/", "/home"]);
+        assert!(started.elapsed() < time::Duration::from_secs(5));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn emits_vector_process_metrics() {
+        let config = HostMetricsConfig {
+            self_metrics: true,
+            ..Default::default()
+        };
+        let metrics = config
+            .capture_metrics()
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        let memory = metrics
+            .iter()
+            .find(|metric| metric.name() == "vector_process_resident_memory_bytes")
+            .expect("Missing vector_process_resident_memory_bytes");
+        assert!(matches!(memory.value(), MetricValue::Gauge { value } if *value > 0.0));
+        assert_eq!(memory.namespace(), Some("host"));
+        assert!(memory.tag_value("host").is_some());
+
+        assert!(!HostMetricsConfig::default()
+            .capture_metrics()
+            .await
+            .any(|event| event.as_metric().name().starts_with("vector_process_")));
+    }
+
+    #[test]
+    fn drops_unchanged_counters() {
+        let scrape = |idle: f64, active: f64| {
+            let counter = |device: &str, value| {
+                Event::from(
+                    Metric::new(
+                        "disk_read_bytes_total",
+                        MetricKind::Absolute,
+                        MetricValue::Counter { value },
+                    )
+                    .with_tags(Some(btreemap! { "device" => device })),
+                )
+            };
+            let gauge = Event::from(Metric::new(
+                "load1",
+                MetricKind::Absolute,
+                MetricValue::Gauge { value: 1.0 },
+            ));
+            vec![counter("sda", idle), counter("sdb", active), gauge].into_iter()
+        };
+        let devices = |events: Vec<Event>| {
+            events
+                .into_iter()
+                .map(|event| {
+                    let metric = event.into_metric();
+                    metric
+                        .tag_value("device")
+                        .unwrap_or_else(|| metric.name().into())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut previous = HashMap::new();
+        let events = drop_unchanged_counters(scrape(10.0, 10.0), &mut previous);
+        assert_eq!(devices(events), vec!["sda", "sdb", "load1"]);
+        let events = drop_unchanged_counters(scrape(10.0, 20.0), &mut previous);
+        assert_eq!(devices(events), vec!["sdb", "load1"]);
+        let events = drop_unchanged_counters(scrape(10.0, 20.0), &mut previous);
+        assert_eq!(devices(events), vec!["load1"]);
+        let events = drop_unchanged_counters(scrape(11.0, 30.0), &mut previous);
+        assert_eq!(devices(events), vec!["sda", "sdb", "load1"]);
+    }
+
+    #[tokio::test]
+    async fn retries_failed_scrapes_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let config = HostMetricsConfig {
+            retry_empty_scrape: true,
+            emit_errors: true,
+            ..Default::default()
+        };
+        let error = config.collect_error(&"Not found.");
+        let load = config.gauge("load1", Utc::now(), 0.5, btreemap! {});
+        let delay = time::Duration::from_millis(1);
+        let calls = AtomicUsize::new(0);
+        let (calls, error, load) = (&calls, &error, &load);
+
+        // Fails first, then succeeds.
+        let scrape = config
+            .retry_failed_scrape(delay, || async move {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => vec![(Collector::Cpu, error.clone()), (Collector::Load, vec![])],
+                    _ => vec![
+                        (Collector::Cpu, vec![]),
+                        (Collector::Load, vec![load.clone()]),
+                    ],
+                }
+            })
+            .await;
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+        assert_eq!(scrape[1].1, vec![load.clone()]);
+
+        // Keeps failing, which is retried only once.
+        let scrape = config
+            .retry_failed_scrape(delay, || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                vec![(Collector::Cpu, error.clone())]
+            })
+            .await;
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+        assert_eq!(scrape, vec![(Collector::Cpu, error.clone())]);
+
+        // Disabled, or nothing failed.
+        HostMetricsConfig::default()
+            .retry_failed_scrape(delay, || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                vec![(Collector::Cpu, vec![])]
+            })
+            .await;
+        config
+            .retry_failed_scrape(delay, || async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                vec![(Collector::Load, vec![load.clone()])]
+            })
+            .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn emits_counters_and_rates() {
+        let start = Utc.timestamp(1_600_000_000, 0);
+        let scrape = |timestamp| async move {
+            HostMetricsConfig {
+                collectors: Some(vec![Collector::Cpu]),
+                counter_mode: CounterMode::EmitBoth,
+                clock: Clock::fixed(timestamp),
+                ..Default::default()
+            }
+            .capture_metrics()
+            .await
+            .collect::<Vec<_>>()
+        };
+
+        let mut previous = HashMap::new();
+        let first = add_counter_rates(scrape(start).await, &mut previous);
+        assert!(first
+            .iter()
+            .all(|event| !event.as_metric().name().ends_with("_rate")));
+
+        let second = add_counter_rates(
+            scrape(start + chrono::Duration::seconds(15)).await,
+            &mut previous,
+        )
+        .into_iter()
+        .map(Event::into_metric)
+        .collect::<Vec<_>>();
+        assert!(second
+            .iter()
+            .any(|metric| metric.name() == "cpu_seconds_total"
+                && matches!(metric.value(), MetricValue::Counter { .. })));
+        let rates = second
+            .iter()
+            .filter(|metric| metric.name() == "cpu_seconds_rate")
+            .collect::<Vec<_>>();
+        assert_eq!(rates.len(), count_name(&second, "cpu_seconds_total"));
+        assert!(rates.iter().all(|metric| matches!(
+            metric.value(),
+            MetricValue::Gauge { value } if *value >= 0.0
+        )));
+    }
+
+    #[test]
+    fn adds_created_timestamps() {
+        let config = HostMetricsConfig::default();
+        let started = Utc.timestamp(1_600_000_000, 0);
+        let mut previous = HashMap::new();
+        let mut created = |seconds, value| {
+            let events = vec![Event::from(config.counter(
+                "cpu_seconds_total",
+                started + chrono::Duration::seconds(seconds),
+                value,
+                btreemap! { "cpu" => "0", "mode" => "user" },
+            ))];
+            let metrics = add_created_gauges(events, started, &mut previous)
+                .into_iter()
+                .map(Event::into_metric)
+                .collect::<Vec<_>>();
+            assert_eq!(metrics.len(), 2);
+            assert_eq!(metrics[1].name(), "cpu_seconds_created");
+            assert_eq!(metrics[1].tags(), metrics[0].tags());
+            match metrics[1].value() {
+                MetricValue::Gauge { value } => *value,
+                value => panic!("unexpected value {:?}", value),
+            }
+        };
+
+        assert_eq!(created(0, 10.0), 1_600_000_000.0);
+        assert_eq!(created(15, 20.0), 1_600_000_000.0);
+        // The counter was reset
+        assert_eq!(created(30, 5.0), 1_600_000_030.0);
+        assert_eq!(created(45, 8.0), 1_600_000_030.0);
+
+        let config = HostMetricsConfig {
+            time_unit: TimeUnit::Milliseconds,
+            ..Default::default()
+        };
+        let events = vec![Event::from(config.counter(
+            "cpu_seconds_total",
+            started,
+            10.0,
+            btreemap! { "cpu" => "0", "mode" => "user" },
+        ))];
+        let metrics = add_created_gauges(events, started, &mut HashMap::new())
+            .into_iter()
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        assert_eq!(metrics[1].name(), "cpu_milliseconds_created");
+        assert_eq!(
+            metrics[1].value(),
+            &MetricValue::Gauge {
+                value: 1_600_000_000.0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn captures_counter_rates() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        time::pause();
+        let config = HostMetricsConfig::default();
+        let start = Utc.timestamp(1_600_000_000, 0);
+        let calls = AtomicUsize::new(0);
+        let (config, calls) = (&config, &calls);
+        let rates = capture_counter_rates(time::Duration::from_millis(500), || async move {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            let timestamp = start + chrono::Duration::milliseconds(500 * call as i64);
+            vec![
+                Event::from(config.counter(
+                    "cpu_seconds_total",
+                    timestamp,
+                    10.0 + call as f64,
+                    btreemap! { "cpu" => "0", "mode" => "user" },
+                )),
+                Event::from(config.gauge("load1", timestamp, 1.5, btreemap! {})),
+            ]
+        })
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(rates.len(), 1);
+        assert_eq!(rates[0].name(), "cpu_seconds_rate");
+        assert_eq!(
+            rates[0].tags(),
+            Some(&btreemap! { "cpu" => "0", "mode" => "user" })
+        );
+        assert_eq!(rates[0].value(), &MetricValue::Gauge { value: 2.0 });
+    }
+
+    #[tokio::test]
+    async fn drops_non_finite_metrics() {
+        let config = HostMetricsConfig {
+            collectors: Some(vec![]),
+            ..Default::default()
+        };
+        let extra = vec![
+            config.gauge("filesystem_used_ratio", Utc::now(), f64::NAN, btreemap! {}),
+            config.counter("cpu_seconds_total", Utc::now(), f64::INFINITY, btreemap! {}),
+            config.gauge("load1", Utc::now(), 0.5, btreemap! {}),
+        ];
+        let (metrics, dropped) = drop_non_finite(extra.clone());
+        assert_eq!(metrics, vec![extra[2].clone()]);
+        assert_eq!(dropped, vec!["filesystem_used_ratio", "cpu_seconds_total"]);
+
+        let metrics = config
+            .capture_metrics_with(extra)
+            .await
+            .map(Event::into_metric)
+            .collect::<Vec<_>>();
+        assert_eq!(count_name(&metrics, "load1"), 1);
+        assert_eq!(count_name(&metrics, "filesystem_used_ratio"), 0);
+        assert_eq!(count_name(&metrics, "cpu_seconds_total"), 0);
+    }
+
+    #[test]
+    fn smooths_gauges() {
+        let config = HostMetricsConfig::default();
+        let names = vec!["load1".to_string()].into_iter().collect();
+        let mut averages = HashMap::new();
+        let mut smooth = |value| {
+            let events = vec![
+                Event::from(config.gauge("load1", Utc::now(), value, btreemap! {})),
+                Event::from(config.gauge("load5", Utc::now(), value, btreemap! {})),
+            ];
+            let metrics = add_smoothed_gauges(events, &names, 0.5, &mut averages)
+                .into_iter()
+                .map(Event::into_metric)
+                .collect::<Vec<_>>();
+            assert_eq!(metrics.len(), 3);
+            assert_eq!(metrics[2].name(), "load1_smoothed");
+            match metrics[2].value() {
+                MetricValue::Gauge { value } => *value,
+                value => panic!("unexpected value {:?}", value),
+            }
+        };
+
+        // Seeded with the first value
+        assert_eq!(smooth(8.0), 8.0);
+        assert_eq!(smooth(0.0), 4.0);
+        assert_eq!(smooth(0.0), 2.0);
+        assert_eq!(smooth(4.0), 3.0);
+        let mut average = 0.0;
+        for _ in 0..50 {
+            average = smooth(1.0);
+        }
+        assert!((average - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn makes_network_counters_incremental() {
+        let config = HostMetricsConfig::default();
+        let mut previous = HashMap::new();
+        let mut scrape = |received| {
+            let mut counters = vec![
+                config.counter(
+                    "network_receive_bytes_total",
+                    Utc::now(),
+                    received,
+                    btreemap! { "device" => "eth0" },
+                ),
+                config.counter("cpu_seconds_total", Utc::now(), 500.0, btreemap! {}),
+            ];
+            counters[0].insert_tag("collector".into(), "network".into());
+            counters[1].insert_tag("collector".into(), "cpu".into());
+            let events = counters.into_iter().map(Event::from).collect();
+            make_counters_incremental(events, Collector::Network, &mut previous)
+                .into_iter()
+                .map(Event::into_metric)
+                .collect::<Vec<_>>()
+        };
+
+        // A fresh start emits no increment for what was counted since boot.
+        let metrics = scrape(1e12);
+        assert_eq!(metrics[0].kind(), MetricKind::Incremental);
+        assert_eq!(metrics[0].value(), &MetricValue::Counter { value: 0.0 });
+        assert_eq!(metrics[1].kind(), MetricKind::Absolute);
+        assert_eq!(metrics[1].value(), &MetricValue::Counter { value: 500.0 });
+
+        let metrics = scrape(1e12 + 1500.0);
+        assert_eq!(metrics[0].value(), &MetricValue::Counter { value: 1500.0 });
+
+        // The counter was reset, as when the interface was recreated.
+        let metrics = scrape(200.0);
+        assert_eq!(metrics[0].value(), &MetricValue::Counter { value: 200.0 });
+    }
+
+    #[tokio::test]
+    async fn emits_inventory_metrics_periodically() {
+        let config = HostMetricsConfig {
+            collectors: Some(vec![Collector::Host]),
+            inventory: InventoryConfig {
+                every: NonZeroU64::new(3),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let names = config.inventory_names();
+        let mut emitted = Vec::new();
+        for scrape in 0..5 {
+            let metrics = drop_inventory_metrics(
+                config.capture_metrics().await.collect(),
+                &names,
+                scrape,
+                config.inventory.every.unwrap(),
+            );
+            let metrics = metrics
+                .into_iter()
+                .map(Event::into_metric)
+                .collect::<Vec<_>>();
+            // Changing metrics are emitted on every scrape.
+            assert_eq!(count_name(&metrics, "uptime"), 1);
+            emitted.push(count_name(&metrics, "host_info"));
+        }
+        assert_eq!(emitted, vec![1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn names_counter_rates() {
+        assert_eq!(rate_name("cpu_seconds_total"), "cpu_seconds_rate");
+        assert_eq!(
+            rate_name("memory_swapped_in_bytes_total"),
+            "memory_swapped_in_bytes_rate"
+        );
+        assert_eq!(rate_name("uptime"), "uptime_rate");
+    }
+
+    #[test]
+    fn converts_time_metrics_to_milliseconds() {
+        let config = HostMetricsConfig {
+            time_unit: TimeUnit::Milliseconds,
+            ..Default::default()
+        };
+        let metric = config.counter("cpu_seconds_total", Utc::now(), 1.5, btreemap! {});
+        assert_eq!(metric.name(), "cpu_milliseconds_total");
+        assert_eq!(metric.value(), &MetricValue::Counter { value: 1500.0 });
+
+        let metric = config.gauge("uptime", Utc::now(), 2.0, btreemap! {});
+        assert_eq!(metric.name(), "uptime");
+        assert_eq!(metric.value(), &MetricValue::Gauge { value: 2000.0 });
+
+        let metric = config.gauge("memory_free_bytes", Utc::now(), 2.0, btreemap! {});
+        assert_eq!(metric.name(), "memory_free_bytes");
+        assert_eq!(metric.value(), &MetricValue::Gauge { value: 2.0 });
+
+        let metric = HostMetricsConfig::default().counter(
+            "cpu_seconds_total",
+            Utc::now(),
+            1.5,
+            btreemap! {},
+        );
+        assert_eq!(metric.name(), "cpu_seconds_total");
+        assert_eq!(metric.value(), &MetricValue::Counter { value: 1.5 });
+    }
+
+    #[tokio::test]
+    async fn generates_disk_metrics() {
+        let metrics = HostMetricsConfig::default().disk_metrics().await;
+        // The Windows test runner doesn't generate any disk metrics on the VM.
+        #[cfg(not(target_os = "windows"))]
+        assert!(!metrics.is_empty());
+        // They should all have a "device" tag
+        assert_eq!(count_tag(&metrics, "device"), metrics.len());
+
+        // Only some devices expose an error count, and the flags aren't
+        // reported for partitions
+        let metrics = metrics
+            .into_iter()
+            .filter(|metric| {
+                !["disk_errors_total", "disk_readonly", "disk_removable"].contains(&metric.name())
+            })
+            .collect::<Vec<_>>();
+        assert!(all_counters(&metrics));
+        assert!(metrics.len() % 4 == 0);
+
+        // There are exactly four disk_* names for every device
+        for name in &[
+            "disk_read_bytes_total",
+            "disk_reads_completed_total",
+            "disk_written_bytes_total",
+            "disk_writes_completed_total",
+        ] {
+            assert_eq!(
+                count_name(&metrics, name),
+                metrics.len() / 4,
+                "name={}",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn filters_disk_metrics_on_device() {
+        assert_filtered_metrics("device", |devices| async {
+            HostMetricsConfig {
+                disk: DiskConfig {
+                    devices,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .disk_metrics()
+            .await
+        })
+        .await;
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn generates_filesystem_metrics() {
+        let metrics = HostMetricsConfig::default().filesystem_metrics().await;
+        assert!(!metrics.is_empty());
+        assert!(all_gauges(&metrics));
+
+        #[allow(unused_mut)]
+        let mut names = vec![
+            "filesystem_free_bytes",
+            "filesystem_total_bytes",
+            "filesystem_used_bytes",
+            "filesystem_used_ratio",
+            "filesystem_near_full",
+            "filesystem_stat_duration_seconds",
+        ];
+        #[cfg(target_os = "linux")]
+        names.push("filesystem_readonly");
+        assert!(metrics.len() % names.len() == 0);
+
+        // Every filesystem has each of the filesystem_* names
+        for name in &names {
+            assert_eq!(
+                count_name(&metrics, name),
+                metrics.len() / names.len(),
+                "name={}",
+                name
+            );
+        }
+
+        // They should all have "filesystem" and "mountpoint" tags
+        assert_eq!(count_tag(&metrics, "filesystem"), metrics.len());
+        assert_eq!(count_tag(&metrics, "mountpoint"), metrics.len());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[tokio::test]
+    async fn generates_filesystem_metrics() {
+        let metrics = HostMetricsConfig::default().filesystem_metrics().await;
+        assert!(!metrics.is_empty());
+        assert!(metrics.len() % 5 == 0);
+        assert!(all_gauges(&metrics));
+
+        // There are exactly five filesystem_* names
+        for name in &[
+            "filesystem_free_bytes",
+            "filesystem_total_bytes",
+            "filesystem_used_bytes",
+            "filesystem_near_full",
+            "filesystem_stat_duration_seconds",
+        ] {
+            assert_eq!(
+                count_name(&metrics, name),
+                metrics.len() / 5,
+                "name={}",
+                name
+            );
+        }
+
+        // They should all have "filesystem" and "mountpoint" tags
+        assert_eq!(count_tag(&metrics, "filesystem"), metrics.len());
+        assert_eq!(count_tag(&metrics, "mountpoint"), metrics.len());
+    }
+
+    #[tokio::test]
+    async fn times_slow_calls() {
+        time::pause();
+        let (output, duration) = timed(async {
+            time::sleep(time::Duration::from_millis(1500)).await;
+            "usage"
+        })
+        .await;
+        assert_eq!(output, "usage");
+        assert!(duration >= time::Duration::from_millis(1500));
+        assert!(duration < time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn truncates_long_tag_values() {
+        let tags = btreemap! {
+            "command" => "/usr/bin/python3 -m http.server",
+            "pid" => "42",
+        };
+        let metric = HostMetricsConfig::default().gauge("process", Utc::now(), 1.0, tags.clone());
+        assert_eq!(metric.tags(), Some(&tags));
+
+        let config = HostMetricsConfig {
+            max_tag_value_len: Some(8),
+            ..Default::default()
+        };
+        let metric = config.gauge("process", Utc::now(), 1.0, tags);
+        assert_eq!(metric.tag_value("command"), Some("/usr/bi…".into()));
+        assert_eq!(metric.tag_value("pid"), Some("42".into()));
+    }
+
+    #[test]
+    fn flags_near_full_filesystems() {
+        let config = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                near_full_threshold: 0.8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let near_full = |used, total| {
+            config
+                .filesystem_near_full(used, total, Utc::now(), btreemap! {})
+                .value()
+                .clone()
+        };
+        assert_eq!(near_full(90.0, 100.0), MetricValue::Gauge { value: 1.0 });
+        assert_eq!(near_full(50.0, 100.0), MetricValue::Gauge { value: 0.0 });
+        assert_eq!(near_full(0.0, 0.0), MetricValue::Gauge { value: 0.0 });
+    }
+
+    #[test]
+    fn skips_small_filesystems() {
+        let mounts = vec![
+            ("/", 100 << 30),
+            ("/boot/efi", 512 << 20),
+            ("/srv", 1 << 30),
+        ];
+        let large_mounts = |config: &HostMetricsConfig| {
+            mounts
+                .iter()
+                .filter(|(_, total)| config.filesystem_large_enough(*total))
+                .map(|(mount, _)| *mount)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            large_mounts(&HostMetricsConfig::default()),
+            vec!["/", "/boot/efi", "/srv"]
+        );
+        let config = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                min_size_bytes: 1 << 30,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(large_mounts(&config), vec!["/", "/srv"]);
+    }
+
+    #[test]
+    fn filesystem_excludes_pseudo_filesystems_by_default() {
+        let config = FilesystemConfig::default();
+        assert!(!config.filesystems.contains_str(Some("cgroup")));
+        assert!(!config.filesystems.contains_str(Some("proc")));
+        assert!(config.filesystems.contains_str(Some("ext4")));
+
+        assert!(!config.mountpoints.contains_path(Some(Path::new("/proc"))));
+        assert!(!config
+            .mountpoints
+            .contains_path(Some(Path::new("/var/lib/docker/overlay2/merged"))));
+        assert!(config.mountpoints.contains_path(Some(Path::new("/"))));
+        assert!(config.mountpoints.contains_path(Some(Path::new("/home"))));
+    }
+
+    #[test]
+    fn filesystem_defaults_are_replaced_by_config() {
+        let config: FilesystemConfig = toml::from_str(
+            r#"
+            filesystems.excludes = ["tmpfs"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.filesystems.contains_str(Some("proc")));
+        assert!(!config.filesystems.contains_str(Some("tmpfs")));
+        assert!(!config.mountpoints.contains_path(Some(Path::new("/proc"))));
+    }
+
+    #[tokio::test]
+    async fn filesystem_metrics_filters_on_device() {
+        assert_filtered_metrics("device", |devices| async {
+            HostMetricsConfig {
+                filesystem: FilesystemConfig {
+                    devices,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .filesystem_metrics()
+            .await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn filesystem_metrics_filters_on_filesystem() {
+        assert_filtered_metrics("filesystem", |filesystems| async {
+            HostMetricsConfig {
+                filesystem: FilesystemConfig {
+                    filesystems,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .filesystem_metrics()
+            .await
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn filesystem_metrics_filters_on_mountpoint() {
+        assert_filtered_metrics("mountpoint", |mountpoints| async {
+            HostMetricsConfig {
+                filesystem: FilesystemConfig {
+                    mountpoints,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .filesystem_metrics()
+            .await
+        })
+        .await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn filesystem_metrics_scoped_to_paths() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let paths = vec![first.path().to_path_buf(), second.path().to_path_buf()];
+        let metrics = HostMetricsConfig {
+            filesystem: FilesystemConfig {
+                paths: paths.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .filesystem_metrics()
+        .await;
+
+        assert_eq!(
+            collect_tag_values(&metrics, "path"),
+            paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect::<HashSet<_>>()
+        );
+        assert_eq!(count_tag(&metrics, "path"), metrics.len());
+        assert_eq!(count_tag(&metrics, "mountpoint"), metrics.len());
+        assert_eq!(count_name(&metrics, "filesystem_total_bytes"), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn filesystem_paths_resolve_under_host_root() {
+        let host_root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(host_root.path().join("data/logs")).unwrap();
+        let metrics = HostMetricsConfig {
+            host_root: Some(host_root.path().into()),
+            filesystem: FilesystemConfig {
+                paths: vec!["/data/logs".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .filesystem_metrics()
+        .await;
+
+        assert_eq!(count_name(&metrics, "filesystem_total_bytes"), 1);
+        assert_eq!(
+            collect_tag_values(&metrics, "path"),
+            vec!["/data/logs".to_string()].into_iter().collect()
+        );
+        // The whole host root is on the same filesystem.
+        assert_eq!(
+            collect_tag_values(&metrics, "mountpoint"),
+            vec!["/".to_string()].into_iter().collect()
+        );
+    }
+
+    // The Windows CI environment produces zero network metrics, causing
+    // this to always fail.
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn generates_network_metrics() {
+        // Hosts may have no other interface than the loopback.
+        let metrics = HostMetricsConfig {
+            network: NetworkConfig {
+                devices: None,
+                exclude_virtual: Some(false),
+                split_family: false,
+                incremental: false,
+                aliases: HashMap::new(),
+                alias_as_device: false,
+            },
+            ..Default::default()
+        }
+        .network_metrics()
+        .await;
+        assert!(!metrics.is_empty());
+        let (settings, counters): (Vec<_>, Vec<_>) = metrics.iter().cloned().partition(|metric| {
+            ["network_mtu_bytes", "network_tx_queue_length"].contains(&metric.name())
+        });
+        assert!(all_counters(&counters));
+        assert!(all_gauges(&settings));
+
+        // All metrics are named network_*
+        assert!(!metrics
+            .iter()
+            .any(|metric| !metric.name().starts_with("network_")));
+
+        // They should all have a "device" tag
+        assert_eq!(count_tag(&metrics, "device"), metrics.len());
+
+        #[cfg(target_os = "linux")]
+        for name in &[
+            "network_receive_compressed_total",
+            "network_receive_multicast_total",
+            "network_transmit_compressed_total",
+        ] {
+            assert!(count_name(&metrics, name) > 0, "name={}", name);
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn tags_network_metrics_with_family() {
+        let metrics = HostMetricsConfig {
+            network: NetworkConfig {
+                devices: None,
+                exclude_virtual: Some(false),
+                split_family: true,
+                incremental: false,
+                aliases: HashMap::new(),
+                alias_as_device: false,
+            },
+            ..Default::default()
+        }
+        .network_metrics()
+        .await;
+
+        let split = metrics
+            .iter()
+            .filter(|metric| {
+                metric.name().ends_with("_bytes_total") || metric.name().ends_with("_packets_total")
+            })
+            .collect::<Vec<_>>();
+        assert!(!split.is_empty());
+        for metric in &split {
+            let family = metric.tag_value("family");
+            assert!(
+                family == Some("all".into()) || family == Some("ipv6".into()),
+                "metric={:?}",
+                metric
+            );
+        }
+        assert!(metrics
+            .iter()
+            .filter(|metric| metric.name() == "network_receive_errs_total")
+            .all(|metric| metric.tag_value("family").is_none()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_dev_snmp6() {
+        let stats = parse_dev_snmp6(
+            "ifIndex                         \t2
+Ip6InReceives                   \t5012
+Ip6InOctets                     \t734211
+Ip6OutRequests                  \t4821
+Ip6OutOctets                    \t530144
+",
+        );
+        assert_eq!(stats["Ip6InOctets"], 734_211);
+        assert_eq!(stats["Ip6OutRequests"], 4821);
+        assert_eq!(stats["ifIndex"], 2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parses_net_dev() {
+        let stats = parse_net_dev(
+            "Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 2776770   11307    0    0    0     0          0         0  2776770   11307    0    0    0     0       0          0
+  eth0: 1215645    2751    0    0    0     0          3        42  1782404    4324    0    0    0   427       0          7
+",
+        );
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats["eth0"],
+            NetDevStats {
+                receive_compressed: 3,
+                receive_multicast: 42,
+                transmit_compressed: 7,
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_carrier_changes() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let eth0 = sysfs.path().join("class/net/eth0");
+        std::fs::create_dir_all(&eth0).unwrap();
+        std::fs::write(eth0.join("carrier_changes"), "14\n").unwrap();
+
+        assert_eq!(carrier_changes(sysfs.path(), "eth0"), Some(14));
+        assert_eq!(carrier_changes(sysfs.path(), "eth1"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_interface_settings() {
+        let sysfs = tempfile::tempdir().unwrap();
+        let eth0 = sysfs.path().join("class/net/eth0");
+        std::fs::create_dir_all(&eth0).unwrap();
+        std::fs::write(eth0.join("mtu"), "9000\n").unwrap();
+        std::fs::write(eth0.join("tx_queue_len"), "1000\n").unwrap();
+
+        assert_eq!(interface_setting(sysfs.path(), "eth0", "mtu"), Some(9000));
+        assert_eq!(
+            interface_setting(sysfs.path(), "eth0", "tx_queue_len"),
+            Some(1000)
+        );
+        assert_eq!(interface_setting(sysfs.path(), "eth1", "mtu"), None);
+    }
+
+    // The Windows CI environment produces zero network metrics, causing
+    // this to always fail.
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn network_metrics_filters_on_device() {
+        assert_filtered_metrics("device", |devices| async {
+            HostMetricsConfig {
+                network: NetworkConfig {
+                    devices: Some(devices),
+                    exclude_virtual: None,
+                    split_family: false,
+                    incremental: false,
+                    aliases: HashMap::new(),
+                    alias_as_device: false,
+                },
+                ..Default::default()
+            }
+            .network_metrics()
+            .await
+        })
+        .await;
+    }
+
+    #[test]
+    fn aliases_network_interfaces() {
+        let metrics = || {
+            vec![
+                Metric::new(
+                    "network_receive_bytes_total",
+                    MetricKind::Absolute,
+                    MetricValue::Counter { value: 1.0 },
+                )
+                .with_tags(Some(btreemap! { "device" => "enp3s0f1" })),
+                Metric::new(
+                    "network_receive_bytes_total",
+                    MetricKind::Absolute,
+                    MetricValue::Counter { value: 2.0 },
+                )
+                .with_tags(Some(btreemap! { "device" => "eth0" })),
+            ]
+        };
+        let mut config = HostMetricsConfig::default();
+        config.network.aliases = vec![("enp3s0f1".to_string(), "uplink".to_string())]
+            .into_iter()
+            .collect();
+
+        let mut aliased = metrics();
+        config.alias_interfaces(&mut aliased);
+        assert_eq!(
+            aliased[0].tags(),
+            Some(&btreemap! { "device" => "enp3s0f1", "alias" => "uplink" })
+        );
+        assert_eq!(aliased[1].tags(), Some(&btreemap! { "device" => "eth0" }));
+
+        config.network.alias_as_device = true;
+        let mut aliased = metrics();
+        config.alias_interfaces(&mut aliased);
+        assert_eq!(aliased[0].tags(), Some(&btreemap! { "device" => "uplink" }));
+        assert_eq!(aliased[1].tags(), Some(&btreemap! { "device" => "eth0" }));
+    }
+
+    #[test]
+    fn excludes_virtual_network_devices_by_default() {
+        let devices = NetworkConfig::default().devices();
+        assert!(!devices.contains_str(Some("lo")));
+        assert!(!devices.contains_str(Some("veth1a2b3c")));
+        assert!(devices.contains_str(Some("eth0")));
+
+        let config = NetworkConfig {
+            devices: None,
+            exclude_virtual: Some(false),
+            split_family: false,
+            incremental: false,
+            aliases: HashMap::new(),
+            alias_as_device: false,
+        };
+        assert!(config.devices().contains_str(Some("lo")));
+
+        let config = NetworkConfig {
+            devices: Some(FilterList {
+                includes: Some(vec![PatternWrapper::new("lo").unwrap()]),
+                excludes: None,
+            }),
+            exclude_virtual: None,
+            split_family: false,
+            incremental: false,
+            aliases: HashMap::new(),
+            alias_as_device: false,
+        };
+        assert!(config.devices().contains_str(Some("lo")));
+        assert!(!config.devices().contains_str(Some("eth0")));
+    }
+
+    // Windows does not produce load average metrics.
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn generates_loadavg_metrics() {
+        let metrics = HostMetricsConfig::default().loadavg_metrics().await;
+        assert_eq!(metrics.len(), 3);
+        assert!(all_gauges(&metrics));
+
+        // All metrics are named load*
+        assert!(!metrics
+            .iter()
+            .any(|metric| !metric.name().starts_with("load")));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn generates_loadavg_per_core_metrics() {
+        let metrics = HostMetricsConfig {
+            load_per_core: true,
+            ..Default::default()
+        }
+        .loadavg_metrics()
+        .await;
+        assert_eq!(metrics.len(), 6);
+
+        let cores = heim::cpu::logical_count().await.unwrap() as f64;
+        let value = |name: &str| match metrics
+            .iter()
+            .find(|metric| metric.name() == name)
+            .map(|metric| metric.value())
+        {
+            Some(MetricValue::Gauge { value }) => *value,
+            value => panic!("Unexpected {} value {:?}", name, value),
+        };
+        for name in &["load1", "load5", "load15"] {
+            assert_eq!(value(&format!("{}_per_core", name)), value(name) / cores);
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_host_metrics() {
+        let metrics = HostMetricsConfig::default().host_metrics().await;
+        assert!(all_gauges(&metrics));
+        for name in &["uptime", "boot_time", "host_info"] {
+            assert_eq!(count_name(&metrics, name), 1, "name={}", name);
+        }
+
+        // The kernel random number generator gauges vary by kernel version,
+        // except for the entropy and pool size.
+        let random = metrics
+            .iter()
+            .filter(|metric| metric.name().starts_with("random_"))
+            .count();
+        assert_eq!(metrics.len(), 3 + random);
+        #[cfg(target_os = "linux")]
+        for name in &["random_entropy_available_bits", "random_pool_size_bits"] {
+            assert_eq!(count_name(&metrics, name), 1, "name={}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn generates_host_info_metric() {
+        let metrics = HostMetricsConfig::default().host_metrics().await;
+        let info = metrics
+            .iter()
+            .find(|metric| metric.name() == "host_info")
+            .expect("Missing host_info metric");
+        assert_eq!(info.value(), &MetricValue::Gauge { value: 1.0 });
+
+        let tags = info.tags().expect("Missing tags");
+        for tag in &["kernel", "arch"] {
+            assert!(
+                !tags.get(*tag).expect("Missing tag").is_empty(),
+                "tag={}",
+                tag
+            );
+        }
+        assert!(tags.contains_key("os"));
+        assert!(tags.contains_key("os_version"));
+    }
+
+    #[tokio::test]
+    async fn tags_uptime_with_boot_time() {
+        let boot_time = |metrics: Vec<Metric>| {
+            let uptime = metrics
+                .into_iter()
+                .find(|metric| metric.name() == "uptime")
+                .expect("Missing uptime metric");
+            uptime
+                .tags()
+                .and_then(|tags| tags.get("boot_time").cloned())
+        };
+        let metrics = HostMetricsConfig::default().host_metrics().await;
+        assert_eq!(boot_time(metrics), None);
+
+        let config = HostMetricsConfig {
+            boot_time_tag: true,
+            ..Default::default()
+        };
+        let tag = boot_time(config.host_metrics().await).expect("Missing boot_time tag");
+        let parsed = DateTime::parse_from_rfc3339(&tag).expect("Invalid boot_time tag");
+        assert!(parsed < Utc::now());
+        assert_eq!(boot_time(config.host_metrics().await), Some(tag));
+    }
+
+    #[test]
+    fn summarizes_gauge_samples() {
+        let sample = |load1: f64, load5: f64| {
+            vec![
+                Metric::new(
+                    "load1",
+                    MetricKind::Absolute,
+                    MetricValue::Gauge { value: load1 },
+                ),
+                Metric::new(
+                    "load5",
+                    MetricKind::Absolute,
+                    MetricValue::Gauge { value: load5 },
+                ),
+                Metric::new(
+                    "cpu_seconds_total",
+                    MetricKind::Absolute,
+                    MetricValue::Counter { value: 1.0 },
+                ),
+            ]
+        };
+        let window = vec![sample(1.0, 4.0), sample(3.0, 4.0), sample(2.0, 1.0)];
+
+        let timestamp = Utc::now();
+        let summaries = summarize(&window, timestamp);
+        assert_eq!(summaries.len(), 6);
+        assert!(all_gauges(&summaries));
+        assert!(summaries
+            .iter()
+            .all(|metric| metric.timestamp() == Some(timestamp)));
+
+        let value = |name: &str| {
+            summaries
+                .iter()
+                .find(|metric| metric.name() == name)
+                .map(|metric| metric.value().clone())
+                .unwrap_or_else(|| panic!("Missing {}", name))
+        };
+        assert_eq!(value("load1_min"), MetricValue::Gauge { value: 1.0 });
+        assert_eq!(value("load1_max"), MetricValue::Gauge { value: 3.0 });
+        assert_eq!(value("load1_avg"), MetricValue::Gauge { value: 2.0 });
+        assert_eq!(value("load5_min"), MetricValue::Gauge { value: 1.0 });
+        assert_eq!(value("load5_max"), MetricValue::Gauge { value: 4.0 });
+        assert_eq!(value("load5_avg"), MetricValue::Gauge { value: 3.0 });
+    }
+
+    #[test]
+    fn summary_defaults_to_single_sample() {
+        assert_eq!(SummaryConfig::default().samples(), 1);
+        let config = SummaryConfig {
+            collectors: vec![Collector::Load],
+            samples: 0,
+        };
+        assert_eq!(config.samples(), 1);
+        let config = SummaryConfig {
+            collectors: vec![Collector::Load],
+            samples: 5,
+        };
+        assert_eq!(config.samples(), 5);
+    }
+
+    fn all_counters(metrics: &[Metric]) -> bool {
+        !metrics
+            .iter()
+            .any(|metric| !matches!(metric.value(), &MetricValue::Counter { .. }))
+    }
+
+    fn all_gauges(metrics: &[Metric]) -> bool {
+        !metrics
+            .iter()
+            .any(|metric| !matches!(metric.value(), &MetricValue::Gauge { .. }))
+    }
+
+    fn all_tags_match(metrics: &[Metric], tag: &str, matches: impl Fn(&str) -> bool) -> bool {
+        !metrics.iter().any(|metric| {
+            metric
+                .tags()
+                .unwrap()
+                .get(tag)
+                .map(|value| !matches(value))
+                .unwrap_or(false)
+        })
+    }
+
+    fn count_name(metrics: &[Metric], name: &str) -> usize {
+        metrics
+            .iter()
+            .filter(|metric| metric.name() == name)
+            .count()
+    }
+
+    fn count_tag(metrics: &[Metric], tag: &str) -> usize {
+        metrics
+            .iter()
+            .filter(|metric| {
+                metric
+                    .tags()
+                    .expect("Metric is missing tags")
+                    .contains_key(tag)
+            })
+            .count()
+    }
+
+    fn collect_tag_values(metrics: &[Metric], tag: &str) -> HashSet<String> {
+        metrics
+            .iter()
+            .filter_map(|metric| metric.tags().unwrap().get(tag).cloned())
+            .collect::<HashSet<_>>()
+    }
+
+    type LoggedFields = Arc<std::sync::Mutex<Vec<BTreeMap<String, String>>>>;
+
+    /// Records the fields of every event logged on this thread, formatted as
+    /// strings, until the returned guard is dropped.
+    fn capture_logged_fields() -> (LoggedFields, tracing::subscriber::DefaultGuard) {
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+
+        struct Capture(LoggedFields);
+
+        struct Fields(BTreeMap<String, String>);
+
+        impl Visit for Fields {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.insert(field.name().into(), value.into());
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0.insert(field.name().into(), format!("{:?}", value));
+            }
+        }
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Capture {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                let mut fields = Fields(BTreeMap::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+        }
+
+        let logged = LoggedFields::default();
+        let subscriber =
+            tracing_subscriber::registry::Registry::default().with(Capture(Arc::clone(&logged)));
+        (logged, tracing::subscriber::set_default(subscriber))
+    }
+
+    // Run a series of tests using filters to ensure they are obeyed
+    async fn assert_filtered_metrics<'a, Get, Fut>(tag: &str, get_metrics: Get)
+    where
+        Get: Fn(FilterList) -> Fut,
+        Fut: Future<Output = Vec<Metric>>,
+    {
+        let all_metrics = get_metrics(FilterList::default()).await;
+        let keys = collect_tag_values(&all_metrics, tag);
+        // Pick an arbitrary key value
+        if let Some(key) = keys.into_iter().next() {
+            let key_prefix = &key[..key.len() - 1];
+
+            let filtered_metrics_with = get_metrics(FilterList {
+                includes: Some(vec![PatternWrapper::new(&key).unwrap()]),
+                excludes: None,
+            })
+            .await;
+
+            assert!(filtered_metrics_with.len() <= all_metrics.len());
+            assert!(!filtered_metrics_with.is_empty());
+            assert!(all_tags_match(&filtered_metrics_with, tag, |s| s == key));
+
+            let filtered_metrics_with_match = get_metrics(FilterList {
+                includes: Some(vec![
+                    PatternWrapper::new(&format!("{}*", key_prefix)).unwrap()
+                ]),
+                excludes: None,
+            })
+            .await;
+
+            assert!(filtered_metrics_with_match.len() >= filtered_metrics_with.len());
+            assert!(all_tags_match(&filtered_metrics_with_match, tag, |s| {
+                s.starts_with(key_prefix)
+            }));
+
+            let filtered_metrics_without = get_metrics(FilterList {
+                includes: None,
+                excludes: Some(vec![PatternWrapper::new(&key).unwrap()]),
+            })
+            .await;
+
+            assert!(filtered_metrics_without.len() <= all_metrics.len());
+            assert!(all_tags_match(&filtered_metrics_without, tag, |s| s != key));
+
+            let filtered_metrics_without_match = get_metrics(FilterList {
+                includes: None,
+                excludes: Some(vec![
+                    PatternWrapper::new(&format!("{}*", key_prefix)).unwrap()
+                ]),
+            })
+            .await;
+
+            assert!(filtered_metrics_without_match.len() <= filtered_metrics_without.len());
+            assert!(all_tags_match(&filtered_metrics_without_match, tag, |s| {
+                !s.starts_with(key_prefix)
+            }));
+
+            assert!(
+                filtered_metrics_with.len() + filtered_metrics_without.len() <= all_metrics.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! A minimal netlink client for the dump requests issued by the
+//! netlink-based collectors.
+
+use nix::libc;
+use std::{convert::TryInto, io, mem};
+
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_NOOP: u16 = 1;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+
+/// Sends a dump request and returns the payload of every message in the
+/// response.
+pub(super) fn dump(
+    protocol: libc::c_int,
+    message_type: u16,
+    payload: &[u8],
+) -> io::Result<Vec<Vec<u8>>> {
+    let socket = Socket::open(protocol)?;
+    socket.send(&request(message_type, payload))?;
+
+    let mut messages = Vec::new();
+    let mut buffer = vec![0; 32 * 1024];
+    loop {
+        let len = socket.recv(&mut buffer)?;
+        if parse_messages(&buffer[..len], &mut messages)? {
+            return Ok(messages);
+        }
+    }
+}
+
+fn request(message_type: u16, payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(NLMSG_HDRLEN + payload.len());
+    message.extend_from_slice(&((NLMSG_HDRLEN + payload.len()) as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    message.extend_from_slice(&1u32.to_ne_bytes()); // sequence number
+    message.extend_from_slice(&0u32.to_ne_bytes()); // port id, 0 is the kernel
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Appends the payloads of the messages in `buffer` to `messages`, returning
+/// `true` once the end of the dump has been reached.
+pub(super) fn parse_messages(mut buffer: &[u8], messages: &mut Vec<Vec<u8>>) -> io::Result<bool> {
+    while buffer.len() >= NLMSG_HDRLEN {
+        let len = u32_at(buffer, 0) as usize;
+        if len < NLMSG_HDRLEN || len > buffer.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated netlink message",
+            ));
+        }
+        let payload = &buffer[NLMSG_HDRLEN..len];
+        match u16_at(buffer, 4) {
+            NLMSG_NOOP => {}
+            NLMSG_DONE => return Ok(true),
+            NLMSG_ERROR => {
+                let errno = if payload.len() >= 4 {
+                    i32::from_ne_bytes(payload[..4].try_into().unwrap())
+                } else {
+                    0
+                };
+                if errno != 0 {
+                    return Err(io::Error::from_raw_os_error(-errno));
+                }
+            }
+            _ => messages.push(payload.to_vec()),
+        }
+        buffer = &buffer[align(len).min(buffer.len())..];
+    }
+    Ok(false)
+}
+
+/// Splits a buffer of netlink attributes into their types and payloads.
+pub(super) fn attributes(mut buffer: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    while buffer.len() >= 4 {
+        let len = u16_at(buffer, 0) as usize;
+        if len < 4 || len > buffer.len() {
+            break;
+        }
+        // The upper bits flag nested and byte-order-swapped attributes.
+        attributes.push((u16_at(buffer, 2) & 0x3fff, &buffer[4..len]));
+        buffer = &buffer[align(len).min(buffer.len())..];
+    }
+    attributes
+}
+
+const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+pub(super) fn u16_at(buffer: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes(buffer[offset..offset + 2].try_into().unwrap())
+}
+
+pub(super) fn u32_at(buffer: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(buffer[offset..offset + 4].try_into().unwrap())
+}
+
+struct Socket(libc::c_int);
+
+impl Socket {
+    fn open(protocol: libc::c_int) -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                protocol,
+            )
+        };
+        if fd < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Self(fd))
+        }
+    }
+
+    fn send(&self, message: &[u8]) -> io::Result<()> {
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let sent = unsafe {
+            libc::sendto(
+                self.0,
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn recv(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        let received = unsafe {
+            libc::recv(
+                self.0,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(received as usize)
+        }
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_dump_request() {
+        let message = request(20, &[1, 2, 3, 4]);
+        assert_eq!(message.len(), 20);
+        assert_eq!(u32_at(&message, 0), 20);
+        assert_eq!(u16_at(&message, 4), 20);
+        assert_eq!(u16_at(&message, 6), NLM_F_REQUEST | NLM_F_DUMP);
+        assert_eq!(&message[16..], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn parses_messages_until_done() {
+        let mut buffer = request(20, &[1, 2, 3]);
+        buffer.push(0); // padding
+        buffer.extend(request(NLMSG_DONE, &[0, 0, 0, 0]));
+
+        let mut messages = Vec::new();
+        assert!(parse_messages(&buffer, &mut messages).unwrap());
+        assert_eq!(messages, vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn parses_error_messages() {
+        let buffer = request(NLMSG_ERROR, &(-libc::EPERM).to_ne_bytes());
+        let error = parse_messages(&buffer, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(libc::EPERM));
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn splits_attributes() {
+        let buffer = [
+            5, 0, 1, 0, 42, 0, 0, 0, // type 1, one byte and padding
+            8, 0, 2, 0x80, 1, 2, 3, 4, // nested type 2
+        ];
+        assert_eq!(
+            attributes(&buffer),
+            vec![(1, &[42][..]), (2, &[1, 2, 3, 4][..])]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use super::{clock_ticks_per_second, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::path::Path;
+
+impl HostMetricsConfig {
+    pub async fn process_metrics(&self) -> Vec<Metric> {
+        let procfs = self.procfs_root();
+        let entries = match std::fs::read_dir(&procfs) {
+            Ok(entries) => entries,
+            Err(error) => {
+                error!(message = "Failed to list processes.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let ticks = clock_ticks_per_second();
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        let mut cpu_seconds = Vec::new();
+        for entry in entries.filter_map(Result::ok) {
+            let pid = match entry.file_name().to_str().and_then(|pid| pid.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            // Processes may exit at any point while being read, so those
+            // that can't be read are skipped.
+            let process = match read_process(&procfs, pid, ticks) {
+                Some(process) => process,
+                None => continue,
+            };
+            if !self.process.names.contains_str(Some(process.name.as_str())) {
+                continue;
+            }
+            if self.process.cpu_distribution {
+                cpu_seconds.push(process.cpu_seconds);
+                continue;
+            }
+
+            let tags = btreemap! {
+                "pid" => pid.to_string(),
+                "name" => process.name,
+            };
+            metrics.push(self.counter(
+                "process_cpu_seconds_total",
+                timestamp,
+                process.cpu_seconds,
+                tags.clone(),
+            ));
+            metrics.push(self.gauge(
+                "process_threads",
+                timestamp,
+                process.threads as f64,
+                tags.clone(),
+            ));
+            if let Some(open_fds) = process.open_fds {
+                metrics.push(self.gauge(
+                    "process_open_fds",
+                    timestamp,
+                    open_fds as f64,
+                    tags.clone(),
+                ));
+            }
+            // Only explicitly included processes report their IO, bounding
+            // the number of series. Reading the IO of processes of other
+            // users is denied without elevated privileges.
+            if self.process.names.includes.is_some() {
+                if let Some(io) = read_io(&procfs, pid) {
+                    metrics.push(self.counter(
+                        "process_read_bytes_total",
+                        timestamp,
+                        io.read_bytes as f64,
+                        tags.clone(),
+                    ));
+                    metrics.push(self.counter(
+                        "process_write_bytes_total",
+                        timestamp,
+                        io.write_bytes as f64,
+                        tags,
+                    ));
+                }
+            }
+        }
+        if self.process.cpu_distribution {
+            metrics.push(self.distribution(
+                "process_cpu_seconds",
+                timestamp,
+                cpu_seconds,
+                btreemap! {},
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) struct Process {
+    name: String,
+    /// The CPU time spent in user and kernel mode.
+    pub(super) cpu_seconds: f64,
+    /// The resident memory size, which kernel threads don't report.
+    pub(super) resident_bytes: Option<u64>,
+    pub(super) threads: u64,
+    /// The number of open file descriptors, which is unknown for processes
+    /// of other users unless running with elevated privileges.
+    pub(super) open_fds: Option<u64>,
+}
+
+pub(super) fn read_process(procfs: &Path, pid: u32, ticks: f64) -> Option<Process> {
+    let path = procfs.join(pid.to_string());
+    let status = std::fs::read_to_string(path.join("status")).ok()?;
+    let (name, threads, resident_bytes) = parse_status(&status)?;
+    let stat = std::fs::read_to_string(path.join("stat")).ok()?;
+    let cpu_ticks = parse_cpu_ticks(&stat)?;
+    let open_fds = std::fs::read_dir(path.join("fd"))
+        .ok()
+        .map(|entries| entries.count() as u64);
+    Some(Process {
+        name,
+        cpu_seconds: cpu_ticks as f64 / ticks,
+        resident_bytes,
+        threads,
+        open_fds,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+struct ProcessIo {
+    /// The bytes the process caused to be read from storage.
+    read_bytes: u64,
+    /// The bytes the process caused to be written to storage.
+    write_bytes: u64,
+}
+
+fn read_io(procfs: &Path, pid: u32) -> Option<ProcessIo> {
+    let io = std::fs::read_to_string(procfs.join(pid.to_string()).join("io")).ok()?;
+    parse_io(&io)
+}
+
+/// Parses the storage IO of a process from its `io` file, leaving out the
+/// `rchar` and `wchar` counts, which include reads served from the page cache
+/// and writes to pipes and sockets.
+fn parse_io(io: &str) -> Option<ProcessIo> {
+    let mut read_bytes = None;
+    let mut write_bytes = None;
+    for line in io.lines() {
+        match line.split_once(':') {
+            Some(("read_bytes", value)) => read_bytes = value.trim().parse().ok(),
+            Some(("write_bytes", value)) => write_bytes = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    Some(ProcessIo {
+        read_bytes: read_bytes?,
+        write_bytes: write_bytes?,
+    })
+}
+
+/// Parses the name, thread count and resident memory size of a process from
+/// its `status` file.
+fn parse_status(status: &str) -> Option<(String, u64, Option<u64>)> {
+    let mut name = None;
+    let mut threads = None;
+    let mut resident_bytes = None;
+    for line in status.lines() {
+        match line.split_once(':') {
+            Some(("Name", value)) => name = Some(value.trim().to_string()),
+            Some(("Threads", value)) => threads = value.trim().parse().ok(),
+            Some(("VmRSS", value)) => {
+                resident_bytes = value
+                    .trim()
+                    .strip_suffix(" kB")
+                    .and_then(|kb| kb.trim().parse::<u64>().ok())
+                    .map(|kb| kb * 1024)
+            }
+            _ => {}
+        }
+    }
+    Some((name?, threads?, resident_bytes))
+}
+
+/// Parses the CPU time of a process, in clock ticks, from its `stat` file.
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // The name may contain spaces and parentheses, so the fields are
+    // counted from the end of it, starting with the third.
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace().skip(11);
+    let user = fields.next()?.parse::<u64>().ok()?;
+    let system = fields.next()?.parse::<u64>().ok()?;
+    Some(user + system)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{FilterList, PatternWrapper, ProcessConfig};
+    use super::*;
+    use crate::event::metric::MetricValue;
+    use glob::Pattern;
+    use std::fs;
+
+    #[test]
+    fn reads_process() {
+        let procfs = tempfile::tempdir().unwrap();
+        let process = procfs.path().join("4242");
+        fs::create_dir_all(process.join("fd")).unwrap();
+        fs::write(
+            process.join("status"),
+            "Name:\tpostgres
+Umask:\t0077
+State:\tS (sleeping)
+Tgid:\t4242
+Pid:\t4242
+VmRSS:\t   10240 kB
+Threads:\t7
+SigQ:\t0/63414
+",
+        )
+        .unwrap();
+        fs::write(
+            process.join("stat"),
+            "4242 (postgres) S 1 4242 4242 0 -1 4194560 3041 0 0 0 250 50 0 0 20 0 7 0 2291 \n",
+        )
+        .unwrap();
+        for fd in 0..3 {
+            fs::write(process.join("fd").join(fd.to_string()), "").unwrap();
+        }
+
+        assert_eq!(
+            read_process(procfs.path(), 4242, 100.0),
+            Some(Process {
+                name: "postgres".into(),
+                cpu_seconds: 3.0,
+                resident_bytes: Some(10_485_760),
+                threads: 7,
+                open_fds: Some(3),
+            })
+        );
+        // The process has exited
+        assert_eq!(read_process(procfs.path(), 4243, 100.0), None);
+    }
+
+    #[test]
+    fn parses_cpu_ticks() {
+        assert_eq!(
+            parse_cpu_ticks("812 (tmux: server) S 1 812 812 0 -1 4194624 2047 0 3 0 1174 713 0 0"),
+            Some(1887)
+        );
+        assert_eq!(parse_cpu_ticks("812 (tmux: server) S 1"), None);
+    }
+
+    #[test]
+    fn reads_io() {
+        let procfs = tempfile::tempdir().unwrap();
+        let process = procfs.path().join("4242");
+        fs::create_dir_all(&process).unwrap();
+        fs::write(
+            process.join("io"),
+            "rchar: 323934931
+wchar: 323929600
+syscr: 632687
+syscw: 632675
+read_bytes: 4096
+write_bytes: 323932160
+cancelled_write_bytes: 0
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_io(procfs.path(), 4242),
+            Some(ProcessIo {
+                read_bytes: 4096,
+                write_bytes: 323_932_160,
+            })
+        );
+        // The process has exited, or belongs to another user
+        assert_eq!(read_io(procfs.path(), 4243), None);
+        assert_eq!(parse_io("rchar: 1\nwchar: 2\n"), None);
+    }
+
+    #[test]
+    fn parses_incomplete_status() {
+        assert_eq!(parse_status("Name:\tinit\n"), None);
+        // Kernel threads have no memory of their own
+        assert_eq!(
+            parse_status("Name:\tkthreadd\nThreads:\t1\n"),
+            Some(("kthreadd".into(), 1, None))
+        );
+    }
+
+    #[tokio::test]
+    async fn emits_cpu_distribution() {
+        // Only this process is matched, so that the sample count is known.
+        let name = read_process(
+            &HostMetricsConfig::default().procfs_root(),
+            std::process::id(),
+            clock_ticks_per_second(),
+        )
+        .unwrap()
+        .name;
+        let config = HostMetricsConfig {
+            process: ProcessConfig {
+                names: FilterList {
+                    includes: Some(vec![PatternWrapper::new(&Pattern::escape(&name)).unwrap()]),
+                    excludes: None,
+                },
+                cpu_distribution: true,
+            },
+            ..Default::default()
+        };
+
+        let metrics = config.process_metrics().await;
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name(), "process_cpu_seconds");
+        assert_eq!(metrics[0].tags(), Some(&btreemap! {}));
+        match metrics[0].value() {
+            MetricValue::Distribution { samples, .. } => assert_eq!(samples.len(), 1),
+            value => panic!("unexpected value {:?}", value),
+        }
+    }
+}
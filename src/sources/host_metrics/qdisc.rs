@@ -0,0 +1,160 @@
+use super::{
+    netlink::{self, u32_at},
+    HostMetricsConfig,
+};
+use crate::event::metric::Metric;
+use nix::libc;
+use shared::btreemap;
+use std::ffi::CStr;
+
+const RTM_GETQDISC: u16 = 38;
+const TCMSG_LEN: usize = 20;
+const TCA_KIND: u16 = 1;
+const TCA_STATS2: u16 = 7;
+const TCA_STATS_QUEUE: u16 = 3;
+
+impl HostMetricsConfig {
+    pub async fn qdisc_metrics(&self) -> Vec<Metric> {
+        let messages = match netlink::dump(libc::NETLINK_ROUTE, RTM_GETQDISC, &[0; TCMSG_LEN]) {
+            Ok(messages) => messages,
+            Err(error) => {
+                error!(message = "Failed to load queueing disciplines.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let devices = self.network.devices();
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for qdisc in messages.iter().filter_map(|message| parse_qdisc(message)) {
+            let device = match interface_name(qdisc.interface) {
+                Some(device) => device,
+                None => continue,
+            };
+            if !devices.contains_str(Some(&device)) {
+                continue;
+            }
+            let tags = btreemap! {
+                "device" => device,
+                "qdisc" => qdisc.kind,
+                "handle" => format!("{:x}:", qdisc.handle >> 16),
+            };
+            metrics.push(self.gauge(
+                "network_qdisc_backlog_bytes",
+                timestamp,
+                qdisc.backlog as f64,
+                tags.clone(),
+            ));
+            metrics.push(self.counter(
+                "network_qdisc_drops_total",
+                timestamp,
+                qdisc.drops as f64,
+                tags,
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Qdisc {
+    interface: u32,
+    kind: String,
+    handle: u32,
+    backlog: u32,
+    drops: u32,
+}
+
+/// Parses a `tcmsg` and the queue statistics of its `TCA_STATS2`
+/// attribute, skipping qdiscs without statistics.
+fn parse_qdisc(message: &[u8]) -> Option<Qdisc> {
+    if message.len() < TCMSG_LEN {
+        return None;
+    }
+    let mut kind = None;
+    let mut queue = None;
+    for (attribute, payload) in netlink::attributes(&message[TCMSG_LEN..]) {
+        match attribute {
+            TCA_KIND => {
+                kind = CStr::from_bytes_with_nul(payload)
+                    .ok()
+                    .map(|kind| kind.to_string_lossy().into_owned())
+            }
+            TCA_STATS2 => {
+                queue = netlink::attributes(payload)
+                    .into_iter()
+                    .find(|(attribute, _)| *attribute == TCA_STATS_QUEUE)
+                    .map(|(_, queue)| queue)
+            }
+            _ => {}
+        }
+    }
+    // `struct gnet_stats_queue` holds the qlen, backlog and drops, in order.
+    let queue = queue.filter(|queue| queue.len() >= 12)?;
+
+    Some(Qdisc {
+        interface: u32_at(message, 4),
+        kind: kind?,
+        handle: u32_at(message, 8),
+        backlog: u32_at(queue, 4),
+        drops: u32_at(queue, 8),
+    })
+}
+
+fn interface_name(index: u32) -> Option<String> {
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+    let name = unsafe {
+        if libc::if_indextoname(index, name.as_mut_ptr()).is_null() {
+            return None;
+        }
+        CStr::from_ptr(name.as_ptr())
+    };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `RTM_GETQDISC` response holding the `fq_codel` root qdisc of
+    /// interface 2 with 1514 bytes of backlog and 42 drops, followed by the
+    /// end of the dump.
+    #[cfg(target_endian = "little")]
+    const SAMPLE: &[u8] = &[
+        0x50, 0x00, 0x00, 0x00, 0x24, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x80, 0xff, 0xff,
+        0xff, 0xff, 0x01, 0x00, 0x00, 0x00, 0x0d, 0x00, 0x01, 0x00, 0x66, 0x71, 0x5f, 0x63, 0x6f,
+        0x64, 0x65, 0x6c, 0x00, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x07, 0x00, 0x18, 0x00, 0x03, 0x00,
+        0x03, 0x00, 0x00, 0x00, 0xea, 0x05, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x03, 0x00, 0x02, 0x00, 0x01, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_qdisc_response() {
+        let mut messages = Vec::new();
+        assert!(netlink::parse_messages(SAMPLE, &mut messages).unwrap());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            parse_qdisc(&messages[0]),
+            Some(Qdisc {
+                interface: 2,
+                kind: "fq_codel".into(),
+                handle: 0x8001_0000,
+                backlog: 1514,
+                drops: 42,
+            })
+        );
+    }
+
+    #[test]
+    fn skips_qdiscs_without_stats() {
+        assert_eq!(parse_qdisc(&[0; TCMSG_LEN]), None);
+    }
+
+    #[test]
+    fn resolves_loopback_name() {
+        assert_eq!(interface_name(1), Some("lo".into()));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::path::Path;
+
+impl HostMetricsConfig {
+    pub fn random_metrics(&self) -> Vec<Metric> {
+        let random = read_random(&self.procfs_root().join("sys/kernel/random"));
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        if let Some(entropy) = random.entropy_avail {
+            metrics.push(self.gauge(
+                "random_entropy_available_bits",
+                timestamp,
+                entropy as f64,
+                btreemap! {},
+            ));
+        }
+        if let Some(poolsize) = random.poolsize {
+            metrics.push(self.gauge(
+                "random_pool_size_bits",
+                timestamp,
+                poolsize as f64,
+                btreemap! {},
+            ));
+        }
+        if let Some(reseed) = random.urandom_min_reseed_secs {
+            metrics.push(self.gauge(
+                "random_urandom_min_reseed_seconds",
+                timestamp,
+                reseed as f64,
+                btreemap! {},
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Random {
+    /// The entropy the input pool is credited with, fixed at the pool size
+    /// since Linux 5.18.
+    entropy_avail: Option<u64>,
+    poolsize: Option<u64>,
+    /// The minimum interval between reseeds of the urandom pool, removed
+    /// in Linux 5.18. The kernel doesn't count reseeds or reads.
+    urandom_min_reseed_secs: Option<u64>,
+}
+
+/// Reads the state of the kernel random number generator from
+/// `/proc/sys/kernel/random`, leaving out the values this kernel lacks.
+fn read_random(dir: &Path) -> Random {
+    Random {
+        entropy_avail: read_u64(&dir.join("entropy_avail")),
+        poolsize: read_u64(&dir.join("poolsize")),
+        urandom_min_reseed_secs: read_u64(&dir.join("urandom_min_reseed_secs")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reads_random() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_random(dir.path()), Random::default());
+
+        fs::write(dir.path().join("entropy_avail"), "3754\n").unwrap();
+        fs::write(dir.path().join("poolsize"), "4096\n").unwrap();
+        assert_eq!(
+            read_random(dir.path()),
+            Random {
+                entropy_avail: Some(3754),
+                poolsize: Some(4096),
+                urandom_min_reseed_secs: None,
+            }
+        );
+    }
+}
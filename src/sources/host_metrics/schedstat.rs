@@ -0,0 +1,281 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+
+/// The first version reporting times in nanoseconds, after which the layout
+/// of the `cpu` lines stayed the same.
+const MIN_VERSION: u32 = 15;
+
+/// The version splitting the load imbalance count of the `domain` lines into
+/// four, and adding the name of the domain.
+const SPLIT_IMBALANCE_VERSION: u32 = 17;
+
+impl HostMetricsConfig {
+    pub async fn schedstat_metrics(&self) -> Vec<Metric> {
+        let schedstat = match std::fs::read_to_string(self.procfs_root().join("schedstat")) {
+            Ok(schedstat) => schedstat,
+            Err(error) => {
+                error!(message = "Failed to load scheduler statistics.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+        let cpus = match parse_schedstat(&schedstat) {
+            Some(cpus) => cpus,
+            None => {
+                error!(
+                    message = "Unsupported scheduler statistics version.",
+                    internal_log_rate_secs = self.error_log_rate_secs()
+                );
+                return Vec::new();
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::with_capacity(cpus.len() * 2);
+        for cpu in cpus {
+            let tags = btreemap! { "cpu" => cpu.cpu };
+            metrics.push(self.counter(
+                "schedstat_running_seconds_total",
+                timestamp,
+                cpu.running_ns as f64 / 1_000_000_000.0,
+                tags.clone(),
+            ));
+            metrics.push(self.counter(
+                "schedstat_waiting_seconds_total",
+                timestamp,
+                cpu.waiting_ns as f64 / 1_000_000_000.0,
+                tags,
+            ));
+        }
+        for domain in parse_domain_migrations(&schedstat) {
+            metrics.push(self.counter(
+                "schedstat_migrations_total",
+                timestamp,
+                domain.migrations as f64,
+                btreemap! {
+                    "cpu" => domain.cpu,
+                    "domain" => domain.domain,
+                },
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct CpuSchedstat {
+    cpu: String,
+    /// The time spent running tasks.
+    running_ns: u64,
+    /// The time tasks spent runnable, waiting on the run queue.
+    waiting_ns: u64,
+}
+
+/// Parses the `cpu<N>` lines of `/proc/schedstat`, skipping the scheduling
+/// domain lines. Returns `None` for versions older than 15, which reported
+/// jiffies; a missing version header is taken as a current version.
+fn parse_schedstat(schedstat: &str) -> Option<Vec<CpuSchedstat>> {
+    let mut cpus = Vec::new();
+    for line in schedstat.lines() {
+        let mut fields = line.split_whitespace();
+        let name = match fields.next() {
+            Some(name) => name,
+            None => continue,
+        };
+        if name == "version" {
+            let version = fields.next()?.parse::<u32>().ok()?;
+            if version < MIN_VERSION {
+                return None;
+            }
+            continue;
+        }
+        let cpu = match name.strip_prefix("cpu") {
+            Some(cpu) if !cpu.is_empty() => cpu,
+            _ => continue,
+        };
+        // cpu<N> yld_count 0 sched_count sched_goidle ttwu_count ttwu_local
+        // rq_cpu_time run_delay pcount
+        let mut times = fields.skip(6).map(|field| field.parse::<u64>().ok());
+        if let (Some(Some(running_ns)), Some(Some(waiting_ns))) = (times.next(), times.next()) {
+            cpus.push(CpuSchedstat {
+                cpu: cpu.to_string(),
+                running_ns,
+                waiting_ns,
+            });
+        }
+    }
+    Some(cpus)
+}
+
+#[derive(Debug, PartialEq)]
+struct DomainMigrations {
+    cpu: String,
+    /// The level of the scheduling domain, starting from 0 for the CPUs
+    /// closest to this one.
+    domain: String,
+    /// The tasks the load balancer moved to the CPU from others in the
+    /// domain.
+    migrations: u64,
+}
+
+/// Parses the `domain<N>` lines following each `cpu<N>` line of
+/// `/proc/schedstat`, summing the tasks gained by load balancing in each
+/// idle state and those pushed by active balancing. As version 17 added a
+/// column for the domain name, the counts are taken from the end of the
+/// lines. A missing version header is taken as a current version.
+fn parse_domain_migrations(schedstat: &str) -> Vec<DomainMigrations> {
+    // lb_count lb_balanced lb_failed lb_imbalance lb_gained lb_hot_gained
+    // lb_nobusyq lb_nobusyg, with lb_imbalance split into four since
+    // version 17
+    let mut balance_fields = 11;
+    let mut cpu = None;
+    let mut domains = Vec::new();
+    for line in schedstat.lines() {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let name = match fields.first() {
+            Some(name) => *name,
+            None => continue,
+        };
+        if name == "version" {
+            if let Some(version) = fields
+                .get(1)
+                .and_then(|version| version.parse::<u32>().ok())
+            {
+                balance_fields = if version < SPLIT_IMBALANCE_VERSION {
+                    8
+                } else {
+                    11
+                };
+            }
+            continue;
+        }
+        if let Some(index) = name.strip_prefix("cpu") {
+            cpu = Some(index);
+            continue;
+        }
+        let (cpu, domain) = match (cpu, name.strip_prefix("domain")) {
+            (Some(cpu), Some(domain)) => (cpu, domain),
+            _ => continue,
+        };
+        // The balancing counts for each of the three idle states, then the
+        // active, exec and fork balancing and the wakeup counts.
+        let count = balance_fields * 3 + 12;
+        if fields.len() < count + 2 {
+            continue;
+        }
+        let counts = match fields[fields.len() - count..]
+            .iter()
+            .map(|field| field.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(counts) => counts,
+            None => continue,
+        };
+        // lb_gained is followed by three more balancing counts, and
+        // alb_pushed is the third active balancing count.
+        let gained = (0..3)
+            .map(|state| counts[state * balance_fields + balance_fields - 4])
+            .sum::<u64>();
+        let pushed = counts[balance_fields * 3 + 2];
+        domains.push(DomainMigrations {
+            cpu: cpu.to_string(),
+            domain: domain.to_string(),
+            migrations: gained + pushed,
+        });
+    }
+    domains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_schedstat() {
+        let cpus = parse_schedstat(
+            "version 15
+timestamp 4297299139
+cpu0 0 0 0 0 0 0 1505336587343 35187523428 7371154
+domain0 00000000,00000003 212 202 4 10 6 0 6 200 37 37 0 0 0 0 0 37 34 0 3 0 0 0 0 0 0 0 0 0 0 0 0 0 14 0 0 0 0 0 0 0 0 0 0 0 0 0
+cpu1 0 0 0 0 0 0 1378903346421 30744390312 6844891
+",
+        )
+        .unwrap();
+        assert_eq!(
+            cpus,
+            vec![
+                CpuSchedstat {
+                    cpu: "0".into(),
+                    running_ns: 1_505_336_587_343,
+                    waiting_ns: 35_187_523_428,
+                },
+                CpuSchedstat {
+                    cpu: "1".into(),
+                    running_ns: 1_378_903_346_421,
+                    waiting_ns: 30_744_390_312,
+                },
+            ]
+        );
+
+        // Later versions only changed the domain lines.
+        let cpus = parse_schedstat("version 17\ncpu0 0 0 0 0 0 0 10 20 30\n").unwrap();
+        assert_eq!(cpus[0].waiting_ns, 20);
+        assert_eq!(
+            parse_schedstat("cpu3 0 0 0 0 0 0 10 20 30\n").unwrap()[0].cpu,
+            "3"
+        );
+        assert_eq!(
+            parse_schedstat("version 14\ncpu0 0 0 0 0 0 0 0 0 0 0 0 0\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_domain_migrations() {
+        let domains = parse_domain_migrations(
+            "version 15
+timestamp 4297299139
+cpu0 0 0 0 0 0 0 1505336587343 35187523428 7371154
+domain0 00000003 12 10 1 3 2 0 0 1 40 38 1 2 1 0 1 0 25 20 5 9 4 0 2 3 1 0 1 0 0 0 0 0 0 210 33 0
+domain1 0000000f 6 6 0 0 0 0 0 0 20 19 1 1 3 0 0 1 9 8 1 2 1 0 0 0 0 0 0 0 0 0 0 0 0 64 12 0
+cpu1 0 0 0 0 0 0 1378903346421 30744390312 6844891
+domain0 00000003 7 7 0 0 0 0 0 0 31 30 1 1 1 0 0 1 18 15 3 4 2 0 1 0 0 0 0 0 0 0 0 0 0 197 29 0
+",
+        );
+        assert_eq!(
+            domains,
+            vec![
+                DomainMigrations {
+                    cpu: "0".into(),
+                    domain: "0".into(),
+                    migrations: 8,
+                },
+                DomainMigrations {
+                    cpu: "0".into(),
+                    domain: "1".into(),
+                    migrations: 4,
+                },
+                DomainMigrations {
+                    cpu: "1".into(),
+                    domain: "0".into(),
+                    migrations: 3,
+                },
+            ]
+        );
+
+        // Version 17 names the domains and splits the imbalance count
+        let domains = parse_domain_migrations(
+            "version 17
+cpu0 0 0 0 0 0 0 1505336587343 35187523428 7371154
+domain0 MC 00000003 12 10 1 3 0 0 0 2 0 0 1 40 38 1 2 0 0 0 1 0 1 0 25 20 5 9 0 0 0 4 0 2 3 1 0 1 0 0 0 0 0 0 210 33 0
+",
+        );
+        assert_eq!(domains[0].migrations, 8);
+
+        // Truncated lines are skipped
+        assert_eq!(
+            parse_domain_migrations("version 15\ncpu0 0 0 0 0 0 0 1 2 3\ndomain0 00000003 1 2 3\n"),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+
+impl HostMetricsConfig {
+    pub async fn softirq_metrics(&self) -> Vec<Metric> {
+        let softirqs = match std::fs::read_to_string(self.procfs_root().join("softirqs")) {
+            Ok(softirqs) => softirqs,
+            Err(error) => {
+                error!(message = "Failed to load softirq counts.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for softirq in parse_softirqs(&softirqs) {
+            if !self.softirqs.types.contains_str(Some(&softirq.kind)) {
+                continue;
+            }
+            for (cpu, &count) in softirq.counts.iter().enumerate() {
+                metrics.push(self.counter(
+                    "softirqs_total",
+                    timestamp,
+                    count as f64,
+                    btreemap! {
+                        "type" => softirq.kind.clone(),
+                        "cpu" => cpu.to_string(),
+                    },
+                ));
+            }
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Softirq {
+    kind: String,
+    /// The number of softirqs handled, indexed by CPU.
+    counts: Vec<u64>,
+}
+
+/// Parses the `<type>: <count per CPU>...` lines of `/proc/softirqs`,
+/// skipping the header naming the CPUs.
+fn parse_softirqs(softirqs: &str) -> Vec<Softirq> {
+    softirqs
+        .lines()
+        .filter_map(|line| {
+            let (kind, counts) = line.split_once(':')?;
+            let counts = counts
+                .split_whitespace()
+                .map(|count| count.parse().ok())
+                .collect::<Option<Vec<_>>>()?;
+            Some(Softirq {
+                kind: kind.trim().to_string(),
+                counts,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_softirqs() {
+        let softirqs = parse_softirqs(
+            "                    CPU0       CPU1
+          HI:          1          0
+       TIMER:    1430578    1392247
+      NET_TX:        531        712
+      NET_RX:     184729     202815
+       BLOCK:      97431      85231
+    IRQ_POLL:          0          0
+     TASKLET:       2167        166
+       SCHED:    1021372     998133
+     HRTIMER:         12          9
+         RCU:     678213     662108
+",
+        );
+        assert_eq!(softirqs.len(), 10);
+        assert_eq!(
+            softirqs[3],
+            Softirq {
+                kind: "NET_RX".into(),
+                counts: vec![184_729, 202_815],
+            }
+        );
+        assert_eq!(softirqs[9].kind, "RCU");
+    }
+}
@@ -0,0 +1,127 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The `st` value of listening sockets in `/proc/net/tcp`.
+const TCP_LISTEN: &str = "0A";
+
+impl HostMetricsConfig {
+    pub async fn tcp_listen_metrics(&self) -> Vec<Metric> {
+        let procfs = self.procfs_root();
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+
+        let mut backlogs = BTreeMap::<u16, u64>::new();
+        for file in &["net/tcp", "net/tcp6"] {
+            match std::fs::read_to_string(procfs.join(file)) {
+                Ok(sockets) => {
+                    for (port, backlog) in parse_listen_backlogs(&sockets) {
+                        *backlogs.entry(port).or_default() += backlog;
+                    }
+                }
+                // IPv6 may be disabled
+                Err(error)
+                    if *file == "net/tcp6" && error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    error!(message = "Failed to load TCP sockets.", file = %file, %error, internal_log_rate_secs = self.error_log_rate_secs());
+                    metrics.extend(self.collect_error(&error));
+                }
+            }
+        }
+        for (port, backlog) in backlogs {
+            let port = port.to_string();
+            if self.tcp_listen.ports.contains_str(Some(&port)) {
+                metrics.push(self.gauge(
+                    "tcp_listen_backlog",
+                    timestamp,
+                    backlog as f64,
+                    btreemap! { "port" => port },
+                ));
+            }
+        }
+
+        if let Some(overflows) = read_listen_overflows(&procfs) {
+            metrics.push(self.counter(
+                "tcp_listen_overflow_total",
+                timestamp,
+                overflows as f64,
+                btreemap! {},
+            ));
+        }
+
+        metrics
+    }
+}
+
+/// Parses the accept queue lengths of the listening sockets in a
+/// `/proc/net/tcp` or `/proc/net/tcp6` table.
+fn parse_listen_backlogs(sockets: &str) -> Vec<(u16, u64)> {
+    sockets
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            // sl local_address rem_address st tx_queue:rx_queue ...
+            if fields.len() < 5 || fields[3] != TCP_LISTEN {
+                return None;
+            }
+            let (_, port) = fields[1].rsplit_once(':')?;
+            let (_, rx_queue) = fields[4].split_once(':')?;
+            Some((
+                u16::from_str_radix(port, 16).ok()?,
+                u64::from_str_radix(rx_queue, 16).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Reads the number of times the accept queue of any listening socket
+/// overflowed, which the kernel only tracks system-wide.
+fn read_listen_overflows(procfs: &Path) -> Option<u64> {
+    let netstat = std::fs::read_to_string(procfs.join("net/netstat")).ok()?;
+    parse_netstat(&netstat, "TcpExt", "ListenOverflows")
+}
+
+/// Finds a value in `/proc/net/netstat`, where each group is a line of names
+/// followed by a line of values.
+fn parse_netstat(netstat: &str, group: &str, name: &str) -> Option<u64> {
+    let prefix = format!("{}:", group);
+    let mut lines = netstat.lines().filter(|line| line.starts_with(&prefix));
+    let names = lines.next()?.split_whitespace().skip(1);
+    let values = lines.next()?.split_whitespace().skip(1);
+    names
+        .zip(values)
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_listen_backlogs() {
+        let sockets = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000011 00:00000000 00000000  1000        0 31337 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1234 1 0000000000000000 100 0 0 10 0
+   2: 0500000A:1F90 2200A8C0:D431 01 00000000:00000000 02:000AFC8A 00000000  1000        0 31338 2 0000000000000000 20 4 30 10 -1
+";
+        assert_eq!(parse_listen_backlogs(sockets), vec![(8080, 17), (22, 0)]);
+    }
+
+    #[test]
+    fn parses_listen_overflows() {
+        let netstat = "TcpExt: SyncookiesSent SyncookiesRecv ListenOverflows ListenDrops
+TcpExt: 0 0 42 43
+IpExt: InNoRoutes InTruncatedPkts
+IpExt: 0 0
+";
+        assert_eq!(
+            parse_netstat(netstat, "TcpExt", "ListenOverflows"),
+            Some(42)
+        );
+        assert_eq!(parse_netstat(netstat, "TcpExt", "Missing"), None);
+    }
+}
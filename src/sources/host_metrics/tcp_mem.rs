@@ -0,0 +1,101 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::fs;
+
+impl HostMetricsConfig {
+    /// Reports the TCP memory thresholds and the memory allocated to TCP
+    /// sockets, all counted in pages.
+    pub async fn tcp_memory_metrics(&self) -> Vec<Metric> {
+        let procfs = self.procfs_root();
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        match fs::read_to_string(procfs.join("sys/net/ipv4/tcp_mem")) {
+            Ok(tcp_mem) => {
+                if let Some(thresholds) = parse_tcp_mem(&tcp_mem) {
+                    for &(name, value) in &[
+                        ("tcp_mem_pressure_low", thresholds.low),
+                        ("tcp_mem_pressure", thresholds.pressure),
+                        ("tcp_mem_max", thresholds.max),
+                    ] {
+                        metrics.push(self.gauge(name, timestamp, value as f64, btreemap! {}));
+                    }
+                }
+            }
+            Err(error) => {
+                error!(message = "Failed to load TCP memory thresholds.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+        if let Some(allocated) = fs::read_to_string(procfs.join("net/sockstat"))
+            .ok()
+            .and_then(|sockstat| parse_sockstat_tcp_mem(&sockstat))
+        {
+            metrics.push(self.gauge(
+                "tcp_mem_allocated",
+                timestamp,
+                allocated as f64,
+                btreemap! {},
+            ));
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TcpMem {
+    /// Below this, TCP doesn't regulate its memory allocation.
+    low: u64,
+    /// Above this, TCP moderates its memory consumption until it falls
+    /// below `low` again.
+    pressure: u64,
+    /// Above this, TCP refuses to allocate memory for sockets.
+    max: u64,
+}
+
+/// Parses the `<low> <pressure> <max>` thresholds of `tcp_mem`.
+fn parse_tcp_mem(tcp_mem: &str) -> Option<TcpMem> {
+    let mut fields = tcp_mem.split_whitespace().map(|field| field.parse().ok());
+    Some(TcpMem {
+        low: fields.next()??,
+        pressure: fields.next()??,
+        max: fields.next()??,
+    })
+}
+
+/// Parses the `mem` field of the `TCP:` line of `/proc/net/sockstat`.
+fn parse_sockstat_tcp_mem(sockstat: &str) -> Option<u64> {
+    let line = sockstat
+        .lines()
+        .find_map(|line| line.strip_prefix("TCP:"))?;
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    fields
+        .chunks(2)
+        .find(|field| field[0] == "mem")
+        .and_then(|field| field.get(1)?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tcp_mem() {
+        assert_eq!(
+            parse_tcp_mem("188319\t251094\t376638\n"),
+            Some(TcpMem {
+                low: 188_319,
+                pressure: 251_094,
+                max: 376_638,
+            })
+        );
+        assert_eq!(parse_tcp_mem("188319 251094\n"), None);
+
+        let sockstat = "sockets: used 412
+TCP: inuse 23 orphan 0 tw 4 alloc 31 mem 7
+UDP: inuse 5 mem 2
+";
+        assert_eq!(parse_sockstat_tcp_mem(sockstat), Some(7));
+        assert_eq!(parse_sockstat_tcp_mem("UDP: inuse 5 mem 2\n"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::fs;
+use std::path::Path;
+
+impl HostMetricsConfig {
+    pub async fn thermal_throttle_metrics(&self) -> Vec<Metric> {
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        for throttle in read_throttles(&self.sysfs_root()) {
+            let tags = btreemap! {
+                "cpu" => throttle.cpu.to_string(),
+                "scope" => throttle.scope,
+            };
+            metrics.push(self.counter(
+                "cpu_throttle_count_total",
+                timestamp,
+                throttle.count as f64,
+                tags.clone(),
+            ));
+            if let Some(time_ms) = throttle.time_ms {
+                metrics.push(self.counter(
+                    "cpu_thermal_throttle_seconds_total",
+                    timestamp,
+                    time_ms as f64 / 1000.0,
+                    tags,
+                ));
+            }
+        }
+        metrics
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Throttle {
+    cpu: usize,
+    /// Whether the core of the CPU or its whole package throttled, which
+    /// every CPU of the package reports alike.
+    scope: &'static str,
+    /// The number of times the temperature crossed the throttling threshold.
+    count: u64,
+    /// The time spent above the threshold, only reported since Linux 5.7.
+    time_ms: Option<u64>,
+}
+
+/// Reads the thermal throttling counters of each CPU, which are only
+/// exposed on Intel CPUs.
+fn read_throttles(sysfs: &Path) -> Vec<Throttle> {
+    let entries = match fs::read_dir(sysfs.join("devices/system/cpu")) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut throttles = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let cpu = name.to_str()?.strip_prefix("cpu")?.parse::<usize>().ok()?;
+            Some((cpu, entry.path().join("thermal_throttle")))
+        })
+        .flat_map(|(cpu, path)| {
+            ["core", "package"].iter().filter_map(move |&scope| {
+                let count = read_u64(&path.join(format!("{}_throttle_count", scope)))?;
+                let time_ms = read_u64(&path.join(format!("{}_throttle_total_time_ms", scope)));
+                Some(Throttle {
+                    cpu,
+                    scope,
+                    count,
+                    time_ms,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+    throttles.sort_by_key(|throttle| (throttle.cpu, throttle.scope));
+    throttles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_throttles() {
+        let sysfs = tempfile::tempdir().unwrap();
+        assert_eq!(read_throttles(sysfs.path()), vec![]);
+
+        let cpus = sysfs.path().join("devices/system/cpu");
+        fs::create_dir_all(cpus.join("cpu1")).unwrap();
+        fs::create_dir_all(cpus.join("cpufreq")).unwrap();
+        assert_eq!(read_throttles(sysfs.path()), vec![]);
+
+        let throttle = cpus.join("cpu0/thermal_throttle");
+        fs::create_dir_all(&throttle).unwrap();
+        fs::write(throttle.join("core_throttle_count"), "12\n").unwrap();
+        fs::write(throttle.join("core_throttle_total_time_ms"), "3500\n").unwrap();
+        fs::write(throttle.join("package_throttle_count"), "4\n").unwrap();
+        assert_eq!(
+            read_throttles(sysfs.path()),
+            vec![
+                Throttle {
+                    cpu: 0,
+                    scope: "core",
+                    count: 12,
+                    time_ms: Some(3500),
+                },
+                Throttle {
+                    cpu: 0,
+                    scope: "package",
+                    count: 4,
+                    time_ms: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use super::TopNConfig;
+use crate::event::metric::{Metric, MetricSeries, MetricValue};
+use indexmap::{map::Entry, IndexMap};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// The tag value of the series aggregating everything outside the top N.
+const OTHERS: &str = "others";
+
+/// Keeps the `count` series ranking highest by `metric`, and sums the
+/// metrics of the remaining series into an `others` series.
+pub(super) fn top_n(metrics: Vec<Metric>, config: &TopNConfig) -> Vec<Metric> {
+    let mut ranking = HashMap::<Vec<String>, f64>::new();
+    for metric in metrics
+        .iter()
+        .filter(|metric| metric.name() == config.metric)
+    {
+        if let (Some(series), Some(value)) = (config.series(metric), value(metric)) {
+            *ranking.entry(series).or_default() += value;
+        }
+    }
+    if ranking.len() <= config.count {
+        return metrics;
+    }
+
+    let mut ranking = ranking.into_iter().collect::<Vec<_>>();
+    ranking.sort_by(|(a_series, a_value), (b_series, b_value)| {
+        b_value
+            .partial_cmp(a_value)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a_series.cmp(b_series))
+    });
+    let kept = ranking
+        .into_iter()
+        .take(config.count)
+        .map(|(series, _)| series)
+        .collect::<HashSet<_>>();
+
+    let mut result = Vec::with_capacity(metrics.len());
+    let mut others = IndexMap::<MetricSeries, Metric>::new();
+    for mut metric in metrics {
+        match config.series(&metric) {
+            Some(series) if !kept.contains(&series) => {
+                for tag in &config.tags {
+                    metric.insert_tag(tag.clone(), OTHERS.into());
+                }
+                match others.entry(metric.series().clone()) {
+                    Entry::Occupied(mut entry) => {
+                        if !entry.get_mut().update(&metric) {
+                            debug!(message = "Dropping metric that cannot be aggregated.", name = %metric.name());
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(metric);
+                    }
+                }
+            }
+            _ => result.push(metric),
+        }
+    }
+    result.extend(others.into_iter().map(|(_, metric)| metric));
+    result
+}
+
+impl TopNConfig {
+    /// The values of the identifying tags of a metric, if it has all of them.
+    fn series(&self, metric: &Metric) -> Option<Vec<String>> {
+        let tags = metric.tags()?;
+        self.tags.iter().map(|tag| tags.get(tag).cloned()).collect()
+    }
+}
+
+fn value(metric: &Metric) -> Option<f64> {
+    match metric.value() {
+        MetricValue::Counter { value } | MetricValue::Gauge { value } => Some(*value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::metric::MetricKind;
+    use shared::btreemap;
+
+    fn process_metric(name: &str, pid: u32, value: f64) -> Metric {
+        Metric::new(name, MetricKind::Absolute, MetricValue::Gauge { value }).with_tags(Some(
+            btreemap! {
+                "collector" => "process",
+                "pid" => pid.to_string(),
+                "name" => format!("process{}", pid),
+            },
+        ))
+    }
+
+    #[test]
+    fn keeps_top_n_series_and_others() {
+        let mut metrics = Vec::new();
+        for (pid, cpu) in [(1, 5.0), (2, 50.0), (3, 1.0), (4, 20.0), (5, 2.0)].iter() {
+            metrics.push(process_metric("process_cpu_seconds_total", *pid, *cpu));
+            metrics.push(process_metric("process_memory_bytes", *pid, 100.0));
+        }
+        // Metrics without the identifying tags are never aggregated.
+        metrics.push(
+            Metric::new(
+                "load1",
+                MetricKind::Absolute,
+                MetricValue::Gauge { value: 1.0 },
+            )
+            .with_tags(Some(btreemap! { "collector" => "load" })),
+        );
+
+        let config = TopNConfig {
+            metric: "process_cpu_seconds_total".into(),
+            tags: vec!["pid".into(), "name".into()],
+            count: 2,
+        };
+        let metrics = top_n(metrics, &config);
+
+        let pids = metrics
+            .iter()
+            .filter(|metric| metric.name() == "process_cpu_seconds_total")
+            .map(|metric| metric.tag_value("pid").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(pids, vec!["2", "4", "others"]);
+
+        let others = metrics
+            .iter()
+            .filter(|metric| metric.tag_matches("pid", OTHERS))
+            .map(|metric| (metric.name(), metric.value().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            others,
+            vec![
+                (
+                    "process_cpu_seconds_total",
+                    MetricValue::Gauge { value: 8.0 }
+                ),
+                ("process_memory_bytes", MetricValue::Gauge { value: 300.0 }),
+            ]
+        );
+        assert_eq!(
+            metrics
+                .iter()
+                .filter(|metric| metric.tag_matches("name", OTHERS))
+                .count(),
+            2
+        );
+        assert!(metrics.iter().any(|metric| metric.name() == "load1"));
+        assert_eq!(metrics.len(), 7);
+    }
+
+    #[test]
+    fn keeps_everything_within_count() {
+        let metrics = (1..=3)
+            .map(|pid| process_metric("process_cpu_seconds_total", pid, 1.0))
+            .collect::<Vec<_>>();
+        let config = TopNConfig {
+            metric: "process_cpu_seconds_total".into(),
+            tags: vec!["pid".into()],
+            count: 3,
+        };
+        assert_eq!(top_n(metrics.clone(), &config), metrics);
+    }
+}
@@ -0,0 +1,184 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REBOOT_REQUIRED: &str = "/var/run/reboot-required";
+const UPDATES_AVAILABLE: &str = "/var/lib/update-notifier/updates-available";
+const KERNEL_MODULES: &str = "/lib/modules";
+
+impl HostMetricsConfig {
+    pub async fn updates_metrics(&self) -> Vec<Metric> {
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        if let Some(required) = self.reboot_required() {
+            metrics.push(self.gauge(
+                "reboot_required",
+                timestamp,
+                if required { 1.0 } else { 0.0 },
+                btreemap! {},
+            ));
+        }
+        let path = self.updates_path(&self.updates.updates_available_path, UPDATES_AVAILABLE);
+        if let Some(updates) = fs::read_to_string(path)
+            .ok()
+            .and_then(|updates| parse_updates_available(&updates))
+        {
+            metrics.push(self.gauge("pending_updates", timestamp, updates as f64, btreemap! {}));
+        }
+        metrics
+    }
+
+    /// Whether the system needs a reboot, flagged by the package manager on
+    /// Debian and derivatives, or otherwise by the running kernel not being
+    /// the newest installed one.
+    fn reboot_required(&self) -> Option<bool> {
+        let flag = self.updates_path(&self.updates.reboot_required_path, REBOOT_REQUIRED);
+        if flag.exists() {
+            return Some(true);
+        }
+        let running = fs::read_to_string(self.procfs_root().join("sys/kernel/osrelease")).ok()?;
+        let modules = self.updates_path(&self.updates.kernel_modules_path, KERNEL_MODULES);
+        let running = running.trim();
+        let latest = latest_installed_kernel(&modules, running)?;
+        Some(latest != running)
+    }
+
+    /// A configured path, or the default one on the monitored system.
+    fn updates_path(&self, path: &Option<PathBuf>, default: &str) -> PathBuf {
+        path.clone().unwrap_or_else(|| self.host_path(default))
+    }
+}
+
+/// Finds the newest installed kernel of the same flavour as the `running`
+/// release by comparing the versions in their releases. Kernels of other
+/// flavours, such as `-lowlatency` next to `-generic`, don't compare
+/// reliably so are left out.
+fn latest_installed_kernel(modules: &Path, running: &str) -> Option<String> {
+    let (flavour, _) = release_version(running)?;
+    fs::read_dir(modules)
+        .ok()?
+        .filter_map(|entry| {
+            let release = entry.ok()?.file_name().into_string().ok()?;
+            let (release_flavour, version) = release_version(&release)?;
+            (release_flavour == flavour).then(|| (version, release))
+        })
+        .max()
+        .map(|(_, release)| release)
+}
+
+/// Splits a kernel release into its flavour, being the release with its
+/// numbers replaced by `#` (such as `#.#.#-#-generic`), and those numbers,
+/// by which releases of the same flavour sort.
+fn release_version(release: &str) -> Option<(String, Vec<u64>)> {
+    let mut flavour = String::new();
+    let mut numbers = Vec::new();
+    let mut digits = String::new();
+    for c in release.chars().map(Some).chain(std::iter::once(None)) {
+        match c {
+            Some(c) if c.is_ascii_digit() => digits.push(c),
+            _ => {
+                if !digits.is_empty() {
+                    numbers.push(std::mem::take(&mut digits).parse().ok()?);
+                    flavour.push('#');
+                }
+                flavour.extend(c);
+            }
+        }
+    }
+    Some((flavour, numbers))
+}
+
+/// Parses the number of pending updates from the `<count> updates can be
+/// applied immediately.` summary written by update-notifier, worded as
+/// `<count> packages can be updated.` by older releases.
+fn parse_updates_available(updates: &str) -> Option<u64> {
+    updates.lines().find_map(|line| {
+        let (count, rest) = line.trim().split_once(' ')?;
+        if !rest.contains("can be") {
+            return None;
+        }
+        count.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::UpdatesConfig;
+    use super::*;
+    use crate::event::metric::MetricValue;
+
+    #[test]
+    fn parses_updates_available() {
+        assert_eq!(
+            parse_updates_available(
+                "\n12 updates can be applied immediately.\n5 of these updates are standard security updates.\n"
+            ),
+            Some(12)
+        );
+        assert_eq!(
+            parse_updates_available(
+                "0 packages can be updated.\n0 updates are security updates.\n"
+            ),
+            Some(0)
+        );
+        assert_eq!(parse_updates_available(""), None);
+    }
+
+    #[test]
+    fn finds_latest_installed_kernel() {
+        let modules = tempfile::tempdir().unwrap();
+        for release in &[
+            "5.15.0-91-generic",
+            "5.15.0-101-generic",
+            "5.4.0-150-generic",
+            "6.2.0-1-lowlatency",
+        ] {
+            fs::create_dir(modules.path().join(release)).unwrap();
+        }
+        assert_eq!(
+            latest_installed_kernel(modules.path(), "5.15.0-91-generic"),
+            Some("5.15.0-101-generic".into())
+        );
+        assert_eq!(
+            latest_installed_kernel(modules.path(), "6.2.0-1-lowlatency"),
+            Some("6.2.0-1-lowlatency".into())
+        );
+        assert_eq!(latest_installed_kernel(modules.path(), "6.5.0-1-aws"), None);
+        assert_eq!(
+            release_version("5.15.0-101-generic"),
+            Some(("#.#.#-#-generic".into(), vec![5, 15, 0, 101]))
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_reboot_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let flag = dir.path().join("reboot-required");
+        fs::write(&flag, "*** System restart required ***\n").unwrap();
+        let updates = dir.path().join("updates-available");
+        fs::write(&updates, "3 updates can be applied immediately.\n").unwrap();
+
+        let config = HostMetricsConfig {
+            updates: UpdatesConfig {
+                reboot_required_path: Some(flag.clone()),
+                updates_available_path: Some(updates),
+                kernel_modules_path: Some(dir.path().join("modules")),
+            },
+            ..Default::default()
+        };
+        let metrics = config.updates_metrics().await;
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].name(), "reboot_required");
+        assert_eq!(metrics[0].value(), &MetricValue::Gauge { value: 1.0 });
+        assert_eq!(metrics[1].name(), "pending_updates");
+        assert_eq!(metrics[1].value(), &MetricValue::Gauge { value: 3.0 });
+
+        // Without the flag, the installed kernels can't be found either.
+        fs::remove_file(&flag).unwrap();
+        let metrics = config.updates_metrics().await;
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name(), "pending_updates");
+    }
+}
@@ -0,0 +1,108 @@
+use super::{clock_ticks_per_second, read_u64, HostMetricsConfig};
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::path::Path;
+
+impl HostMetricsConfig {
+    pub async fn vm_metrics(&self) -> Vec<Metric> {
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+
+        match std::fs::read_to_string(self.procfs_root().join("stat")) {
+            Ok(stat) => match parse_steal_ticks(&stat) {
+                Some(ticks) => metrics.push(self.counter(
+                    "vm_steal_seconds_total",
+                    timestamp,
+                    ticks as f64 / clock_ticks_per_second(),
+                    btreemap! {},
+                )),
+                None => {
+                    error!(
+                        message = "Failed to parse CPU steal time.",
+                        internal_log_rate_secs = self.error_log_rate_secs()
+                    )
+                }
+            },
+            Err(error) => {
+                error!(message = "Failed to load CPU statistics.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+
+        if let Some(balloon) = read_xen_balloon(&self.sysfs_root()) {
+            metrics.push(self.gauge(
+                "vm_balloon_current_bytes",
+                timestamp,
+                balloon.current_bytes as f64,
+                btreemap! {},
+            ));
+            metrics.push(self.gauge(
+                "vm_balloon_target_bytes",
+                timestamp,
+                balloon.target_bytes as f64,
+                btreemap! {},
+            ));
+        }
+
+        metrics
+    }
+}
+
+/// Returns the steal time aggregated across all CPUs, in clock ticks, from
+/// the summary `cpu` line of `/proc/stat`.
+fn parse_steal_ticks(stat: &str) -> Option<u64> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    // cpu user nice system idle iowait irq softirq steal ...
+    line.split_whitespace().nth(8)?.parse().ok()
+}
+
+#[derive(Debug, PartialEq)]
+struct Balloon {
+    current_bytes: u64,
+    target_bytes: u64,
+}
+
+/// Reads the Xen memory balloon state, which is absent outside of Xen guests.
+fn read_xen_balloon(sysfs: &Path) -> Option<Balloon> {
+    let path = sysfs.join("devices/system/xen_memory/xen_memory0");
+    Some(Balloon {
+        current_bytes: read_u64(&path.join("info/current_kb"))? * 1024,
+        target_bytes: read_u64(&path.join("target_kb"))? * 1024,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parses_steal_ticks() {
+        let stat = "cpu  2255 34 2290 22625563 6290 127 456 1234 0 0
+cpu0 1132 34 1441 11311718 3675 127 438 617 0 0
+cpu1 1123 0 849 11313845 2614 0 18 617 0 0
+intr 114930548 113199788 3 0 5 263 0 4 [... lots more numbers ...]
+ctxt 1990473
+";
+        assert_eq!(parse_steal_ticks(stat), Some(1234));
+        assert_eq!(parse_steal_ticks("cpu0 1 2 3 4 5 6 7 8\n"), None);
+    }
+
+    #[test]
+    fn reads_xen_balloon() {
+        let sysfs = tempfile::tempdir().unwrap();
+        assert_eq!(read_xen_balloon(sysfs.path()), None);
+
+        let path = sysfs.path().join("devices/system/xen_memory/xen_memory0");
+        fs::create_dir_all(path.join("info")).unwrap();
+        fs::write(path.join("info/current_kb"), "4194304\n").unwrap();
+        fs::write(path.join("target_kb"), "2097152\n").unwrap();
+        assert_eq!(
+            read_xen_balloon(sysfs.path()),
+            Some(Balloon {
+                current_bytes: 4 * 1024 * 1024 * 1024,
+                target_bytes: 2 * 1024 * 1024 * 1024,
+            })
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use super::HostMetricsConfig;
+use crate::event::metric::Metric;
+use shared::btreemap;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use tokio::process::Command;
+
+impl HostMetricsConfig {
+    pub async fn zfs_metrics(&self) -> Vec<Metric> {
+        let kstat = self.procfs_root().join("spl/kstat/zfs");
+        let arcstats = match std::fs::read_to_string(kstat.join("arcstats")) {
+            Ok(arcstats) => parse_kstat(&arcstats),
+            // The kstats only exist when the ZFS module is loaded.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(error) => {
+                error!(message = "Failed to load ZFS ARC statistics.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                return self.collect_error(&error);
+            }
+        };
+
+        let timestamp = self.now();
+        let mut metrics = Vec::new();
+        if let Some(&size) = arcstats.get("size") {
+            metrics.push(self.gauge("zfs_arc_size_bytes", timestamp, size as f64, btreemap! {}));
+        }
+
+        for (pool, health) in read_pool_health(&kstat) {
+            if self.zfs.pools.contains_str(Some(&pool)) {
+                metrics.push(self.gauge(
+                    "zfs_pool_health",
+                    timestamp,
+                    1.0,
+                    btreemap! { "pool" => pool, "health" => health },
+                ));
+            }
+        }
+
+        // The kstats don't include the pool capacity, so it is queried
+        // from the pool configuration instead.
+        match Command::new("zpool")
+            .args(&["list", "-Hp", "-o", "name,allocated,free"])
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => {
+                let list = String::from_utf8_lossy(&output.stdout);
+                for pool in parse_zpool_list(&list) {
+                    if !self.zfs.pools.contains_str(Some(&pool.name)) {
+                        continue;
+                    }
+                    let tags = btreemap! { "pool" => pool.name };
+                    metrics.push(self.gauge(
+                        "zfs_pool_allocated_bytes",
+                        timestamp,
+                        pool.allocated as f64,
+                        tags.clone(),
+                    ));
+                    metrics.push(self.gauge(
+                        "zfs_pool_free_bytes",
+                        timestamp,
+                        pool.free as f64,
+                        tags,
+                    ));
+                }
+            }
+            Ok(output) => {
+                error!(
+                    message = "Failed to list ZFS pools.",
+                    status = %output.status,
+                    internal_log_rate_secs = self.error_log_rate_secs()
+                );
+            }
+            Err(error) => {
+                error!(message = "Failed to list ZFS pools.", %error, internal_log_rate_secs = self.error_log_rate_secs());
+                metrics.extend(self.collect_error(&error));
+            }
+        }
+
+        metrics
+    }
+}
+
+/// Parses the `name type data` rows of a kstat file, skipping the headers
+/// and non-integer values.
+fn parse_kstat(kstat: &str) -> HashMap<String, u64> {
+    kstat
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let value = fields.nth(1)?.parse().ok()?;
+            Some((name.to_string(), value))
+        })
+        .collect()
+}
+
+/// Reads the health of every pool from its `state` kstat.
+fn read_pool_health(kstat: &Path) -> Vec<(String, String)> {
+    let entries = match std::fs::read_dir(kstat) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let state = std::fs::read_to_string(entry.path().join("state")).ok()?;
+            let pool = entry.file_name().to_string_lossy().into_owned();
+            Some((pool, state.trim().to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+struct PoolSpace {
+    name: String,
+    allocated: u64,
+    free: u64,
+}
+
+/// Parses the output of `zpool list -Hp -o name,allocated,free`.
+fn parse_zpool_list(list: &str) -> Vec<PoolSpace> {
+    list.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(PoolSpace {
+                name: fields.next()?.to_string(),
+                allocated: fields.next()?.trim().parse().ok()?,
+                free: fields.next()?.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parses_arcstats() {
+        let arcstats = parse_kstat(
+            "13 1 0x01 123 33456 6289405862 1099736839484
+name                            type data
+hits                            4    103476
+size                            4    2133450328
+c_max                           4    8254291968
+",
+        );
+        assert_eq!(arcstats.get("size"), Some(&2_133_450_328));
+        assert_eq!(arcstats.get("hits"), Some(&103_476));
+        assert_eq!(arcstats.get("name"), None);
+    }
+
+    #[test]
+    fn reads_pool_health() {
+        let kstat = tempfile::tempdir().unwrap();
+        fs::create_dir(kstat.path().join("tank")).unwrap();
+        fs::write(kstat.path().join("tank/state"), "DEGRADED\n").unwrap();
+        fs::write(kstat.path().join("arcstats"), "").unwrap();
+
+        assert_eq!(
+            read_pool_health(kstat.path()),
+            vec![("tank".to_string(), "DEGRADED".to_string())]
+        );
+    }
+
+    #[test]
+    fn parses_pool_space() {
+        assert_eq!(
+            parse_zpool_list("rpool\t41943040000\t58056960000\ntank\t-\t-\n"),
+            vec![PoolSpace {
+                name: "rpool".into(),
+                allocated: 41_943_040_000,
+                free: 58_056_960_000,
+            }]
+        );
+    }
+}
@@ -79,25 +79,256 @@ components: sources: host_metrics: {
 
 	configuration: {
 		collectors: {
-			description: "The list of host metric collector services to use. Defaults to all collectors."
+			description: "The list of host metric collector services to use. Defaults to the collectors listed below, additional collectors must be enabled explicitly. Collectors that are not available on the platform, or whose feature is not enabled in the build, are warned about on startup and collect nothing."
 			common:      true
 			required:    false
 			type: array: {
 				default: ["cpu", "disk", "filesystem", "load", "host", "memory", "network"]
 				items: type: string: {
 					enum: {
-						cpu:        "Metrics related to CPU utilization."
-						disk:       "Metrics related to disk I/O utilization."
-						filesystem: "Metrics related to filesystem space utilization."
-						load:       "Load average metrics (UNIX only)."
-						host:       "Metrics related to host"
-						memory:     "Metrics related to memory utilization."
-						network:    "Metrics related to network utilization."
+						cpu:              "Metrics related to CPU utilization."
+						disk:             "Metrics related to disk I/O utilization."
+						filesystem:       "Metrics related to filesystem space utilization."
+						load:             "Load average metrics (UNIX only)."
+						host:             "Metrics related to host"
+						memory:           "Metrics related to memory utilization."
+						network:          "Metrics related to network utilization."
+						vm:               "Hypervisor-visible metrics for virtual machine guests (Linux only)."
+						cgroup:           "Metrics related to the resource usage of the cgroup Vector runs in (Linux only)."
+						clock:            "The state of the kernel clock synchronization (Linux only)."
+						process:          "Metrics related to individual processes (Linux only)."
+						mdraid:           "Metrics related to the health of Linux software RAID arrays (Linux only)."
+						block_device:     "The capacity and sector size of block devices, filtered by `disk.devices` (Linux only)."
+						tcp_listen:       "Accept queue metrics of listening TCP sockets (Linux only)."
+						net_dev_queue:    "Backlog and drop statistics of the queueing disciplines of network interfaces, filtered by `network.devices` (Linux only, requires the `sources-host_metrics-netlink` feature)."
+						listen_sockets:   "An inventory of the listening TCP and UDP sockets and the processes owning them (Linux only)."
+						kernel_messages:  "The number of messages logged to the kernel ring buffer, read from `/dev/kmsg` (Linux only)."
+						container:        "The CPU and memory usage of running Docker containers (requires the `sources-host_metrics-docker` feature)."
+						buddyinfo:        "The free blocks of the buddy allocator by NUMA node, zone and order, showing memory fragmentation (Linux only)."
+						updates:          "Whether a reboot is required and the number of pending package updates, for patch compliance (Linux only)."
+						schedstat:        "The time each CPU spent running tasks and tasks spent waiting on its run queue, and the tasks migrated to it, read from `/proc/schedstat` (Linux only)."
+						cpuidle:          "The time each CPU spent in each of its idle states (C-states), where a cpuidle driver is loaded (Linux only)."
+						softirqs:         "The number of softirqs handled by each CPU by type, read from `/proc/softirqs` (Linux only)."
+						limits:           "The open files and processes limits of the Vector process itself, not system-wide limits (UNIX only)."
+						thermal_throttle: "The number of times and time each CPU was throttled for overheating, read from `/sys/devices/system/cpu/cpu*/thermal_throttle` (Linux only, Intel CPUs)."
+						zfs:              "ZFS pool health and capacity, and ARC size (Linux only, requires the `sources-host_metrics-zfs` feature)."
+						tcp:              "Round-trip time statistics of TCP connections, read through netlink (Linux only, requires the `sources-host_metrics-netlink` feature)."
 					}
 					syntax: "literal"
 				}
 			}
 		}
+		emit_collected_counts: {
+			description: "Emit a `collected_metrics_count` gauge for every collector, holding the number of metrics it collected in the scrape."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		load_per_core: {
+			description: "Also emit the load averages divided by the number of logical CPUs, as `load1_per_core`, `load5_per_core` and `load15_per_core`, to compare hosts of different sizes."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		boot_time_tag: {
+			description: "Tag the `uptime` metric with the time of the last boot as an ISO 8601 timestamp in `boot_time`, such as `2021-09-01T08:30:00Z`, to show both in one panel. The numeric `uptime` and `boot_time` gauges are unchanged."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		self_metrics: {
+			description: "Emit the resource usage of the Vector process itself as `vector_process_*` metrics, read from `/proc/self` (Linux only)."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		max_concurrency: {
+			description: "The maximum number of concurrent calls made by collectors loading many items, such as the usage of every mounted filesystem. Defaults to the number of CPUs. The metrics are emitted in the same order as when loading one item at a time."
+			common:      false
+			required:    false
+			type: uint: {
+				default: null
+				unit:    null
+				examples: [4]
+			}
+		}
+		retry_empty_scrape: {
+			description: "Retry a scrape once, after a second, when every collector failed or came back empty, such as while procfs is briefly unavailable during a container restart. If the retry fails too, its result is emitted and the next attempt is the next scheduled scrape."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		skip_unchanged_counters: {
+			description: "Skip emitting counters whose value didn't change since the previous scrape, such as those of idle devices. Gauges are always emitted."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		emit_errors: {
+			description: "Emit a `collect_error` gauge whenever a collector fails to load its data, so failures are visible in the metrics stream in addition to the logs."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		tag_unknown_host: {
+			description: "Tag the metrics with `host = \"unknown\"` when the hostname can't be resolved. By default, the `host` tag is left out of all metrics instead. The failure is logged when the source starts."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		max_tag_value_len: {
+			description: "The maximum length of tag values, in characters. Longer values, such as long device paths, are truncated to this length, ending with an ellipsis (`…`). Unlimited by default."
+			common:      false
+			required:    false
+			type: uint: {
+				default: null
+				unit:    null
+				examples: [256]
+			}
+		}
+		error_log_interval_secs: {
+			description: "The minimum interval between logs of the same collection error, such as a file that can't be read. Repeated errors within the interval are counted and summarized instead of logged."
+			common:      false
+			required:    false
+			type: uint: {
+				default: 60
+				unit:    "seconds"
+			}
+		}
+		clamp_values: {
+			description: "Clamp values derived from other counters into their valid ranges, such as `filesystem_used_bytes` into the total size of the filesystem and `filesystem_used_ratio` into `[0, 1]`. Platform quirks can otherwise briefly push them out of range."
+			common:      false
+			required:    false
+			type: bool: default: true
+		}
+		enable: {
+			description: "Enables (`true`) or disables (`false`) individual collectors by name. These take precedence over `collectors`, and collectors not listed here keep the selection made by `collectors`."
+			common:      false
+			required:    false
+			type: object: {
+				examples: [{cpu: false, vm: true}]
+				options: {}
+			}
+		}
+		counters_as_gauges: {
+			description: "Emit all counters as gauges, keeping their `_total` names. Useful for sinks that expect cumulative values as gauges, such as StatsD."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		created_timestamps: {
+			description: "Also emit the time each counter started counting from, in seconds since the epoch, as a gauge named after the counter with `_total` replaced by `_created`, such as `cpu_seconds_created`, following OpenMetrics. The time stays in seconds when `time_unit` is `milliseconds`, the gauge being named after the converted counter, such as `cpu_milliseconds_created`. Counters are taken to have started when Vector did, or at the scrape in which they were found to have been reset. Has no effect on counters emitted as gauges through `counters_as_gauges`."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		counter_mode: {
+			description: "How the values of counters are emitted."
+			common:      false
+			required:    false
+			type: string: {
+				default: "absolute"
+				enum: {
+					absolute:  "Emit the cumulative value of each counter."
+					emit_both: "Also emit the per-second rate of each counter since the previous scrape, as a gauge named after the counter with `_total` replaced by `_rate`, such as `cpu_seconds_rate`. Rates start from the second scrape and are left out after counter resets. Has no effect on counters emitted as gauges through `counters_as_gauges`."
+				}
+				syntax: "literal"
+			}
+		}
+		metric_types: {
+			description: "Overrides the value type of individual metrics, keyed by metric name without the namespace. For example, `uptime` can be emitted as a counter for rate calculations. Takes precedence over `counters_as_gauges`. Unknown metric names are rejected when the source is built."
+			common:      false
+			required:    false
+			type: object: {
+				examples: [
+					{
+						"uptime":                "counter"
+						"disk_read_bytes_total": "gauge"
+					},
+				]
+				options: {}
+			}
+		}
+		sort_metrics: {
+			description: "Emit the metrics of each scrape sorted by name and tags, rather than in collection order."
+			common:      false
+			required:    false
+			type: bool: default: false
+		}
+		top_n: {
+			description: "Limits the cardinality of per-entity metrics. For each entry, only the `count` series with the highest values of `metric` are kept, and the metrics of all other series are summed into a series whose identifying tags are set to `others`."
+			common:      false
+			required:    false
+			type: array: {
+				default: []
+				items: type: object: options: {
+					metric: {
+						description: "The name of the metric whose value ranks the series."
+						required:    true
+						type: string: {
+							examples: ["process_cpu_seconds_total"]
+							syntax: "literal"
+						}
+					}
+					tags: {
+						description: "The tags identifying a series. Metrics missing any of them are left untouched."
+						required:    true
+						type: array: items: type: string: {
+							examples: ["pid", "name"]
+							syntax: "literal"
+						}
+					}
+					count: {
+						description: "The number of series to keep."
+						required:    true
+						type: uint: {
+							examples: [20]
+							unit: null
+						}
+					}
+				}
+			}
+		}
+		tag_filters: {
+			description: "Filters the metrics of all collectors by tag value after collection, keyed by tag name, each with `includes` and `excludes` lists of patterns matched using [globbing](#globbing). Metrics without the tag are kept. Applies on top of the filters of the individual collectors."
+			common:      false
+			required:    false
+			type: object: {
+				examples: [
+					{
+						"mountpoint": {"excludes": ["/snap/*"]}
+						"device": {"excludes": ["loop*", "lo"]}
+					},
+				]
+				options: {}
+			}
+		}
+		timestamp_alignment: {
+			description: "How the timestamps of the metrics are chosen."
+			common:      false
+			required:    false
+			type: string: {
+				default: "collection"
+				enum: {
+					collection: "Use the time each value was collected."
+					interval:   "Round the collection time down to the nearest multiple of `scrape_interval_secs`, so series from multiple hosts share the same timestamps."
+				}
+				syntax: "literal"
+			}
+		}
+		time_unit: {
+			description: "The unit of time-valued metrics, such as `cpu_seconds_total`, `uptime` and `boot_time`. Metric names ending in `_seconds` are renamed to `_milliseconds` accordingly."
+			common:      false
+			required:    false
+			type: string: {
+				default: "seconds"
+				enum: {
+					seconds:      "Report times in seconds."
+					milliseconds: "Report times in milliseconds."
+				}
+				syntax: "literal"
+			}
+		}
 		namespace: {
 			description: "The namespace of metrics. Disabled if empty."
 			common:      false
@@ -107,8 +338,22 @@ components: sources: host_metrics: {
 				syntax:  "literal"
 			}
 		}
+		name_template: {
+			description: """
+				Combines the namespace and name of each metric into its name, replacing the `{namespace}` and `{name}` placeholders, instead of passing the namespace on separately.
+				The separator next to `{namespace}` is dropped when the namespace is disabled.
+				Names used by `top_n` are matched against the resulting names.
+				"""
+			common:      false
+			required:    false
+			type: string: {
+				default: null
+				examples: ["{namespace}_{name}", "{namespace}.{name}", "{name}"]
+				syntax: "literal"
+			}
+		}
 		scrape_interval_secs: {
-			description: "The interval between metric gathering, in seconds."
+			description: "The interval between metric gathering, in seconds. Must be at least 1."
 			common:      true
 			required:    false
 			type: uint: {
@@ -116,28 +361,60 @@ components: sources: host_metrics: {
 				unit:    "seconds"
 			}
 		}
-		disk: {
+		host_root: {
+			description: """
+				Where the root filesystem of the monitored system is mounted, such as when monitoring the host from a container. Collectors read the files of the monitored system under this path, including procfs at `<host_root>/proc` and sysfs at `<host_root>/sys`, and report filesystem usage of the mount points under it. `filesystem.paths` are resolved under it too, while tagged as seen from the monitored system. `PROCFS_ROOT` and `SYSFS_ROOT` take precedence when set. Other explicitly configured paths, such as `cgroups.base_dir`, are used as given.
+
+				The collectors based on heim read procfs and sysfs from one location for the whole Vector process, taken from the first `host_metrics` source started.
+				"""
+			common:      false
+			required:    false
+			type: string: {
+				default: null
+				examples: ["/host"]
+				syntax: "literal"
+			}
+		}
+		cgroups: {
 			common:      false
-			description: #"Options for the "disk" metrics collector."#
+			description: #"Options for the "cgroup" metrics collector."#
 			required:    false
 			type: object: options: {
-				devices: {
+				base_dir: {
 					common:      false
 					required:    false
-					description: "Lists of device name patterns to include or exclude."
+					description: "The path of the cgroup hierarchy to read. Both the legacy (v1) and unified (v2) hierarchies are detected automatically. Defaults to `fs/cgroup` under the sysfs root."
+					type: string: {
+						default: null
+						examples: ["/sys/fs/cgroup"]
+						syntax: "literal"
+					}
+				}
+			}
+		}
+		process: {
+			common:      false
+			description: #"Options for the "process" metrics collector."#
+			required:    false
+			type: object: options: {
+				names: {
+					common:      false
+					required:    false
+					description: "Lists of process name patterns to include or exclude."
 					type: object: options: {
 						includes: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather I/O utilization metrics.
-								Defaults to including all devices.
+								The list of process name patterns for which to gather metrics.
+								Defaults to including all processes.
 								The patterns are matched using [globbing](#globbing).
+								The per-process IO metrics are only emitted when this is set.
 								"""
 							type: array: {
 								default: ["*"]
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["postgres", "java*"]
 									syntax: "literal"
 								}
 							}
@@ -146,44 +423,88 @@ components: sources: host_metrics: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather I/O utilization metrics.
-								Defaults to excluding no devices.
+								The list of process name patterns for which to gather metrics.
+								Defaults to excluding no processes.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: []
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["kworker*"]
 									syntax: "literal"
 								}
 							}
 						}
 					}
 				}
+				cpu_distribution: {
+					common:      false
+					required:    false
+					description: "Emit the CPU time of all matched processes as a single `process_cpu_seconds` distribution, instead of per-process series tagged with their name and ID."
+					type: bool: default: false
+				}
 			}
 		}
-		filesystem: {
+		tcp: {
 			common:      false
-			description: #"Options for the "filesystem" metrics collector."#
+			description: #"Options for the "tcp" metrics collector."#
 			required:    false
 			type: object: options: {
-				devices: {
+				group_by: {
 					common:      false
 					required:    false
-					description: "Lists of device name patterns to include or exclude."
+					description: "How connections are grouped before their round-trip times are summarized. The group is emitted as a tag of the same name."
+					type: string: {
+						default: "state"
+						enum: {
+							state:  "Group connections by TCP state."
+							subnet: "Group connections by the subnet of the remote address."
+						}
+						syntax: "literal"
+					}
+				}
+				ipv4_prefix_length: {
+					common:      false
+					required:    false
+					description: "The prefix length of the subnets IPv4 remote addresses are grouped into."
+					type: uint: {
+						default: 24
+						unit:    null
+					}
+				}
+				ipv6_prefix_length: {
+					common:      false
+					required:    false
+					description: "The prefix length of the subnets IPv6 remote addresses are grouped into."
+					type: uint: {
+						default: 64
+						unit:    null
+					}
+				}
+			}
+		}
+		tcp_listen: {
+			common:      false
+			description: #"Options for the "tcp_listen" metrics collector."#
+			required:    false
+			type: object: options: {
+				ports: {
+					common:      false
+					required:    false
+					description: "Lists of listening port patterns to include or exclude."
 					type: object: options: {
 						includes: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather usage metrics.
-								Defaults to including all devices.
+								The list of listening port patterns for which to gather metrics.
+								Defaults to including all ports.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: ["*"]
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["80", "8*"]
 									syntax: "literal"
 								}
 							}
@@ -192,37 +513,44 @@ components: sources: host_metrics: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather usage metrics.
-								Defaults to excluding no devices.
+								The list of listening port patterns for which to gather metrics.
+								Defaults to excluding no ports.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: []
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["22"]
 									syntax: "literal"
 								}
 							}
 						}
 					}
 				}
-				filesystems: {
+			}
+		}
+		listen_sockets: {
+			common:      false
+			description: #"Options for the "listen_sockets" metrics collector."#
+			required:    false
+			type: object: options: {
+				ports: {
 					common:      false
 					required:    false
-					description: "Lists of filesystem name patterns to include or exclude."
+					description: "Lists of listening port patterns to include or exclude."
 					type: object: options: {
 						includes: {
 							required: false
 							common:   false
 							description: """
-								The list of filesystem name patterns for which to gather usage metrics.
-								Defaults to including all filesystems.
+								The list of listening port patterns for which to gather metrics.
+								Defaults to including all ports.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: ["*"]
 								items: type: string: {
-									examples: ["ntfs", "ext*"]
+									examples: ["80", "8*"]
 									syntax: "literal"
 								}
 							}
@@ -231,37 +559,44 @@ components: sources: host_metrics: {
 							required: false
 							common:   false
 							description: """
-								The list of filesystem name patterns for which to gather usage metrics.
-								Defaults to excluding no filesystems.
+								The list of listening port patterns for which to gather metrics.
+								Defaults to excluding no ports.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: []
 								items: type: string: {
-									examples: ["ntfs", "ext*"]
+									examples: ["22"]
 									syntax: "literal"
 								}
 							}
 						}
 					}
 				}
-				mountpoints: {
+			}
+		}
+		buddyinfo: {
+			common:      false
+			description: #"Options for the "buddyinfo" metrics collector."#
+			required:    false
+			type: object: options: {
+				nodes: {
 					common:      false
 					required:    false
-					description: "Lists of mount point path patterns to include or exclude."
+					description: "Lists of NUMA node patterns to include or exclude."
 					type: object: options: {
 						includes: {
 							required: false
 							common:   false
 							description: """
-								The list of mount point path patterns for which to gather usage metrics.
-								Defaults to including all mount points.
+								The list of NUMA node patterns for which to gather metrics.
+								Defaults to including all NUMA nodes.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: ["*"]
 								items: type: string: {
-									examples: ["/home", "/raid*"]
+									examples: ["0"]
 									syntax: "literal"
 								}
 							}
@@ -270,14 +605,53 @@ components: sources: host_metrics: {
 							required: false
 							common:   false
 							description: """
-								The list of mount point path patterns for which to gather usage metrics.
-								Defaults to excluding no mount points.
+								The list of NUMA node patterns for which to gather metrics.
+								Defaults to excluding no NUMA nodes.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: []
 								items: type: string: {
-									examples: ["/home", "/raid*"]
+									examples: ["1"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				zones: {
+					common:      false
+					required:    false
+					description: "Lists of zone patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of zone patterns for which to gather metrics.
+								Defaults to including all zones.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["Normal"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of zone patterns for which to gather metrics.
+								Defaults to excluding no zones.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: {
+									examples: ["DMA", "DMA32"]
 									syntax: "literal"
 								}
 							}
@@ -286,28 +660,28 @@ components: sources: host_metrics: {
 				}
 			}
 		}
-		network: {
+		softirqs: {
 			common:      false
-			description: #"Options for the "network" metrics collector."#
+			description: #"Options for the "softirqs" metrics collector."#
 			required:    false
 			type: object: options: {
-				devices: {
+				types: {
 					common:      false
 					required:    false
-					description: "Lists of device name patterns to include or exclude."
+					description: "Lists of softirq type patterns to include or exclude."
 					type: object: options: {
 						includes: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather network utilization metrics.
-								Defaults to including all devices.
+								The list of softirq type patterns for which to gather metrics.
+								Defaults to including all types.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: ["*"]
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["NET_RX", "NET_TX"]
 									syntax: "literal"
 								}
 							}
@@ -316,14 +690,14 @@ components: sources: host_metrics: {
 							required: false
 							common:   false
 							description: """
-								The list of device name patterns for which to gather network utilization metrics.
-								Defaults to excluding no devices.
+								The list of softirq type patterns for which to gather metrics.
+								Defaults to excluding no types.
 								The patterns are matched using [globbing](#globbing).
 								"""
 							type: array: {
 								default: []
 								items: type: string: {
-									examples: ["sda", "dm-*"]
+									examples: ["HI", "IRQ_POLL"]
 									syntax: "literal"
 								}
 							}
@@ -332,90 +706,1198 @@ components: sources: host_metrics: {
 				}
 			}
 		}
-	}
-
-	output: metrics: {
-		_host_metrics_tags: {
-			collector: {
-				description: "Which collector this metric comes from."
-				required:    true
-			}
-			host: {
-				description: "The hostname of the originating system."
-				required:    true
-				examples: [_values.local_host]
-			}
-		}
-
-		// Host CPU
-		host_cpu_seconds_total: _host & {
-			description: "The number of CPU seconds accumulated in different operating modes."
-			type:        "counter"
-			tags:        _host_metrics_tags & {
-				collector: examples: ["cpu"]
-				cpu: {
-					description: "The index of the CPU core or socket."
-					required:    true
-					examples: ["1"]
-				}
-				mode: {
-					description: "Which mode the CPU was running in during the given time."
-					required:    true
-					examples: ["idle", "system", "user", "nice"]
+		container: {
+			common:      false
+			description: #"Options for the "container" metrics collector."#
+			required:    false
+			type: object: options: {
+				docker_host: {
+					common:      false
+					required:    false
+					description: "The Docker daemon address to connect to, such as `unix:///var/run/docker.sock` or `http://127.0.0.1:2375`. Defaults to the `DOCKER_HOST` environment variable, or the local socket."
+					type: string: {
+						default: null
+						examples: ["unix:///var/run/docker.sock"]
+						syntax: "literal"
+					}
+				}
+				tls: {
+					common:      false
+					required:    false
+					description: "The TLS certificates for connecting to a Docker daemon over HTTPS, as for the `docker_logs` source."
+					type: object: options: {
+						ca_file: {
+							required:    true
+							description: "Path to the CA certificate file."
+							type: string: {
+								examples: ["certs/ca.pem"]
+								syntax: "literal"
+							}
+						}
+						crt_file: {
+							required:    true
+							description: "Path to the TLS certificate file."
+							type: string: {
+								examples: ["certs/cert.pem"]
+								syntax: "literal"
+							}
+						}
+						key_file: {
+							required:    true
+							description: "Path to the TLS key file."
+							type: string: {
+								examples: ["certs/key.pem"]
+								syntax: "literal"
+							}
+						}
+					}
+				}
+				names: {
+					common:      false
+					required:    false
+					description: "Lists of container name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of container name patterns for which to gather metrics.
+								Defaults to including all containers.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["web*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of container name patterns for which to gather metrics.
+								Defaults to excluding no containers.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: {
+									examples: ["*-sidecar"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+		kernel_messages: {
+			common:      false
+			description: #"Options for the "kernel_messages" metrics collector."#
+			required:    false
+			type: object: options: {
+				by_severity: {
+					common:      false
+					required:    false
+					description: "Also report the number of messages still in the kernel ring buffer by severity."
+					type: bool: default: false
+				}
+			}
+		}
+		updates: {
+			common:      false
+			description: #"Options for the "updates" metrics collector."#
+			required:    false
+			type: object: options: {
+				reboot_required_path: {
+					common:      false
+					required:    false
+					description: "The file whose existence flags that a reboot is required, as created by the package manager on Debian and derivatives."
+					type: string: {
+						default: "/var/run/reboot-required"
+						syntax:  "literal"
+					}
+				}
+				updates_available_path: {
+					common:      false
+					required:    false
+					description: "The summary of the pending updates written by update-notifier."
+					type: string: {
+						default: "/var/lib/update-notifier/updates-available"
+						syntax:  "literal"
+					}
+				}
+				kernel_modules_path: {
+					common:      false
+					required:    false
+					description: "The directory holding the modules of the installed kernels. Without the `reboot_required_path` file, a reboot is reported as required if the running kernel is not the newest installed one of the same flavour, such as `-generic`, compared by the version in their releases."
+					type: string: {
+						default: "/lib/modules"
+						syntax:  "literal"
+					}
+				}
+			}
+		}
+		zfs: {
+			common:      false
+			description: #"Options for the "zfs" metrics collector."#
+			required:    false
+			type: object: options: {
+				pools: {
+					common:      false
+					required:    false
+					description: "Lists of pool name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of pool name patterns for which to gather metrics.
+								Defaults to including all pools.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["tank", "rpool*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of pool name patterns for which to gather metrics.
+								Defaults to excluding no pools.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: {
+									examples: ["backup*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+		cloud_metadata: {
+			common:      false
+			description: "Options for tagging the metrics with the metadata of the cloud instance Vector runs on. Requires the `sources-host_metrics-cloud_metadata` feature."
+			required:    false
+			type: object: options: {
+				enabled: {
+					common:      false
+					required:    false
+					description: "Query the instance metadata endpoint once when the source starts, and tag every metric with `instance_id`, `instance_type` and `availability_zone`. Off the cloud, no tags are added."
+					type: bool: default: false
+				}
+				provider: {
+					common:      false
+					required:    false
+					description: "The cloud provider to query. By default, each provider is tried in turn."
+					type: string: {
+						default: null
+						enum: {
+							aws:   "Amazon EC2, through IMDSv2."
+							gcp:   "Google Compute Engine."
+							azure: "Azure virtual machines."
+						}
+						syntax: "literal"
+					}
+				}
+				endpoint: {
+					common:      false
+					required:    false
+					description: "Overrides the metadata endpoint of the provider."
+					type: string: {
+						default: null
+						examples: ["http://169.254.169.254"]
+						syntax: "literal"
+					}
+				}
+			}
+		}
+		summary: {
+			common:      false
+			description: "Options for summarizing gauges over several samples taken within each scrape interval."
+			required:    false
+			type: object: options: {
+				collectors: {
+					common:      false
+					required:    false
+					description: "The list of collectors whose gauges are summarized. For each gauge, `<name>_min`, `<name>_max`, and `<name>_avg` gauges are emitted in addition to the regular sample."
+					type: array: {
+						default: []
+						items: type: string: {
+							examples: ["load", "memory"]
+							syntax: "literal"
+						}
+					}
+				}
+				samples: {
+					common:      false
+					required:    false
+					description: "The number of samples taken within each scrape interval for the summarized collectors, which must not split it into periods shorter than a nanosecond."
+					type: uint: {
+						default: 1
+						unit:    null
+					}
+				}
+			}
+		}
+		inventory: {
+			common:      false
+			description: "Options for emitting metrics which rarely change, such as `host_info`, less often to save ingest."
+			required:    false
+			type: object: options: {
+				every: {
+					common:      false
+					required:    false
+					description: "Emit the inventory metrics only on the first scrape and on every `every`th scrape after it. By default they are emitted on every scrape."
+					type: uint: {
+						default: null
+						examples: [20]
+						unit: null
+					}
+				}
+				metrics: {
+					common:      false
+					required:    false
+					description: "The names of the inventory metrics. Other metrics are emitted on every scrape."
+					type: array: {
+						default: ["host_info", "boot_time", "memory_total_bytes", "memory_swap_total_bytes", "disk_size_bytes", "disk_sector_size_bytes", "filesystem_total_bytes"]
+						items: type: string: {
+							examples: ["host_info"]
+							syntax: "literal"
+						}
+					}
+				}
+			}
+		}
+		smoothing: {
+			common:      false
+			description: "Options for emitting an exponentially weighted moving average of noisy gauges, for more stable alerting."
+			required:    false
+			type: object: options: {
+				metrics: {
+					common:      false
+					required:    false
+					description: "The names of the gauges to smooth. For each, a `<name>_smoothed` gauge is emitted in addition to the raw value, starting out at the first value seen."
+					type: array: {
+						default: []
+						items: type: string: {
+							examples: ["load1", "memory_available_bytes"]
+							syntax: "literal"
+						}
+					}
+				}
+				factor: {
+					common:      false
+					required:    false
+					description: "The weight of the latest value in the average, greater than 0 and at most 1. Lower values smooth more."
+					type: float: default: 0.3
+				}
+			}
+		}
+		cpu: {
+			common:      false
+			description: #"Options for the "cpu" metrics collector."#
+			required:    false
+			type: object: options: {
+				include_offline: {
+					common:      false
+					required:    false
+					description: "Also report the CPUs taken offline through hotplug, whose times are stale, and tag every CPU with `online`. By default, offline CPUs are skipped. Offline CPUs are only detected on Linux."
+					type: bool: default: false
+				}
+				saturation: {
+					common:      false
+					required:    false
+					description: "Also report `cpu_saturation_ratio`, a single signal of how saturated the CPUs are, for autoscalers."
+					type: bool: default: false
+				}
+				percentiles: {
+					common:      false
+					required:    false
+					description: "Report the median, 95th percentile and maximum of the CPU times across the CPUs for each mode, as `cpu_seconds_p50`, `cpu_seconds_p95` and `cpu_seconds_max`, instead of `cpu_seconds_total` for every CPU. Keeps the number of series independent of the number of CPUs on machines with many cores, while still showing hot cores."
+					type: bool: default: false
+				}
+			}
+		}
+		disk: {
+			common:      false
+			description: #"Options for the "disk" and "block_device" metrics collectors."#
+			required:    false
+			type: object: options: {
+				devices: {
+					common:      false
+					required:    false
+					description: "Lists of device name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather I/O utilization metrics.
+								Defaults to including all devices.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather I/O utilization metrics.
+								Defaults to excluding no devices.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				resolve_dm_names: {
+					common:      false
+					required:    false
+					description: "Add a `name` tag with the device-mapper name (such as `vg-lv` for LVM volumes) to `dm-*` devices. Linux only."
+					type: bool: default: false
+				}
+				serial_tags: {
+					common:      false
+					required:    false
+					description: "Add a `serial` tag with the serial number of the drive, which stays the same across reboots unlike the device name. Read from sysfs for NVMe and virtio drives, and otherwise from the udev database. Left out where unavailable. Linux only."
+					type: bool: default: false
+				}
+			}
+		}
+		filesystem: {
+			common:      false
+			description: #"Options for the "filesystem" metrics collector."#
+			required:    false
+			type: object: options: {
+				devices: {
+					common:      false
+					required:    false
+					description: "Lists of device name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather usage metrics.
+								Defaults to including all devices.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather usage metrics.
+								Defaults to excluding no devices.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: []
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				filesystems: {
+					common:      false
+					required:    false
+					description: "Lists of filesystem name patterns to include or exclude. Setting this option replaces the default exclusions."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of filesystem name patterns for which to gather usage metrics.
+								Defaults to including all filesystems.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["ntfs", "ext*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of filesystem name patterns for which to gather usage metrics.
+								Defaults to excluding pseudo and container filesystems, like the Prometheus node exporter.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts", "devtmpfs", "fusectl", "hugetlbfs", "iso9660", "mqueue", "nsfs", "overlay", "proc", "procfs", "pstore", "rpc_pipefs", "securityfs", "selinuxfs", "squashfs", "sysfs", "tracefs"]
+								items: type: string: {
+									examples: ["ntfs", "ext*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				mountpoints: {
+					common:      false
+					required:    false
+					description: "Lists of mount point path patterns to include or exclude. Setting this option replaces the default exclusions."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of mount point path patterns for which to gather usage metrics.
+								Defaults to including all mount points.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["/home", "/raid*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of mount point path patterns for which to gather usage metrics.
+								Defaults to excluding kernel and container runtime mount points, like the Prometheus node exporter.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["/dev", "/dev/*", "/proc", "/proc/*", "/sys", "/sys/*", "/run/credentials/*", "/var/lib/docker/*", "/var/lib/containers/storage/*"]
+								items: type: string: {
+									examples: ["/home", "/raid*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				near_full_threshold: {
+					common:      false
+					required:    false
+					description: "The used ratio above which `filesystem_near_full` reports a filesystem as near full."
+					type: float: default: 0.9
+				}
+				classes: {
+					common:      false
+					required:    false
+					description: "Groups mounts into classes by path prefix, reported in the `class` tag of the filesystem metrics and of the disk metrics of mounted devices. A mount belongs to the class with the longest prefix containing it, the first listed on ties, and to `other` if none does. Prefixes match whole path components, so `/data` doesn't match `/database`."
+					type: object: {
+						examples: [{"os": ["/", "/boot"], "data": ["/data", "/srv"], "tmp": ["/tmp"]}]
+						options: {}
+					}
+				}
+				enumeration_timeout_secs: {
+					common:      false
+					required:    false
+					description: "How long enumerating the mounted filesystems may take before it is cut short, keeping the filesystems found until then. Guards against automounts blocking the scrape. Defaults to `scrape_interval_secs`."
+					type: uint: {
+						default: null
+						unit:    "seconds"
+					}
+				}
+				errors: {
+					common:      false
+					required:    false
+					description: "Also emit the number of errors detected by each filesystem as `filesystem_errors_total`, read from `/sys/fs/ext4/<device>/errors_count`. Only ext4 counts its errors, other filesystems are left out."
+					type: bool: default: false
+				}
+				min_size_bytes: {
+					common:      false
+					required:    false
+					description: "Leave out mounted filesystems smaller than this many bytes, such as boot and EFI system partitions. Doesn't apply to `paths`."
+					type: uint: {
+						default: 0
+						unit:    "bytes"
+					}
+				}
+				mount_source: {
+					common:      false
+					required:    false
+					description: "Where the mounted filesystems are enumerated from."
+					type: string: {
+						default: "partitions"
+						enum: {
+							partitions: "The partitions as listed by the operating system."
+							mountinfo:  "Every mount listed in `/proc/self/mountinfo`, including bind mounts, which some minimal containers leave out of the partitions. Linux only, other platforms use the partitions."
+						}
+						syntax: "literal"
+					}
+				}
+				normalize_types: {
+					common:      false
+					required:    false
+					description: "Report FUSE and overlay filesystems under the type of the filesystem they implement, for example `fuseblk.ntfs-3g` as `ntfs`, keeping the reported type in the `raw_fstype` tag. The `filesystems` filter matches the normalized type."
+					type: bool: default: false
+				}
+				paths: {
+					common:      false
+					required:    false
+					description: "Report the usage of the filesystems containing these paths, tagged with the path and the resolved mount point, instead of enumerating every mounted filesystem. The `devices`, `filesystems` and `mountpoints` filters don't apply to them."
+					type: array: {
+						default: []
+						items: type: string: {
+							examples: ["/var/lib/postgresql", "/"]
+							syntax: "literal"
+						}
+					}
+				}
+			}
+		}
+		memory: {
+			common:      false
+			description: #"Options for the "memory" metrics collector."#
+			required:    false
+			type: object: options: {
+				free_compatible: {
+					common:      false
+					required:    false
+					description: "Compute `memory_used_bytes` as `free` reports it, counting reclaimable slab memory as cache, rather than as the memory neither free nor used for buffers or the page cache. Linux only."
+					type: bool: default: false
+				}
+			}
+		}
+		network: {
+			common:      false
+			description: #"Options for the "network" metrics collector."#
+			required:    false
+			type: object: options: {
+				devices: {
+					common:      false
+					required:    false
+					description: "Lists of device name patterns to include or exclude."
+					type: object: options: {
+						includes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather network utilization metrics.
+								Defaults to including all devices.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["*"]
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+						excludes: {
+							required: false
+							common:   false
+							description: """
+								The list of device name patterns for which to gather network utilization metrics.
+								Defaults to excluding the loopback and virtual interfaces, unless `exclude_virtual` is disabled.
+								The patterns are matched using [globbing](#globbing).
+								"""
+							type: array: {
+								default: ["lo", "br-*", "cali*", "cni*", "docker*", "flannel*", "veth*", "virbr*", "vnet*"]
+								items: type: string: {
+									examples: ["sda", "dm-*"]
+									syntax: "literal"
+								}
+							}
+						}
+					}
+				}
+				exclude_virtual: {
+					common:      false
+					required:    false
+					description: "Exclude the loopback interface and the virtual interfaces of bridges, container runtimes and hypervisors, such as `docker0` and `veth*`, when `devices` is not set."
+					type: bool: default: true
+				}
+				aliases: {
+					common:      false
+					required:    false
+					description: "Aliases of network interfaces by interface name, such as `uplink` for `enp3s0f1`, reported in the `alias` tag of the network metrics. Interfaces without an alias are left untagged."
+					type: object: {
+						examples: [{enp3s0f1: "uplink", enp3s0f2: "storage"}]
+						options: {}
+					}
+				}
+				alias_as_device: {
+					common:      false
+					required:    false
+					description: "Report the aliases configured in `aliases` in the `device` tag, replacing the interface names, instead of in a separate `alias` tag. Interfaces without an alias keep their name."
+					type: bool: default: false
+				}
+				incremental: {
+					common:      false
+					required:    false
+					description: "Emit the network counters as increments since the previous scrape instead of as cumulative values. The first scrape after Vector starts emits increments of zero rather than everything counted since boot, and counters which went backwards, such as those of recreated interfaces, increment by their current value. No state is kept across restarts."
+					type: bool: default: false
+				}
+				split_family: {
+					common:      false
+					required:    false
+					description: "Tag the byte and packet counters of the interfaces with the protocol `family` they count. The interface counters don't distinguish protocols and are tagged `all`, while on Linux the IPv6 traffic of each interface is also reported, tagged `ipv6`."
+					type: bool: default: false
+				}
+			}
+		}
+	}
+
+	output: metrics: {
+		_host_metrics_tags: {
+			collector: {
+				description: "Which collector this metric comes from."
+				required:    true
+			}
+			host: {
+				description: "The hostname of the originating system."
+				required:    true
+				examples: [_values.local_host]
+			}
+			instance_id: {
+				description: "The ID of the cloud instance, when `cloud_metadata.enabled` is set."
+				required:    false
+				examples: ["i-0123456789abcdef0"]
+			}
+			instance_type: {
+				description: "The type of the cloud instance, when `cloud_metadata.enabled` is set."
+				required:    false
+				examples: ["m5.large", "n1-standard-1"]
+			}
+			availability_zone: {
+				description: "The availability zone of the cloud instance, when `cloud_metadata.enabled` is set."
+				required:    false
+				examples: ["us-east-1b"]
+			}
+		}
+
+		// Host CPU
+		host_cpu_seconds_total: _host & {
+			description: "The number of CPU seconds accumulated in different operating modes."
+			type:        "counter"
+			tags:        _host_metrics_tags & {
+				collector: examples: ["cpu"]
+				cpu: {
+					description: "The index of the CPU core or socket."
+					required:    true
+					examples: ["1"]
+				}
+				mode: {
+					description: "Which mode the CPU was running in during the given time."
+					required:    true
+					examples: ["idle", "system", "user", "nice"]
+				}
+				online: {
+					description: "Whether the CPU is online. Only set when `cpu.include_offline` is enabled."
+					required:    false
+					examples: ["true", "false"]
+				}
+			}
+		}
+		cpu_saturation_ratio: _host & {
+			description:   "How saturated the CPUs are, from 0 to 1, if `cpu.saturation` is enabled. Computed as `max(min(load1 / logical CPUs, 1), cpu pressure avg10 / 100)`: the larger of the one-minute load average per logical CPU, capped at 1, and the share of the last 10 seconds in which some tasks waited for a CPU, from `/proc/pressure/cpu`. Falls back to the load per CPU where pressure stall information is unavailable."
+			type:          "gauge"
+			relevant_when: "OS is UNIX"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["cpu"]
+			}
+		}
+		cpu_seconds_p50: _host & _cpu_percentile & {description: "The median across the CPUs of the number of CPU seconds accumulated in the mode."}
+		cpu_seconds_p95: _host & _cpu_percentile & {description: "The 95th percentile across the CPUs of the number of CPU seconds accumulated in the mode."}
+		cpu_seconds_max: _host & _cpu_percentile & {description: "The largest number of CPU seconds accumulated in the mode by any CPU."}
+
+		// Host disk
+		disk_read_bytes_total:       _host & _disk_counter & {description: "The accumulated number of bytes read in."}
+		disk_reads_completed_total:  _host & _disk_counter & {description: "The accumulated number of read operations completed."}
+		disk_written_bytes_total:    _host & _disk_counter & {description: "The accumulated number of bytes written out."}
+		disk_writes_completed_total: _host & _disk_counter & {description: "The accumulated number of write operations completed."}
+		disk_errors_total: _host & _disk_counter & {
+			description:   "The accumulated number of failed I/O requests, for disks whose driver exposes it."
+			relevant_when: "OS is Linux"
+		}
+		disk_readonly: _host & _disk_gauge & {
+			description:   "Whether the block device is read-only (1) or not (0)."
+			relevant_when: "OS is Linux"
+		}
+		disk_removable: _host & _disk_gauge & {
+			description:   "Whether the block device is removable (1) or not (0), such as USB drives and optical drives. Not reported for partitions."
+			relevant_when: "OS is Linux"
+		}
+
+		// Block devices
+		disk_size_bytes:        _host & _block_device & {description: "The capacity of the block device in bytes."}
+		disk_sector_size_bytes: _host & _block_device & {description: "The hardware sector size of the block device in bytes."}
+
+		// Host filesystem
+		filesystem_free_bytes:  _host & _filesystem_bytes & {description: "The number of bytes free on the named filesystem."}
+		filesystem_total_bytes: _host & _filesystem_bytes & {description: "The total number of bytes in the named filesystem."}
+		filesystem_used_bytes:  _host & _filesystem_bytes & {description: "The number of bytes used on the named filesystem."}
+		filesystem_used_ratio:  _host & _filesystem_bytes & {description: "The ratio between used and total bytes on the named filesystem."}
+		filesystem_near_full: _host & _filesystem_bytes & {description: "Whether the ratio between used and total bytes on the named filesystem exceeds `filesystem.near_full_threshold` (1) or not (0)."}
+		filesystem_readonly: _host & _filesystem_bytes & {
+			description:   "Whether the filesystem is mounted read-only (1) or not (0), such as after being remounted due to errors."
+			relevant_when: "OS is Linux"
+		}
+		filesystem_stat_duration_seconds: _host & _filesystem_bytes & {
+			description:   "The time taken to load the usage of the filesystem, which grows on degraded mounts such as unresponsive network filesystems."
+			relevant_when: "`filesystem.paths` is not configured"
+		}
+		filesystem_errors_total: _host & _filesystem_bytes & {
+			description:   "The number of errors the filesystem has detected since it was created, such as corrupted metadata. Only reported for ext4."
+			type:          "counter"
+			relevant_when: "OS is Linux and `filesystem.errors` is enabled"
+		}
+
+		// Host load
+		load1:           _host & _loadavg & {description: "System load averaged over the last 1 minute."}
+		load5:           _host & _loadavg & {description: "System load averaged over the last 5 minutes."}
+		load15:          _host & _loadavg & {description: "System load averaged over the last 15 minutes."}
+		load1_per_core:  _host & _loadavg & {description: "`load1` divided by the number of logical CPUs, if `load_per_core` is enabled."}
+		load5_per_core:  _host & _loadavg & {description: "`load5` divided by the number of logical CPUs, if `load_per_core` is enabled."}
+		load15_per_core: _host & _loadavg & {description: "`load15` divided by the number of logical CPUs, if `load_per_core` is enabled."}
+
+		// Host time
+		uptime: _host & _host_metric & {
+			description: "The number of seconds since the last boot."
+			tags: boot_time: {
+				description: "The time of the last boot as an ISO 8601 timestamp, if `boot_time_tag` is enabled."
+				required:    false
+				examples: ["2021-09-01T08:30:00Z"]
+			}
+		}
+		boot_time: _host & _host_metric & {description: "The UNIX timestamp of the last boot."}
+		host_info: _host & _host_metric & {
+			description: "Always `1`, labeled with the kernel and operating system of the host."
+			tags: {
+				kernel: {
+					description: "The kernel release."
+					required:    true
+					examples: ["5.10.0-8-amd64"]
+				}
+				os: {
+					description: "The operating system name."
+					required:    true
+					examples: ["Linux", "Darwin", "Windows"]
+				}
+				os_version: {
+					description: "The operating system version string."
+					required:    true
+					examples: ["#1 SMP Debian 5.10.46-4 (2021-08-03)"]
+				}
+				arch: {
+					description: "The CPU architecture."
+					required:    true
+					examples: ["x86_64", "aarch64"]
+				}
+			}
+		}
+		random_entropy_available_bits: _host & _host_metric & {
+			description:   "The number of bits of entropy credited to the kernel random number generator, fixed at the pool size since Linux 5.18."
+			relevant_when: "OS is Linux"
+		}
+		random_pool_size_bits: _host & _host_metric & {
+			description:   "The size of the entropy pool of the kernel random number generator in bits."
+			relevant_when: "OS is Linux"
+		}
+		random_urandom_min_reseed_seconds: _host & _host_metric & {
+			description:   "The minimum number of seconds between reseeds of the urandom pool. Not reported since Linux 5.18."
+			relevant_when: "OS is Linux"
+		}
+
+		// Host memory
+		memory_active_bytes:           _host & _memory_gauge & _memory_nowin & {description: "The number of bytes of active main memory."}
+		memory_available_bytes:        _host & _memory_gauge & {description:                 "The number of bytes of main memory available."}
+		memory_buffers_bytes:          _host & _memory_linux & {description:                 "The number of bytes of main memory used by buffers."}
+		memory_cached_bytes:           _host & _memory_linux & {description:                 "The number of bytes of main memory used by cached blocks."}
+		memory_free_bytes:             _host & _memory_gauge & {description:                 "The number of bytes of main memory not used."}
+		memory_inactive_bytes:         _host & _memory_macos & {description:                 "The number of bytes of main memory that is not active."}
+		memory_pressure_ratio:         _host & _memory_linux & {description:                 "The share of the last 10 seconds in which some tasks were stalled waiting for memory, from the `avg10` of `/proc/pressure/memory`. Only emitted on kernels with pressure stall information enabled."}
+		memory_shared_bytes:           _host & _memory_linux & {description:                 "The number of bytes of main memory shared between processes."}
+		memory_swap_free_bytes:        _host & _memory_gauge & {description:                 "The number of free bytes of swap space."}
+		memory_swapped_in_bytes_total: _host & _memory_counter & _memory_nowin & {
+			description: "The number of bytes that have been swapped in to main memory."
+		}
+		memory_swapped_out_bytes_total: _host & _memory_counter & _memory_nowin & {
+			description: "The number of bytes that have been swapped out from main memory."
+		}
+		memory_swap_total_bytes: _host & _memory_gauge & {description: "The total number of bytes of swap space."}
+		memory_swap_used_bytes:  _host & _memory_gauge & {description: "The number of used bytes of swap space."}
+		memory_total_bytes:      _host & _memory_gauge & {description: "The total number of bytes of main memory."}
+		memory_used_bytes:       _host & _memory_linux & {description: "The number of bytes of main memory used by programs or caches."}
+		memory_wired_bytes:      _host & _memory_macos & {description: "The number of wired bytes of main memory."}
+		swap_device_total_bytes: _host & _swap_device & {description: "The size of the swap partition or file in bytes."}
+		swap_device_used_bytes:  _host & _swap_device & {description: "The number of bytes used on the swap partition or file."}
+
+		// Host network
+		network_receive_bytes_total:         _host & _network_gauge & _network_family & {description: "The number of bytes received on this interface."}
+		network_receive_errs_total:          _host & _network_gauge & {description: "The number of errors encountered during receives on this interface."}
+		network_receive_packets_total:       _host & _network_gauge & _network_family & {description: "The number of packets received on this interface."}
+		network_receive_compressed_total:    _host & _network_linux & {description: "The number of compressed packets received on this interface."}
+		network_receive_multicast_total:     _host & _network_linux & {description: "The number of multicast packets received on this interface."}
+		network_transmit_compressed_total:   _host & _network_linux & {description: "The number of compressed packets transmitted on this interface."}
+		network_carrier_changes_total:       _host & _network_linux & {description: "The number of times the link of this interface went up or down. Not reported by some virtual interfaces."}
+		network_mtu_bytes:                   _host & _network_linux & {description: "The maximum transmission unit of this interface, the largest packet it sends without fragmenting it."}
+		network_tx_queue_length:             _host & _network_linux & {description: "The maximum number of packets queued for transmission on this interface by the driver."}
+		network_transmit_bytes_total:        _host & _network_gauge & _network_family & {description: "The number of bytes transmitted on this interface."}
+		network_transmit_errs_total:         _host & _network_gauge & {description: "The number of errors encountered during transmits on this interface."}
+		network_transmit_packets_drop_total: _host & _network_nomac & {description: "The number of packets dropped during transmits on this interface."}
+		network_transmit_packets_total:      _host & _network_nomac & _network_family & {description: "The number of packets transmitted on this interface."}
+		tcp_mem_pressure_low:                _host & _tcp_mem & {description: "The number of pages allocated to TCP below which TCP doesn't regulate its memory allocation, from `/proc/sys/net/ipv4/tcp_mem`."}
+		tcp_mem_pressure:                    _host & _tcp_mem & {description: "The number of pages allocated to TCP above which TCP moderates its memory consumption."}
+		tcp_mem_max:                         _host & _tcp_mem & {description: "The number of pages TCP may allocate at most for its sockets."}
+		tcp_mem_allocated:                   _host & _tcp_mem & {description: "The number of pages currently allocated to TCP sockets."}
+
+		// Network queueing disciplines
+		network_qdisc_backlog_bytes: _host & _qdisc & {
+			description: "The number of bytes queued in the queueing discipline."
+			type:        "gauge"
+		}
+		network_qdisc_drops_total: _host & _qdisc & {
+			description: "The number of packets dropped by the queueing discipline."
+			type:        "counter"
+		}
+
+		// ZFS
+		zfs_arc_size_bytes: _host & _zfs & {
+			description: "The current size of the ZFS adaptive replacement cache."
+			type:        "gauge"
+		}
+		zfs_pool_health: _host & _zfs_pool & {
+			description: "Always 1, with the current health of the pool in the `health` tag."
+			tags: health: {
+				description: "The health of the pool."
+				required:    true
+				examples: ["ONLINE", "DEGRADED", "FAULTED"]
+			}
+		}
+		zfs_pool_allocated_bytes: _host & _zfs_pool & {description: "The number of bytes allocated in the pool."}
+		zfs_pool_free_bytes:      _host & _zfs_pool & {description: "The number of bytes free in the pool."}
+
+		// Vector process
+		vector_process_cpu_seconds_total: _host & _vector_process & {
+			description: "The CPU time spent by the Vector process, in user and system mode."
+			type:        "counter"
+		}
+		vector_process_resident_memory_bytes: _host & _vector_process & {
+			description: "The resident memory size of the Vector process."
+			type:        "gauge"
+		}
+		vector_process_threads: _host & _vector_process & {
+			description: "The number of threads of the Vector process."
+			type:        "gauge"
+		}
+		vector_process_open_fds: _host & _vector_process & {
+			description: "The number of open file descriptors of the Vector process."
+			type:        "gauge"
+		}
+
+		// Listening sockets
+		listen_socket: _host & {
+			description:   "Always 1, for every port a TCP or UDP socket is listening on."
+			type:          "gauge"
+			relevant_when: "OS is Linux"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["listen_sockets"]
+				proto: {
+					description: "The transport protocol of the socket."
+					required:    true
+					examples: ["tcp", "udp"]
+				}
+				port: {
+					description: "The local port of the socket."
+					required:    true
+					examples: ["22"]
+				}
+				pid: {
+					description: "The ID of the process owning the socket, if it can be inspected."
+					required:    false
+					examples: ["812"]
+				}
+				process: {
+					description: "The name of the process owning the socket, if it can be inspected."
+					required:    false
+					examples: ["sshd"]
+				}
+			}
+		}
+
+		// Containers
+		container_cpu_seconds_total: _host & _container & {
+			description: "The CPU time spent by the container, in user and system mode."
+			type:        "counter"
+		}
+		container_memory_usage_bytes: _host & _container & {
+			description: "The memory usage of the container."
+			type:        "gauge"
+		}
+
+		// Buddy allocator
+		memory_free_blocks: _host & {
+			description:   "The number of free blocks of 2^order contiguous pages. Few free blocks of higher orders indicate fragmented memory."
+			type:          "gauge"
+			relevant_when: "OS is Linux"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["buddyinfo"]
+				node: {
+					description: "The NUMA node of the zone."
+					required:    true
+					examples: ["0"]
+				}
+				zone: {
+					description: "The memory zone."
+					required:    true
+					examples: ["DMA32", "Normal"]
+				}
+				order: {
+					description: "The order of the blocks, each consisting of 2^order pages."
+					required:    true
+					examples: ["0", "10"]
+				}
+			}
+		}
+
+		// Kernel messages
+		kernel_messages_total: _host & _kernel_messages & {
+			description: "The number of messages logged to the kernel ring buffer since boot. Reading them requires the `CAP_SYSLOG` capability when `kernel.dmesg_restrict` is set, without which nothing is reported."
+			type:        "counter"
+		}
+		kernel_buffered_messages: _host & _kernel_messages & {
+			description: "The number of messages still in the kernel ring buffer, if `kernel_messages.by_severity` is enabled."
+			type:        "gauge"
+			tags: severity: {
+				description: "The syslog severity of the messages."
+				required:    true
+				examples: ["err", "warning", "info"]
+			}
+		}
+
+		// Updates
+		reboot_required: _host & _updates & {
+			description: "Whether the system needs a reboot to apply updates, 1 if so and 0 otherwise. Not reported if it can't be determined."
+		}
+		pending_updates: _host & _updates & {
+			description: "The number of pending package updates, as counted by update-notifier. Not reported where it isn't available."
+		}
+
+		// CPU idle states
+		cpu_cstate_seconds_total: _host & {
+			description:   "The time the CPU spent in the idle state. Not reported without a cpuidle driver, as in most virtual machines."
+			type:          "counter"
+			relevant_when: "OS is Linux"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["cpuidle"]
+				cpu: {
+					description: "The index of the CPU core or socket."
+					required:    true
+					examples: ["1"]
+				}
+				state: {
+					description: "The name of the idle state."
+					required:    true
+					examples: ["POLL", "C1E", "C6"]
 				}
 			}
 		}
 
-		// Host disk
-		disk_read_bytes_total:       _host & _disk_counter & {description: "The accumulated number of bytes read in."}
-		disk_reads_completed_total:  _host & _disk_counter & {description: "The accumulated number of read operations completed."}
-		disk_written_bytes_total:    _host & _disk_counter & {description: "The accumulated number of bytes written out."}
-		disk_writes_completed_total: _host & _disk_counter & {description: "The accumulated number of write operations completed."}
+		// Resource limits
+		limit_max_open_files: _host & _limits & {
+			description: "The maximum number of files the Vector process may have open, its soft `RLIMIT_NOFILE` limit. This is a limit of the Vector process, not of the host. Not reported if unlimited."
+		}
+		limit_max_processes: _host & _limits & {
+			description: "The maximum number of processes the user running Vector may create, the soft `RLIMIT_NPROC` limit of the Vector process. This is a limit of the Vector process, not of the host. Not reported if unlimited."
+		}
 
-		// Host filesystem
-		filesystem_free_bytes:  _host & _filesystem_bytes & {description: "The number of bytes free on the named filesystem."}
-		filesystem_total_bytes: _host & _filesystem_bytes & {description: "The total number of bytes in the named filesystem."}
-		filesystem_used_bytes:  _host & _filesystem_bytes & {description: "The number of bytes used on the named filesystem."}
-		filesystem_used_ratio:  _host & _filesystem_bytes & {description: "The ratio between used and total bytes on the named filesystem."}
+		// Thermal throttling
+		cpu_throttle_count_total: _host & _thermal_throttle & {
+			description: "The number of times the temperature of the CPU core or package crossed its throttling threshold. Only reported on Intel CPUs."
+		}
+		cpu_thermal_throttle_seconds_total: _host & _thermal_throttle & {
+			description: "The time the CPU core or package spent throttled above its temperature threshold. Only reported on Intel CPUs since Linux 5.7."
+		}
 
-		// Host load
-		load1:  _host & _loadavg & {description: "System load averaged over the last 1 second."}
-		load5:  _host & _loadavg & {description: "System load averaged over the last 5 seconds."}
-		load15: _host & _loadavg & {description: "System load averaged over the last 15 seconds."}
+		// Softirqs
+		softirqs_total: _host & {
+			description:   "The number of softirqs of the type handled by the CPU."
+			type:          "counter"
+			relevant_when: "OS is Linux"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["softirqs"]
+				cpu: {
+					description: "The index of the CPU core or socket."
+					required:    true
+					examples: ["1"]
+				}
+				type: {
+					description: "The type of softirq."
+					required:    true
+					examples: ["NET_RX", "TIMER", "SCHED"]
+				}
+			}
+		}
 
-		// Host time
-		uptime:    _host & _host_metric & {description: "The number of seconds since the last boot."}
-		boot_time: _host & _host_metric & {description: "The UNIX timestamp of the last boot."}
+		// Scheduler
+		schedstat_running_seconds_total: _host & _schedstat & {
+			description: "The time the CPU spent running tasks."
+		}
+		schedstat_waiting_seconds_total: _host & _schedstat & {
+			description: "The time tasks spent runnable but waiting on the run queue of the CPU, the scheduling latency."
+		}
+		schedstat_migrations_total: _host & _schedstat & {
+			description: "The number of tasks the load balancer moved to the CPU from other CPUs of the scheduling domain."
+			tags: domain: {
+				description: "The level of the scheduling domain, from 0 for the CPUs closest to this one, such as its SMT siblings, up to the whole system."
+				required:    true
+				examples: ["0", "1"]
+			}
+		}
 
-		// Host memory
-		memory_active_bytes:           _host & _memory_gauge & _memory_nowin & {description: "The number of bytes of active main memory."}
-		memory_available_bytes:        _host & _memory_gauge & {description:                 "The number of bytes of main memory available."}
-		memory_buffers_bytes:          _host & _memory_linux & {description:                 "The number of bytes of main memory used by buffers."}
-		memory_cached_bytes:           _host & _memory_linux & {description:                 "The number of bytes of main memory used by cached blocks."}
-		memory_free_bytes:             _host & _memory_gauge & {description:                 "The number of bytes of main memory not used."}
-		memory_inactive_bytes:         _host & _memory_macos & {description:                 "The number of bytes of main memory that is not active."}
-		memory_shared_bytes:           _host & _memory_linux & {description:                 "The number of bytes of main memory shared between processes."}
-		memory_swap_free_bytes:        _host & _memory_gauge & {description:                 "The number of free bytes of swap space."}
-		memory_swapped_in_bytes_total: _host & _memory_counter & _memory_nowin & {
-			description: "The number of bytes that have been swapped in to main memory."
+		// Source
+		scrape_interval_seconds: _host & {
+			description: "The configured `scrape_interval_secs`, emitted on every scrape."
+			type:        "gauge"
+			tags: host: _host_metrics_tags.host
 		}
-		memory_swapped_out_bytes_total: _host & _memory_counter & _memory_nowin & {
-			description: "The number of bytes that have been swapped out from main memory."
+		host_metrics_build_info: _host & {
+			description: "Always 1, emitted on every scrape to identify the Vector version and the enabled collectors."
+			type:        "gauge"
+			tags: {
+				host: _host_metrics_tags.host
+				version: {
+					description: "The version of Vector."
+					required:    true
+					examples: ["0.16.0"]
+				}
+				collectors: {
+					description: "The enabled collectors, sorted and separated by commas."
+					required:    true
+					examples: ["cpu,disk,filesystem,host,load,memory,network"]
+				}
+			}
 		}
-		memory_swap_total_bytes: _host & _memory_gauge & {description: "The total number of bytes of swap space."}
-		memory_swap_used_bytes:  _host & _memory_gauge & {description: "The number of used bytes of swap space."}
-		memory_total_bytes:      _host & _memory_gauge & {description: "The total number of bytes of main memory."}
-		memory_used_bytes:       _host & _memory_linux & {description: "The number of bytes of main memory used by programs or caches."}
-		memory_wired_bytes:      _host & _memory_macos & {description: "The number of wired bytes of main memory."}
 
-		// Host network
-		network_receive_bytes_total:         _host & _network_gauge & {description: "The number of bytes received on this interface."}
-		network_receive_errs_total:          _host & _network_gauge & {description: "The number of errors encountered during receives on this interface."}
-		network_receive_packets_total:       _host & _network_gauge & {description: "The number of packets received on this interface."}
-		network_transmit_bytes_total:        _host & _network_gauge & {description: "The number of bytes transmitted on this interface."}
-		network_transmit_errs_total:         _host & _network_gauge & {description: "The number of errors encountered during transmits on this interface."}
-		network_transmit_packets_drop_total: _host & _network_nomac & {description: "The number of packets dropped during transmits on this interface."}
-		network_transmit_packets_total:      _host & _network_nomac & {description: "The number of packets transmitted on this interface."}
+		collected_metrics_count: _host & {
+			description: "The number of metrics a collector collected in the scrape, if `emit_collected_counts` is enabled."
+			type:        "gauge"
+			tags:        _host_metrics_tags
+		}
+
+		// Errors
+		collect_error: _host & {
+			description: "Set to 1 when a collector fails to load its data, if `emit_errors` is enabled."
+			type:        "gauge"
+			tags:        _host_metrics_tags & {
+				collector: examples: ["cpu", "disk"]
+				error: {
+					description: "The first 64 characters of the error message."
+					required:    true
+					examples: ["No such file or directory (os error 2)"]
+				}
+			}
+		}
+
+		// Host cgroup
+		cgroup_memory_usage_bytes: _host & _cgroup_gauge & {description: "The number of bytes of memory used by the cgroup."}
+		cgroup_memory_limit_bytes: _host & _cgroup_gauge & {description: "The memory limit of the cgroup, in bytes. Not emitted when the cgroup is unlimited."}
+		cgroup_cpu_periods_total: _host & _cgroup_counter & {description: "The number of CPU bandwidth enforcement periods that have elapsed."}
+		cgroup_cpu_throttled_periods_total: _host & _cgroup_counter & {description: "The number of periods in which the cgroup was throttled."}
+		cgroup_cpu_throttled_seconds_total: _host & _cgroup_counter & {description: "The total time the cgroup was throttled for, in seconds."}
+		cgroup_io_read_bytes_total: _host & _cgroup_io & {description: "The number of bytes the cgroup read from the block device."}
+		cgroup_io_write_bytes_total: _host & _cgroup_io & {description: "The number of bytes the cgroup wrote to the block device."}
+		cgroup_io_read_ops_total: _host & _cgroup_io & {description: "The number of read operations the cgroup issued to the block device."}
+		cgroup_io_write_ops_total: _host & _cgroup_io & {description: "The number of write operations the cgroup issued to the block device."}
+
+		// Processes
+		process_cpu_seconds_total: _host & _process_counter & {description: "The CPU time the process spent in user and kernel mode."}
+		process_threads:           _host & _process_gauge & {description: "The number of threads of the process."}
+		process_open_fds:          _host & _process_gauge & {description: "The number of file descriptors the process has open. Only emitted for processes whose descriptors Vector is allowed to list."}
+		process_read_bytes_total:  _host & _process_counter & {description: "The number of bytes the process caused to be read from storage. Only emitted when `process.names.includes` is configured, for processes whose IO Vector is allowed to read."}
+		process_write_bytes_total: _host & _process_counter & {description: "The number of bytes the process caused to be written to storage. Only emitted when `process.names.includes` is configured, for processes whose IO Vector is allowed to read."}
+		process_cpu_seconds: _host & {
+			description:   "The distribution of the CPU time spent by the matched processes, with one sample per process. Emitted instead of the per-process metrics when `process.cpu_distribution` is enabled."
+			type:          "histogram"
+			relevant_when: "OS is Linux"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["process"]
+			}
+		}
+
+		// Software RAID
+		mdraid_disks_active: _host & _mdraid & {description: "The number of active disks of the array."}
+		mdraid_disks_total:  _host & _mdraid & {description: "The number of disks the array is configured with."}
+		mdraid_degraded:     _host & _mdraid & {description: "Whether the array is degraded (1), having fewer active disks than configured, or not (0)."}
+
+		// TCP listening sockets
+		tcp_listen_backlog: _host & _tcp_listen & {
+			description: "The number of connections waiting in the accept queues of the sockets listening on the port."
+			type:        "gauge"
+			tags:        _host_metrics_tags & {
+				port: {
+					description: "The listening port."
+					required:    true
+					examples: ["8080"]
+				}
+			}
+		}
+		tcp_listen_overflow_total: _host & _tcp_listen & {
+			description: "The number of times the accept queue of a listening socket overflowed. The kernel only tracks this across all sockets."
+			type:        "counter"
+		}
+
+		// Clock
+		clock_offset_seconds: _host & _clock & {description: "The offset between the system clock and the reference clock, as last reported to the kernel by the time synchronization daemon (e.g. NTP or chrony)."}
+		clock_sync_status:    _host & _clock & {description: "Whether the kernel considers the system clock synchronized (1) or not (0)."}
+
+		// TCP
+		tcp_rtt_seconds_min: _host & _tcp & {description: "The lowest smoothed round-trip time of the connections in the group."}
+		tcp_rtt_seconds_max: _host & _tcp & {description: "The highest smoothed round-trip time of the connections in the group."}
+		tcp_rtt_seconds_avg: _host & _tcp & {description: "The average smoothed round-trip time of the connections in the group."}
+
+		// Virtual machine
+		vm_steal_seconds_total: _host & _vm & {
+			description: "The number of seconds, summed across all CPUs, that the hypervisor spent running other guests while this one was runnable."
+			type:        "counter"
+		}
+		vm_balloon_current_bytes: _host & _vm & {
+			description: "The current size of the Xen memory balloon."
+			type:        "gauge"
+		}
+		vm_balloon_target_bytes: _host & _vm & {
+			description: "The target size of the Xen memory balloon."
+			type:        "gauge"
+		}
 
 		// Helpers
 		_host: {
@@ -427,23 +1909,86 @@ components: sources: host_metrics: {
 			required:    true
 			examples: ["sda", "sda1", "dm-1"]
 		}
-		_disk_counter: {
-			type: "counter"
+		_disk_counter: _disk & {type: "counter"}
+		_disk_gauge:   _disk & {type: "gauge"}
+		_disk: {
 			tags: _host_metrics_tags & {
 				collector: examples: ["disk"]
 				device: _disk_device
+				name: {
+					description: "The device-mapper name of the device, if `disk.resolve_dm_names` is enabled."
+					required:    false
+					examples: ["vg-lv"]
+				}
+				serial: {
+					description: "The serial number of the drive, if `disk.serial_tags` is enabled."
+					required:    false
+					examples: ["S4EWNX0N123456"]
+				}
+				mountpoint: {
+					description: "The path of the filesystem mounted from the device, if it is mounted on exactly one path. Linux only."
+					required:    false
+					examples: ["/", "/srv"]
+				}
+				class: {
+					description: "The class of the mount of the device, if `filesystem.classes` is configured. Linux only."
+					required:    false
+					examples: ["os", "data", "other"]
+				}
+			}
+		}
+		_block_device: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["block_device"]
+				device: _disk_device
+				name: {
+					description: "The device-mapper name of the device, if `disk.resolve_dm_names` is enabled."
+					required:    false
+					examples: ["vg-lv"]
+				}
+				serial: {
+					description: "The serial number of the drive, if `disk.serial_tags` is enabled."
+					required:    false
+					examples: ["S4EWNX0N123456"]
+				}
 			}
+			relevant_when: "OS is Linux"
 		}
 		_filesystem_bytes: {
 			type: "gauge"
 			tags: _host_metrics_tags & {
 				collector: examples: ["filesystem"]
-				device: _disk_device
+				device: {
+					description: "The device the filesystem is mounted from. Not set when `filesystem.paths` is configured."
+					required:    false
+					examples: ["/dev/sda1", "/dev/mapper/vg-data", "overlay"]
+				}
+				block_device: {
+					description: "The kernel name of the block device backing the filesystem, as in the `device` tag of the disk metrics, so both can be joined. Linux only."
+					required:    false
+					examples: ["sda1", "dm-1"]
+				}
 				filesystem: {
-					description: "The name of the filesystem type."
-					required:    true
+					description: "The name of the filesystem type. Not set when `filesystem.paths` is configured."
+					required:    false
 					examples: ["ext4", "ntfs"]
 				}
+				raw_fstype: {
+					description: "The filesystem type as reported by the system. Only set when `filesystem.normalize_types` is enabled."
+					required:    false
+					examples: ["fuseblk.ntfs-3g", "fuse.fuse-overlayfs"]
+				}
+				class: {
+					description: "The class of the mount, if `filesystem.classes` is configured."
+					required:    false
+					examples: ["os", "data", "other"]
+				}
+				path: {
+					description: "The configured path the usage was reported for. Only set when `filesystem.paths` is configured."
+					required:    false
+					examples: ["/var/lib/postgresql"]
+				}
 			}
 		}
 		_loadavg: {
@@ -453,6 +1998,18 @@ components: sources: host_metrics: {
 			}
 			relevant_when: "OS is not Windows"
 		}
+		_cpu_percentile: {
+			type:          "gauge"
+			relevant_when: "`cpu.percentiles` is enabled"
+			tags:          _host_metrics_tags & {
+				collector: examples: ["cpu"]
+				mode: {
+					description: "Which mode the CPUs were running in during the given time."
+					required:    true
+					examples: ["idle", "system", "user", "nice"]
+				}
+			}
+		}
 		_host_metric: {
 			type: "gauge"
 			tags: _host_metrics_tags & {
@@ -474,18 +2031,242 @@ components: sources: host_metrics: {
 		_memory_linux: _memory_gauge & {relevant_when: "OS is Linux"}
 		_memory_macos: _memory_gauge & {relevant_when: "OS is macOS X"}
 		_memory_nowin: {relevant_when: "OS is not Windows"}
+		_swap_device: _memory_linux & {
+			tags: {
+				device: {
+					description: "The path of the swap partition or file, as listed in `/proc/swaps`."
+					required:    true
+					examples: ["/dev/sda2", "/swapfile"]
+				}
+				type: {
+					description: "Whether the swap space is a `partition` or a `file`."
+					required:    true
+					examples: ["partition", "file"]
+				}
+			}
+		}
+		_cgroup: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["cgroup"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_cgroup_counter: _cgroup & {type: "counter"}
+		_cgroup_gauge:   _cgroup & {type: "gauge"}
+		_cgroup_io: _cgroup_counter & {
+			tags: device: {
+				description: "The name of the block device, or its `major:minor` number if it can't be resolved. Read from `io.stat`, or from the `blkio` controller on the legacy hierarchy."
+				required:    true
+				examples: ["sda", "nvme0n1", "259:3"]
+			}
+		}
+		_process: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["process"]
+				name: {
+					description: "The name of the process."
+					required:    true
+					examples: ["postgres"]
+				}
+				pid: {
+					description: "The ID of the process."
+					required:    true
+					examples: ["4242"]
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
+		_process_counter: _process & {type: "counter"}
+		_process_gauge:   _process & {type: "gauge"}
+		_mdraid: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["mdraid"]
+				device: {
+					description: "The name of the array."
+					required:    true
+					examples: ["md0"]
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
+		_vector_process: {
+			tags: host: _host_metrics_tags.host
+			relevant_when: "OS is Linux and `self_metrics` is enabled"
+		}
+		_container: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["container"]
+				container_id: {
+					description: "The ID of the container."
+					required:    true
+					examples: ["8dfafdbc3a40"]
+				}
+				name: {
+					description: "The name of the container."
+					required:    true
+					examples: ["web"]
+				}
+				image: {
+					description: "The image of the container."
+					required:    true
+					examples: ["nginx:1.21"]
+				}
+			}
+		}
+		_kernel_messages: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["kernel_messages"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_schedstat: {
+			type: "counter"
+			tags: _host_metrics_tags & {
+				collector: examples: ["schedstat"]
+				cpu: {
+					description: "The index of the CPU core or socket."
+					required:    true
+					examples: ["1"]
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
+		_limits: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["limits"]
+			}
+			relevant_when: "OS is UNIX"
+		}
+		_thermal_throttle: {
+			type: "counter"
+			tags: _host_metrics_tags & {
+				collector: examples: ["thermal_throttle"]
+				cpu: {
+					description: "The index of the CPU core or socket."
+					required:    true
+					examples: ["1"]
+				}
+				scope: {
+					description: "Whether the CPU core or its whole package was throttled. Every CPU of a package reports the same package count."
+					required:    true
+					enum: {
+						core:    "The CPU core."
+						package: "The package (socket) of the CPU."
+					}
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
+		_updates: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["updates"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_zfs: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["zfs"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_zfs_pool: _zfs & {
+			type: "gauge"
+			tags: pool: {
+				description: "The name of the pool."
+				required:    true
+				examples: ["tank"]
+			}
+		}
+		_tcp_listen: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["tcp_listen"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_clock: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["clock"]
+			}
+			relevant_when: "OS is Linux"
+		}
+		_tcp: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["tcp"]
+				state: {
+					description: "The TCP state of the connections, if `tcp.group_by` is `state`."
+					required:    false
+					examples: ["established", "close_wait"]
+				}
+				subnet: {
+					description: "The subnet of the remote addresses, if `tcp.group_by` is `subnet`."
+					required:    false
+					examples: ["10.0.0.0/24"]
+				}
+			}
+			relevant_when: "OS is Linux"
+		}
+		_vm: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["vm"]
+			}
+			relevant_when: "OS is Linux"
+		}
 		_network_gauge: {
 			type: "gauge"
 			tags: _host_metrics_tags & {
 				collector: examples: ["network"]
+				device: {
+					description: "The network interface device name, or its alias if `network.alias_as_device` is enabled."
+					required:    true
+					examples: ["eth0", "enp5s3"]
+				}
+				alias: {
+					description: "The alias of the network interface configured in `network.aliases`. Not set for interfaces without an alias."
+					required:    false
+					examples: ["uplink", "storage"]
+				}
+			}
+		}
+		_network_linux: _network_gauge & {relevant_when: "OS is Linux"}
+		_network_family: tags: family: {
+			description: "The protocol family counted, if `network.split_family` is enabled. The interface counters of all protocols are tagged `all`, and the IPv6 counters the kernel keeps per interface `ipv6` (Linux only)."
+			required:    false
+			examples: ["all", "ipv6"]
+		}
+		_qdisc: {
+			tags: _host_metrics_tags & {
+				collector: examples: ["net_dev_queue"]
 				device: {
 					description: "The network interface device name."
 					required:    true
 					examples: ["eth0", "enp5s3"]
 				}
+				qdisc: {
+					description: "The kind of the queueing discipline."
+					required:    true
+					examples: ["fq_codel", "pfifo_fast"]
+				}
+				handle: {
+					description: "The handle of the queueing discipline, as shown by `tc qdisc`."
+					required:    true
+					examples: ["8001:", "0:"]
+				}
 			}
+			relevant_when: "OS is Linux"
 		}
 		_network_nomac: _network_gauge & {relevant_when: "OS is not macOS"}
+		_tcp_mem: {
+			type: "gauge"
+			tags: _host_metrics_tags & {
+				collector: examples: ["network"]
+			}
+			relevant_when: "OS is Linux"
+		}
 	}
 
 	telemetry: metrics: {